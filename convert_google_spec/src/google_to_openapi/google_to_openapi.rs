@@ -42,11 +42,13 @@ pub fn from_spec_to_openapi_info(google_spec: &Spec) -> openapi::Info {
 }
 
 
-pub fn methods_to_operations(methods: &GoogleMethods) -> openapi::Operations {
+// Methods come paired with the name of the resource they belong to, which is used as tag.
+pub fn methods_to_operations(methods: &[(String, Method)]) -> openapi::Operations {
     let mut base_struct = openapi::Operations { ..Default::default() };
 
-    for method in &methods.0 {
-        let operation: openapi::Operation = method_to_operation(&method); //::from(method);
+    for &(ref resource_name, ref method) in methods {
+        let mut operation: openapi::Operation = method_to_operation(&method); //::from(method);
+        operation.tags = Some(vec![resource_name.to_string()]);
 
         //TODO write the rest, find a better way of doing this
         if method.http_method == "GET" {
@@ -18,19 +18,31 @@ pub fn google_spec_to_openapi(spec: &Spec) -> openapi::Spec {
     // resources is a BTreeMap
     // TODO Remove clones
     let spec_paths = google_spec.resources.0
-        .values()
-        .flat_map(|resource| resource.methods.values())
-        // Fold into a  BTreeMap<String, Vec<Method>>
-        .fold(BTreeMap::new(), |mut acc, method|
+        .iter()
+        .flat_map(|(resource_name, resource)| resource.methods.values().map(move |method| (resource_name, method)))
+        // Fold into a  BTreeMap<String, Vec<(resource name, Method)>>
+        .fold(BTreeMap::new(), |mut acc, (resource_name, method)|
         {
             let path = format!("/{}", &method.path);
-            acc.entry(path).or_insert(vec![]).push(method.clone());
+            acc.entry(path).or_insert(vec![]).push((resource_name.to_string(), method.clone()));
             acc
         })
         //TODO: map on the hash and add method_path properly
-        .iter().map(|(method_path, methods)| (method_path.to_string(), google_to_openapi::methods_to_operations(&GoogleMethods(methods.clone()))))
+        .iter().map(|(method_path, methods)| (method_path.to_string(), google_to_openapi::methods_to_operations(methods)))
         .collect::<BTreeMap<_, _>>();
 
+    // Each resource becomes a tag so grouping survives a round trip.
+    let tags = google_spec.resources.0
+        .iter()
+        .map(|(resource_name, resource)| {
+            openapi::Tag {
+                name: resource_name.to_string(),
+                description: resource.description.clone(),
+                ..Default::default()
+            }
+        })
+        .collect::<Vec<_>>();
+
     openapi::Spec {
         swagger: "2.0".to_string(),
         schemes: Some(vec!["https".to_string()]),
@@ -45,7 +57,7 @@ pub fn google_spec_to_openapi(spec: &Spec) -> openapi::Spec {
         parameters: None,
         responses: None,
         security_definitions: None,
-        tags: None,
+        tags: if tags.is_empty() { None } else { Some(tags) },
     }
 
 }
@@ -1,6 +1,9 @@
 extern crate openapi;
 extern crate inflector;
 extern crate google_discovery_spec;
+extern crate openapi_extensions;
+#[cfg(test)]
+extern crate serde_json;
 
 pub mod google_to_openapi;
 pub mod openapi_to_google;
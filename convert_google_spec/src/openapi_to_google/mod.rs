@@ -2,8 +2,16 @@ use openapi;
 use std::collections::BTreeMap;
 use inflector::Inflector;
 use google_discovery_spec::schema::*;
+use openapi_extensions::{self, Document, Extensions};
 
-pub fn openapi_spec_to_google(spec: openapi::Spec) -> Spec {
+#[derive(Default)]
+pub struct ConversionOptions {
+    // Group Google resources by the first tag of each operation instead of
+    // by the prefix of its operationId.
+    pub group_by_tags: bool,
+}
+
+pub fn openapi_spec_to_google(spec: openapi::Spec, options: &ConversionOptions) -> Spec {
     let title = spec.info.title.unwrap();
     let name = title.to_lowercase();
     let version = spec.info.version.unwrap();
@@ -18,12 +26,113 @@ pub fn openapi_spec_to_google(spec: openapi::Spec) -> Spec {
         protocol: "rest".to_string(),
         base_path: spec.base_path.unwrap(),
         schemas: openapi_definitions_to_google_schemas(spec.definitions.unwrap()),
-        resources: openapi_paths_to_google_resources(spec.paths, &spec.parameters.unwrap()),
+        resources: openapi_paths_to_google_resources(
+            spec.paths,
+            &spec.parameters.unwrap(),
+            &tag_descriptions(&spec.tags),
+            options,
+        ),
         //aliases: None, //from_openapi_to_google::openapi_parameters_to_aliases(&spec.parameters),
     }
 
 }
 
+// Discovery documents accept custom fields, the extensions of the spec, its
+// operations, parameters and definitions are carried over to the matching
+// Google elements. Returns them located in the serialized Google spec.
+pub fn google_extensions(document: &Document, options: &ConversionOptions) -> Extensions {
+    let mut extensions = Extensions::default();
+    let mut carry = |from: Option<&openapi_extensions::ExtensionMap>, to: &str| {
+        for (name, value) in from.into_iter().flat_map(|from| from.iter()) {
+            extensions.insert(to, name, value.clone());
+        }
+    };
+
+    carry(document.extensions.spec(), "");
+    carry(document.extensions.info(), "");
+
+    for (name, definition) in document.spec.definitions.iter().flat_map(|definitions| definitions.iter()) {
+        carry(document.extensions.definition(name), &openapi_extensions::pointer(&["schemas", name]));
+        for property in definition.properties.iter().flat_map(|properties| properties.keys()) {
+            carry(
+                document.extensions.at(&openapi_extensions::pointer(&["definitions", name, "properties", property])),
+                &openapi_extensions::pointer(&["schemas", name, "properties", property]),
+            );
+        }
+    }
+
+    for (path, operations) in &document.spec.paths {
+        let verb_operations = vec![
+            ("get", &operations.get),
+            ("post", &operations.post),
+            ("put", &operations.put),
+            ("patch", &operations.patch),
+            ("delete", &operations.delete),
+            ("head", &operations.head),
+        ];
+        for (verb, operation) in verb_operations {
+            let operation = match *operation {
+                Some(ref operation) => operation,
+                None => continue,
+            };
+            let method = [
+                "resources",
+                &operation_to_resource_name(operation, options),
+                "methods",
+                &operation_to_operation_name(operation),
+            ];
+            carry(document.extensions.operation(path, verb), &openapi_extensions::pointer(&method));
+
+            let parameters = operation.parameters.iter().flat_map(|parameters| parameters.iter());
+            for (index, parameter) in parameters.enumerate() {
+                if let openapi::ParameterOrRef::Parameter { ref name, .. } = *parameter {
+                    let mut location = method.to_vec();
+                    location.extend(&["parameters", name.as_str()]);
+                    carry(document.extensions.parameter(path, verb, index), &openapi_extensions::pointer(&location));
+                }
+            }
+        }
+    }
+
+    extensions
+}
+
+
+// Operations that end as the same method of a resource overwrite each other,
+// with tag grouping pets.list and owners.list tagged store both become store.list.
+// Returns a message for each method with more than one operation.
+pub fn method_collisions(spec: &openapi::Spec, options: &ConversionOptions) -> Vec<String> {
+    let mut methods: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+    for operations in spec.paths.values() {
+        let verb_operations = vec![
+            &operations.get,
+            &operations.post,
+            &operations.put,
+            &operations.patch,
+            &operations.delete,
+            &operations.head,
+        ];
+        for operation in verb_operations.into_iter().filter_map(|operation| operation.as_ref()) {
+            let operation_id = match operation.operation_id {
+                Some(ref operation_id) => operation_id,
+                None => continue,
+            };
+            methods
+                .entry((operation_to_resource_name(operation, options), operation_to_operation_name(operation)))
+                .or_insert_with(Vec::new)
+                .push(operation_id.to_string());
+        }
+    }
+
+    methods
+        .into_iter()
+        .filter(|&(_, ref operation_ids)| operation_ids.len() > 1)
+        .map(|((resource, method), operation_ids)| {
+            format!("Operations {} all become the method {}.{}", operation_ids.join(", "), resource, method)
+        })
+        .collect()
+}
+
 
 fn openapi_definitions_to_google_schemas(
     definitions: BTreeMap<String, openapi::Schema>,
@@ -53,10 +162,20 @@ fn openapi_definitions_to_google_schemas(
     GoogleSchemas(schemas)
 }
 
+// tag name => tag description
+fn tag_descriptions(tags: &Option<Vec<openapi::Tag>>) -> BTreeMap<String, Option<String>> {
+    tags.iter()
+        .flat_map(|tags| tags.iter())
+        .map(|tag| (tag.name.clone(), tag.description.clone()))
+        .collect()
+}
+
 // TODO:  Not need to pass parameters all over the place
 fn openapi_paths_to_google_resources(
     paths: BTreeMap<String, openapi::Operations>,
     parameters: &BTreeMap<String, openapi::Parameter>,
+    tags: &BTreeMap<String, Option<String>>,
+    options: &ConversionOptions,
 ) -> GoogleResources {
 
     // resource (user)=> [path /user/list, verb GET, operation]
@@ -82,7 +201,7 @@ fn openapi_paths_to_google_resources(
             if operation.is_some() {
                 let oper = operation.unwrap();
                 let mut path_group = resources
-                    .entry(operation_to_operation_group(&oper))
+                    .entry(operation_to_resource_name(&oper, options))
                     .or_insert_with(Vec::new);
                 path_group.push((path_name.clone(), verb_name, oper));
             }
@@ -93,9 +212,10 @@ fn openapi_paths_to_google_resources(
         resources
             .into_iter()
             .map(|(resource_name, path_operation_hash)| {
+                let description = tags.get(&resource_name).and_then(|d| d.clone());
                 (
                     resource_name,
-                    to_google_resource(path_operation_hash, parameters),
+                    to_google_resource(path_operation_hash, description, parameters),
                 )
             })
             .collect(),
@@ -104,6 +224,7 @@ fn openapi_paths_to_google_resources(
 
 fn to_google_resource(
     path_operation_hash: Vec<(String, &str, openapi::Operation)>,
+    description: Option<String>,
     parameters: &BTreeMap<String, openapi::Parameter>,
 ) -> Resource {
     //           println!("{:?}", std::time::SystemTime::now());
@@ -123,7 +244,10 @@ fn to_google_resource(
         })
         .collect();
     //           println!("{:?}", std::time::SystemTime::now());
-    Resource { methods: methods }
+    Resource {
+        description: description,
+        methods: methods,
+    }
 }
 
 fn operation_to_operation_name(operation: &openapi::Operation) -> String {
@@ -134,6 +258,16 @@ fn operation_to_operation_name(operation: &openapi::Operation) -> String {
     operation_name.to_string()
 }
 
+// With tag grouping the first tag names the resource, operations without tags
+// fall back to the operationId prefix.
+fn operation_to_resource_name(operation: &openapi::Operation, options: &ConversionOptions) -> String {
+    let first_tag = operation.tags.as_ref().and_then(|tags| tags.first());
+    match first_tag {
+        Some(tag) if options.group_by_tags => tag.to_string(),
+        _ => operation_to_operation_group(operation),
+    }
+}
+
 fn operation_to_operation_group(operation: &openapi::Operation) -> String {
     let operation_id = operation.operation_id.as_ref().expect(
         "An operation ID is lacking.",
@@ -388,3 +522,67 @@ fn openapi_schema_to_google_schema(
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use google_to_openapi::google_spec_to_openapi;
+    use serde_json;
+
+    fn spec(paths: &str) -> openapi::Spec {
+        serde_json::from_str(&format!(r#"{{
+            "swagger": "2.0",
+            "info": {{"title": "Shop", "version": "v1", "description": "A shop."}},
+            "basePath": "/v1",
+            "definitions": {{"Pet": {{"type": "object", "properties": {{"name": {{"type": "string"}}}}}}}},
+            "parameters": {{}},
+            "tags": [{{"name": "store", "description": "Everything on sale."}}],
+            "paths": {}
+        }}"#, paths)).unwrap()
+    }
+
+    fn list(operation_id: &str, tag: &str) -> String {
+        format!(r##"{{
+            "operationId": "{}",
+            "tags": ["{}"],
+            "responses": {{"200": {{"description": "ok", "schema": {{"$ref": "#/definitions/Pet"}}}}}}
+        }}"##, operation_id, tag)
+    }
+
+    #[test]
+    fn finds_operations_ending_as_the_same_method() {
+        let spec = spec(&format!(
+            r#"{{"/pets": {{"get": {}}}, "/owners": {{"get": {}}}}}"#,
+            list("pets.list", "store"),
+            list("owners.list", "store")
+        ));
+        let by_tags = ConversionOptions { group_by_tags: true };
+
+        assert_eq!(
+            method_collisions(&spec, &by_tags),
+            vec!["Operations owners.list, pets.list all become the method store.list"]
+        );
+        assert!(method_collisions(&spec, &ConversionOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn tags_round_trip_as_resources() {
+        let spec = spec(&format!(
+            r#"{{"/pets": {{"get": {}}}, "/owners": {{"get": {}}}}}"#,
+            list("pets.list", "store"),
+            list("owners.list", "people")
+        ));
+        let google = openapi_spec_to_google(spec, &ConversionOptions { group_by_tags: true });
+
+        assert_eq!(google.resources.0.keys().collect::<Vec<_>>(), vec!["people", "store"]);
+        assert_eq!(google.resources.0["store"].description, Some("Everything on sale.".to_string()));
+        assert_eq!(google.resources.0["people"].description, None);
+        assert_eq!(google.resources.0["store"].methods["list"].id, "pets.list");
+
+        let tags = google_spec_to_openapi(&google).tags.unwrap();
+        assert_eq!(
+            tags.iter().map(|tag| (tag.name.as_str(), tag.description.clone())).collect::<Vec<_>>(),
+            vec![("people", None), ("store", Some("Everything on sale.".to_string()))]
+        );
+    }
+}
@@ -115,6 +115,8 @@ pub struct Property {
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct Resource {
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub description: Option<String>,
     pub methods: BTreeMap<String, Method>,
 }
 
@@ -0,0 +1,744 @@
+extern crate regex;
+extern crate serde_json;
+
+// Supported syntax:
+// $                    root
+// .name ['name']       child, several names can be given with ['a','b']
+// [0] [-1]             array index, several can be given with [0,1]
+// [1:3] [-2:] [::2]    array slice, [start:end:step] with any of them left out
+// .* [*]               every child
+// ..name ..*           recursive descent
+// [?(@.name)]          children where name exists
+// [?(@.name == 'x')]   children comparing a value with ==, !=, <, <=, > or >=
+// [?(@.name =~ '^x')]  children whose value matches a regular expression
+// [?(@property == 'x')] children by their own name (or index), with any operator
+
+use regex::Regex;
+use serde_json::Value;
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, String>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Key {
+    Field(String),
+    Index(usize),
+}
+
+// Where a selected node is in the document, from the root.
+pub type Location = Vec<Key>;
+
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Child(Selector),
+    Descendant(Selector),
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Names(Vec<String>),
+    Indexes(Vec<i64>),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Wildcard,
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone)]
+struct Filter {
+    subject: Subject,
+    comparison: Option<Comparison>,
+}
+
+#[derive(Debug, Clone)]
+enum Comparison {
+    Compare(Operator, Value),
+    // =~, the expression is compiled once when parsing.
+    Matches(Regex),
+}
+
+#[derive(Debug, Clone)]
+enum Subject {
+    // @.a.b, relative to the child
+    Path(Vec<Key>),
+    // @property, the name or index of the child
+    Property,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl JsonPath {
+    pub fn parse(source: &str) -> Result<JsonPath> {
+        let segments = Parser::new(source).parse()?;
+        Ok(JsonPath {
+            source: source.to_string(),
+            segments: segments,
+        })
+    }
+
+    // Nodes matching the path, in document order.
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<(Location, &'a Value)> {
+        let mut current = vec![(vec![], root)];
+        for segment in &self.segments {
+            let mut next = vec![];
+            for (location, value) in current {
+                match *segment {
+                    Segment::Child(ref selector) => apply(selector, location, value, &mut next),
+                    Segment::Descendant(ref selector) => {
+                        for (location, value) in descendants(location, value) {
+                            apply(selector, location, value, &mut next);
+                        }
+                    }
+                }
+            }
+            current = next;
+        }
+        current
+    }
+
+    pub fn locate(&self, root: &Value) -> Vec<Location> {
+        self.select(root).into_iter().map(|(location, _)| location).collect()
+    }
+}
+
+pub fn get<'a>(root: &'a Value, location: &[Key]) -> Option<&'a Value> {
+    location.iter().fold(Some(root), |value, key| {
+        value.and_then(|value| match *key {
+            Key::Field(ref name) => value.as_object().and_then(|map| map.get(name)),
+            Key::Index(index) => value.as_array().and_then(|array| array.get(index)),
+        })
+    })
+}
+
+pub fn get_mut<'a>(root: &'a mut Value, location: &[Key]) -> Option<&'a mut Value> {
+    let mut current = root;
+    for key in location {
+        current = match *key {
+            Key::Field(ref name) => current.as_object_mut().and_then(|map| map.get_mut(name))?,
+            Key::Index(index) => current.as_array_mut().and_then(|array| array.get_mut(index))?,
+        };
+    }
+    Some(current)
+}
+
+// Removes the node from its parent. Removing several array elements must be
+// done from the last to the first one or the indexes will not match anymore.
+pub fn remove(root: &mut Value, location: &[Key]) -> Option<Value> {
+    let (last, parent) = location.split_last()?;
+    let parent = get_mut(root, parent)?;
+    match *last {
+        Key::Field(ref name) => parent.as_object_mut().and_then(|map| map.remove(name)),
+        Key::Index(index) => parent.as_array_mut().and_then(|array| {
+            if index < array.len() { Some(array.remove(index)) } else { None }
+        }),
+    }
+}
+
+// Normalized path, $['paths']['/users']['get']
+pub fn to_string(location: &[Key]) -> String {
+    location.iter().fold("$".to_string(), |acc, key| match *key {
+        Key::Field(ref name) => format!("{}['{}']", acc, name.replace('\'', "\\'")),
+        Key::Index(index) => format!("{}[{}]", acc, index),
+    })
+}
+
+// JSON pointer, /paths/~1users/get
+pub fn to_pointer(location: &[Key]) -> String {
+    location
+        .iter()
+        .map(|key| match *key {
+            Key::Field(ref name) => format!("/{}", name.replace('~', "~0").replace('/', "~1")),
+            Key::Index(index) => format!("/{}", index),
+        })
+        .collect()
+}
+
+
+fn with_key(location: &[Key], key: Key) -> Location {
+    let mut location = location.to_vec();
+    location.push(key);
+    location
+}
+
+fn children<'a>(location: &[Key], value: &'a Value) -> Vec<(Location, &'a Value)> {
+    match *value {
+        Value::Object(ref map) => map.iter()
+            .map(|(name, child)| (with_key(location, Key::Field(name.to_string())), child))
+            .collect(),
+        Value::Array(ref array) => array.iter()
+            .enumerate()
+            .map(|(index, child)| (with_key(location, Key::Index(index)), child))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// The node itself and everything under it.
+fn descendants<'a>(location: Location, value: &'a Value) -> Vec<(Location, &'a Value)> {
+    let mut result = vec![];
+    for (child_location, child) in children(&location, value) {
+        result.append(&mut descendants(child_location, child));
+    }
+    result.insert(0, (location, value));
+    result
+}
+
+fn apply<'a>(selector: &Selector, location: Location, value: &'a Value, result: &mut Vec<(Location, &'a Value)>) {
+    match *selector {
+        Selector::Wildcard => result.append(&mut children(&location, value)),
+        Selector::Names(ref names) => {
+            if let Value::Object(ref map) = *value {
+                for name in names {
+                    if let Some(child) = map.get(name) {
+                        result.push((with_key(&location, Key::Field(name.to_string())), child));
+                    }
+                }
+            }
+        }
+        Selector::Indexes(ref indexes) => {
+            if let Value::Array(ref array) = *value {
+                for &index in indexes {
+                    let index = if index < 0 { array.len() as i64 + index } else { index };
+                    if index >= 0 && (index as usize) < array.len() {
+                        result.push((with_key(&location, Key::Index(index as usize)), &array[index as usize]));
+                    }
+                }
+            }
+        }
+        Selector::Slice(start, end, step) => {
+            if let Value::Array(ref array) = *value {
+                for index in slice(array.len() as i64, start, end, step.unwrap_or(1)) {
+                    result.push((with_key(&location, Key::Index(index)), &array[index]));
+                }
+            }
+        }
+        Selector::Filter(ref filter) => {
+            for (child_location, child) in children(&location, value) {
+                if filter.matches(child, child_location.last()) {
+                    result.push((child_location, child));
+                }
+            }
+        }
+    }
+}
+
+impl Filter {
+    fn matches(&self, value: &Value, key: Option<&Key>) -> bool {
+        let property;
+        let subject = match self.subject {
+            Subject::Path(ref path) => get(value, path),
+            Subject::Property => {
+                property = match key {
+                    Some(&Key::Field(ref name)) => Value::String(name.to_string()),
+                    Some(&Key::Index(index)) => Value::from(index),
+                    None => Value::Null,
+                };
+                Some(&property)
+            }
+        };
+        match (subject, &self.comparison) {
+            (None, _) => false,
+            (Some(_), &None) => true,
+            (Some(found), &Some(Comparison::Compare(operator, ref expected))) => compare(found, operator, expected),
+            (Some(found), &Some(Comparison::Matches(ref regex))) => found.as_str().map_or(false, |found| regex.is_match(found)),
+        }
+    }
+}
+
+// The indexes of [start:end:step] in an array of length items, as RFC 9535 does
+// them: negative bounds count from the end and a step of 0 selects nothing.
+fn slice(length: i64, start: Option<i64>, end: Option<i64>, step: i64) -> Vec<usize> {
+    let normalize = |index: i64| if index >= 0 { index } else { length + index };
+    let mut indexes = vec![];
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).max(0).min(length);
+        let upper = normalize(end.unwrap_or(length)).max(0).min(length);
+        let mut index = lower;
+        while index < upper {
+            indexes.push(index as usize);
+            index += step;
+        }
+    } else if step < 0 {
+        let upper = start.map_or(length - 1, normalize).max(-1).min(length - 1);
+        let lower = end.map_or(-1, normalize).max(-1).min(length - 1);
+        let mut index = upper;
+        while lower < index {
+            indexes.push(index as usize);
+            index += step;
+        }
+    }
+    indexes
+}
+
+fn compare(found: &Value, operator: Operator, expected: &Value) -> bool {
+    use std::cmp::Ordering;
+
+    let ordering = match (found, expected) {
+        (&Value::Number(ref a), &Value::Number(ref b)) => {
+            a.as_f64().and_then(|a| b.as_f64().and_then(|b| a.partial_cmp(&b)))
+        }
+        (&Value::String(ref a), &Value::String(ref b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    match operator {
+        Operator::Equal => found == expected,
+        Operator::NotEqual => found != expected,
+        Operator::Less => ordering == Some(Ordering::Less),
+        Operator::LessOrEqual => ordering.map_or(false, |o| o != Ordering::Greater),
+        Operator::Greater => ordering == Some(Ordering::Greater),
+        Operator::GreaterOrEqual => ordering.map_or(false, |o| o != Ordering::Less),
+    }
+}
+
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Parser {
+        Parser {
+            chars: source.trim().chars().collect(),
+            position: 0,
+        }
+    }
+
+    fn parse(&mut self) -> Result<Vec<Segment>> {
+        self.expect('$')?;
+        let mut segments = vec![];
+        while let Some(c) = self.peek() {
+            let segment = match c {
+                '.' if self.peek_at(1) == Some('.') => {
+                    self.position += 2;
+                    Segment::Descendant(self.dot_or_bracket()?)
+                }
+                '.' => {
+                    self.position += 1;
+                    Segment::Child(self.dot_selector()?)
+                }
+                '[' => Segment::Child(self.bracket_selector()?),
+                _ => return Err(self.error("expected '.' or '['")),
+            };
+            segments.push(segment);
+        }
+        Ok(segments)
+    }
+
+    fn dot_or_bracket(&mut self) -> Result<Selector> {
+        if self.peek() == Some('[') {
+            self.bracket_selector()
+        } else {
+            self.dot_selector()
+        }
+    }
+
+    fn dot_selector(&mut self) -> Result<Selector> {
+        if self.peek() == Some('*') {
+            self.position += 1;
+            return Ok(Selector::Wildcard);
+        }
+        let name = self.name();
+        if name.is_empty() {
+            return Err(self.error("expected a name"));
+        }
+        Ok(Selector::Names(vec![name]))
+    }
+
+    fn bracket_selector(&mut self) -> Result<Selector> {
+        self.expect('[')?;
+        self.skip_spaces();
+        let selector = match self.peek() {
+            Some('*') => {
+                self.position += 1;
+                Selector::Wildcard
+            }
+            Some('?') => {
+                self.position += 1;
+                self.expect('(')?;
+                let filter = self.filter()?;
+                self.skip_spaces();
+                self.expect(')')?;
+                Selector::Filter(filter)
+            }
+            Some('\'') | Some('"') => {
+                let mut names = vec![self.quoted()?];
+                while self.next_in_list() {
+                    names.push(self.quoted()?);
+                }
+                Selector::Names(names)
+            }
+            Some(':') => self.slice(None)?,
+            _ => {
+                let first = self.integer()?;
+                self.skip_spaces();
+                if self.peek() == Some(':') {
+                    self.slice(Some(first))?
+                } else {
+                    let mut indexes = vec![first];
+                    while self.next_in_list() {
+                        indexes.push(self.integer()?);
+                    }
+                    Selector::Indexes(indexes)
+                }
+            }
+        };
+        self.skip_spaces();
+        self.expect(']')?;
+        Ok(selector)
+    }
+
+    // :end:step after the start of a slice, both optional.
+    fn slice(&mut self, start: Option<i64>) -> Result<Selector> {
+        self.expect(':')?;
+        self.skip_spaces();
+        let end = self.optional_integer()?;
+        self.skip_spaces();
+        let step = if self.peek() == Some(':') {
+            self.position += 1;
+            self.skip_spaces();
+            self.optional_integer()?
+        } else {
+            None
+        };
+        Ok(Selector::Slice(start, end, step))
+    }
+
+    fn optional_integer(&mut self) -> Result<Option<i64>> {
+        match self.peek() {
+            Some(c) if c == '-' || c.is_ascii_digit() => Ok(Some(self.integer()?)),
+            _ => Ok(None),
+        }
+    }
+
+    // @.name.other['quoted'][0] or @property, followed optionally by an operator and a literal
+    fn filter(&mut self) -> Result<Filter> {
+        self.skip_spaces();
+        self.expect('@')?;
+        let mut path = vec![];
+        let property = "property".chars().enumerate().all(|(i, c)| self.peek_at(i) == Some(c));
+        if property {
+            self.position += "property".len();
+        }
+        if !property {
+            loop {
+                match self.peek() {
+                    Some('.') => {
+                        self.position += 1;
+                        let name = self.name();
+                        if name.is_empty() {
+                            return Err(self.error("expected a name"));
+                        }
+                        path.push(Key::Field(name));
+                    }
+                    Some('[') => {
+                        self.position += 1;
+                        self.skip_spaces();
+                        let key = match self.peek() {
+                            Some('\'') | Some('"') => Key::Field(self.quoted()?),
+                            _ => {
+                                let index = self.integer()?;
+                                if index < 0 {
+                                    return Err(self.error("negative indexes are not supported in filters"));
+                                }
+                                Key::Index(index as usize)
+                            }
+                        };
+                        self.skip_spaces();
+                        self.expect(']')?;
+                        path.push(key);
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        self.skip_spaces();
+        // None is =~, which compares with a regular expression instead of a literal.
+        let operator = match (self.peek(), self.peek_at(1)) {
+            (Some('='), Some('=')) => Some((Some(Operator::Equal), 2)),
+            (Some('='), Some('~')) => Some((None, 2)),
+            (Some('!'), Some('=')) => Some((Some(Operator::NotEqual), 2)),
+            (Some('<'), Some('=')) => Some((Some(Operator::LessOrEqual), 2)),
+            (Some('>'), Some('=')) => Some((Some(Operator::GreaterOrEqual), 2)),
+            (Some('<'), _) => Some((Some(Operator::Less), 1)),
+            (Some('>'), _) => Some((Some(Operator::Greater), 1)),
+            _ => None,
+        };
+        let comparison = match operator {
+            None => None,
+            Some((operator, length)) => {
+                self.position += length;
+                self.skip_spaces();
+                let literal = self.literal()?;
+                match operator {
+                    None => {
+                        let expression = literal.as_str().ok_or_else(|| self.error("=~ needs a quoted regular expression"))?;
+                        let regex = Regex::new(expression).map_err(|e| self.error(&format!("invalid regular expression: {}", e)))?;
+                        Some(Comparison::Matches(regex))
+                    }
+                    Some(operator) => Some(Comparison::Compare(operator, literal)),
+                }
+            }
+        };
+
+        Ok(Filter {
+            subject: if property { Subject::Property } else { Subject::Path(path) },
+            comparison: comparison,
+        })
+    }
+
+    fn literal(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('\'') | Some('"') => Ok(Value::String(self.quoted()?)),
+            _ => {
+                let start = self.position;
+                while self.peek().map_or(false, |c| c.is_alphanumeric() || c == '-' || c == '.' || c == '+') {
+                    self.position += 1;
+                }
+                let word: String = self.chars[start..self.position].iter().collect();
+                serde_json::from_str(&word).map_err(|_| self.error(&format!("invalid literal '{}'", word)))
+            }
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let start = self.position;
+        while self.peek().map_or(false, |c| !c.is_whitespace() && !".[]()=!<>,".contains(c)) {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let quote = self.peek().ok_or_else(|| self.error("expected a quote"))?;
+        self.position += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some('\\') => {
+                    self.position += 1;
+                    let escaped = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                    text.push(escaped);
+                }
+                Some(c) if c == quote => break,
+                Some(c) => text.push(c),
+            }
+            self.position += 1;
+        }
+        self.position += 1;
+        Ok(text)
+    }
+
+    fn integer(&mut self) -> Result<i64> {
+        let start = self.position;
+        if self.peek() == Some('-') {
+            self.position += 1;
+        }
+        while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        let number: String = self.chars[start..self.position].iter().collect();
+        number.parse().map_err(|_| self.error("expected an index"))
+    }
+
+    // Consumes the comma of a list like ['a', 'b'] if there is one.
+    fn next_in_list(&mut self) -> bool {
+        self.skip_spaces();
+        if self.peek() == Some(',') {
+            self.position += 1;
+            self.skip_spaces();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().map_or(false, |c| c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).cloned()
+    }
+
+    fn error(&self, message: &str) -> String {
+        let source: String = self.chars.iter().collect();
+        format!("Invalid JSONPath {}: {} at position {}", source, message, self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> Value {
+        serde_json::from_str(r#"{
+            "paths": {
+                "/pets": {"get": {"operationId": "list", "tags": ["pets"], "x-size": 3}},
+                "/pets/{id}": {"get": {"operationId": "get", "tags": ["pets"], "x-size": 1}, "delete": {"operationId": "remove", "tags": ["admin"]}},
+                "/o'clock": {"get": {"operationId": "time"}}
+            },
+            "list": [0, 1, 2, 3, 4, 5]
+        }"#).unwrap()
+    }
+
+    fn select(path: &str) -> Vec<String> {
+        let document = document();
+        JsonPath::parse(path).unwrap().locate(&document).iter().map(|location| to_string(location)).collect()
+    }
+
+    fn values(path: &str) -> Vec<Value> {
+        let document = document();
+        JsonPath::parse(path).unwrap().select(&document).into_iter().map(|(_, value)| value.clone()).collect()
+    }
+
+    #[test]
+    fn selects_children() {
+        assert_eq!(select("$"), vec!["$"]);
+        assert_eq!(select("$.paths['/pets'].get"), vec!["$['paths']['/pets']['get']"]);
+        assert_eq!(select("$.paths[\"/pets\"]['get'].operationId"), vec!["$['paths']['/pets']['get']['operationId']"]);
+        assert_eq!(select("$.paths['/pets', '/missing']"), vec!["$['paths']['/pets']"]);
+        assert!(select("$.missing.get").is_empty());
+    }
+
+    #[test]
+    fn selects_quoted_keys_with_escapes() {
+        assert_eq!(values("$.paths['/o\\'clock'].get.operationId"), vec![Value::from("time")]);
+        assert_eq!(select("$.paths['/o\\'clock']"), vec!["$['paths']['/o\\'clock']"]);
+    }
+
+    #[test]
+    fn selects_wildcards() {
+        assert_eq!(select("$.paths['/pets/{id}'].*").len(), 2);
+        assert_eq!(select("$.paths['/pets/{id}'][*]").len(), 2);
+        assert_eq!(values("$.list[*]").len(), 6);
+        assert!(select("$.list[0][*]").is_empty());
+    }
+
+    #[test]
+    fn selects_descendants() {
+        assert_eq!(values("$..operationId"), vec![Value::from("list"), Value::from("get"), Value::from("remove"), Value::from("time")]);
+        assert_eq!(values("$.paths..tags[0]"), vec![Value::from("pets"), Value::from("pets"), Value::from("admin")]);
+        // The node itself and everything under it.
+        assert_eq!(select("$.list..*").len(), 6);
+    }
+
+    #[test]
+    fn selects_indexes() {
+        assert_eq!(values("$.list[0]"), vec![Value::from(0)]);
+        assert_eq!(values("$.list[-1]"), vec![Value::from(5)]);
+        assert_eq!(values("$.list[4, 1]"), vec![Value::from(4), Value::from(1)]);
+        assert!(values("$.list[6]").is_empty());
+        assert!(values("$.list[-7]").is_empty());
+    }
+
+    #[test]
+    fn selects_slices() {
+        let numbers = |path: &str| values(path).iter().map(|value| value.as_i64().unwrap()).collect::<Vec<i64>>();
+        assert_eq!(numbers("$.list[1:3]"), vec![1, 2]);
+        assert_eq!(numbers("$.list[:2]"), vec![0, 1]);
+        assert_eq!(numbers("$.list[4:]"), vec![4, 5]);
+        assert_eq!(numbers("$.list[-2:]"), vec![4, 5]);
+        assert_eq!(numbers("$.list[::2]"), vec![0, 2, 4]);
+        assert_eq!(numbers("$.list[1:5:3]"), vec![1, 4]);
+        assert_eq!(numbers("$.list[::-1]"), vec![5, 4, 3, 2, 1, 0]);
+        assert_eq!(numbers("$.list[4:1:-2]"), vec![4, 2]);
+        assert_eq!(numbers("$.list[:]"), vec![0, 1, 2, 3, 4, 5]);
+        assert!(numbers("$.list[::0]").is_empty());
+        assert!(numbers("$.list[3:1]").is_empty());
+        assert!(numbers("$.list[10:20]").is_empty());
+    }
+
+    #[test]
+    fn selects_with_filters() {
+        assert_eq!(values("$.paths.*[?(@.operationId == 'get')].operationId"), vec![Value::from("get")]);
+        assert_eq!(values("$.paths.*[?(@.tags[0] != 'pets')].operationId"), vec![Value::from("remove")]);
+        assert_eq!(values("$.paths.*[?(@['x-size'] > 2)].operationId"), vec![Value::from("list")]);
+        assert_eq!(values("$.paths.*[?(@['x-size'] <= 1)].operationId"), vec![Value::from("get")]);
+        assert_eq!(values("$.paths.*[?(@.tags)].operationId").len(), 3);
+        assert_eq!(values("$.paths.*[?(@.operationId =~ '^(list|time)$')].operationId"), vec![Value::from("list"), Value::from("time")]);
+        assert_eq!(select("$.paths[?(@property =~ '^/pets')]"), vec!["$['paths']['/pets']", "$['paths']['/pets/{id}']"]);
+        assert_eq!(values("$.list[?(@property >= 4)]"), vec![Value::from(4), Value::from(5)]);
+        assert_eq!(values("$.list[?(@ == 3)]"), vec![Value::from(3)]);
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        for invalid in &[
+            "",
+            "paths",
+            "$.",
+            "$paths",
+            "$.paths[",
+            "$.paths['/pets'",
+            "$.paths['/pets]",
+            "$.list[a]",
+            "$.list[1:x]",
+            "$[?(@.a == )]",
+            "$[?(@.a == 'x']",
+            "$[?(@.a =~ 3)]",
+            "$[?(@.a =~ '(')]",
+            "$[?(@[-1])]",
+        ] {
+            let error = JsonPath::parse(invalid).err().unwrap_or_else(|| panic!("{} should not parse", invalid));
+            assert!(error.starts_with("Invalid JSONPath"), "{}", error);
+        }
+        assert_eq!(JsonPath::parse("$.a b").unwrap_err(), "Invalid JSONPath $.a b: expected '.' or '[' at position 3");
+    }
+
+    #[test]
+    fn converts_locations() {
+        let location = vec![Key::Field("paths".to_string()), Key::Field("/pets/{id}".to_string()), Key::Index(0)];
+        assert_eq!(to_string(&location), "$['paths']['/pets/{id}'][0]");
+        assert_eq!(to_pointer(&location), "/paths/~1pets~1{id}/0");
+        assert_eq!(to_pointer(&[Key::Field("a~b".to_string())]), "/a~0b");
+    }
+
+    #[test]
+    fn removes_nodes() {
+        let mut document = document();
+        assert_eq!(remove(&mut document, &[Key::Field("list".to_string()), Key::Index(1)]), Some(Value::from(1)));
+        assert_eq!(document["list"].as_array().unwrap().len(), 5);
+        assert_eq!(remove(&mut document, &[Key::Field("list".to_string()), Key::Index(9)]), None);
+        assert!(remove(&mut document, &[Key::Field("paths".to_string())]).is_some());
+        assert!(document.get("paths").is_none());
+        assert_eq!(remove(&mut document, &[]), None);
+    }
+}
@@ -0,0 +1,440 @@
+extern crate openapi_examples;
+extern crate openapi_extensions;
+extern crate regex;
+extern crate serde_json;
+
+// Checks HTTP exchanges against a spec: finds the operation of a request by its
+// method and path, then checks its parameters and body, and the status and body
+// of its response. It works on the untyped document because the typed schemas
+// do not keep the bounds.
+
+use openapi_examples::schema::parameter_value;
+use openapi_extensions::VERBS;
+use regex::Regex;
+use serde_json::Value;
+use std::fmt;
+
+pub mod traffic;
+pub use openapi_examples::schema::check as check_value;
+
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    pub method: String,
+    // Without the query, as it arrives: /v1/pets/1
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    // JSON, or an object of strings for form bodies.
+    pub body: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    // None when the body was not recorded or is empty.
+    pub body: Option<Value>,
+}
+
+// A request with the response it got, as recorded.
+#[derive(Debug, Clone, Default)]
+pub struct Exchange {
+    pub request: Request,
+    pub response: Response,
+}
+
+// The operation a request goes to.
+#[derive(Debug)]
+pub struct Route<'a> {
+    // The path as in the spec: /pets/{pet_id}
+    pub path: &'a str,
+    pub verb: &'a str,
+    pub operation: &'a Value,
+    pub path_parameters: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteError {
+    UnknownPath,
+    // The verbs the path has.
+    UnknownVerb(Vec<String>),
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RouteError::UnknownPath => write!(f, "No path of the spec matches"),
+            RouteError::UnknownVerb(ref verbs) => {
+                let verbs: Vec<String> = verbs.iter().map(|verb| verb.to_uppercase()).collect();
+                write!(f, "The path only has {}", verbs.join(", "))
+            }
+        }
+    }
+}
+
+// The paths of a spec ready to route requests, their expressions are built once
+// for all the requests.
+pub struct Router<'a> {
+    base_path: &'a str,
+    // With more literal text first: /pets/mine before /pets/{pet_id}.
+    templates: Vec<Template<'a>>,
+}
+
+struct Template<'a> {
+    path: &'a str,
+    item: &'a Value,
+    expression: Regex,
+    names: Vec<String>,
+}
+
+impl<'a> Router<'a> {
+    pub fn new(document: &'a Value) -> Router<'a> {
+        let base_path = document.get("basePath").and_then(|base_path| base_path.as_str()).unwrap_or("");
+        let parameter = Regex::new(r"\{([^}]+)\}").unwrap();
+        let paths = document.get("paths").and_then(|paths| paths.as_object());
+        let mut templates: Vec<(usize, Template)> = paths
+            .into_iter()
+            .flat_map(|paths| paths.iter())
+            .filter_map(|(path, item)| {
+                let (expression, names) = expression(&parameter, path);
+                let literal_length = parameter.replace_all(path, "").len();
+                Regex::new(&expression).ok().map(|expression| {
+                    let template = Template {
+                        path: path,
+                        item: item,
+                        expression: expression,
+                        names: names,
+                    };
+                    (literal_length, template)
+                })
+            })
+            .collect();
+        templates.sort_by_key(|&(literal_length, _)| -(literal_length as i64));
+        Router {
+            base_path: base_path.trim_end_matches('/'),
+            templates: templates.into_iter().map(|(_, template)| template).collect(),
+        }
+    }
+
+    // The basePath is taken off first. When several paths match, the one with
+    // more literal text wins.
+    pub fn route(&self, method: &str, path: &str) -> Result<Route<'a>, RouteError> {
+        if !path.starts_with(self.base_path) {
+            return Err(RouteError::UnknownPath);
+        }
+        let path = match &path[self.base_path.len()..] {
+            "" => "/",
+            rest if rest.starts_with('/') => rest,
+            _ => return Err(RouteError::UnknownPath),
+        };
+
+        let candidates: Vec<(&Template<'a>, Vec<(String, String)>)> = self.templates
+            .iter()
+            .filter_map(|template| template.matches(path).map(|parameters| (template, parameters)))
+            .collect();
+
+        let method = method.to_lowercase();
+        let verb = VERBS.iter().find(|verb| **verb == method);
+        for &(template, ref parameters) in &candidates {
+            if let (Some(verb), Some(operation)) = (verb, verb.and_then(|verb| template.item.get(verb))) {
+                return Ok(Route {
+                    path: template.path,
+                    verb: verb,
+                    operation: operation,
+                    path_parameters: parameters.clone(),
+                });
+            }
+        }
+        match candidates.first() {
+            Some(&(template, _)) => {
+                let verbs = template.item.as_object().map_or(vec![], |item| {
+                    item.keys().filter(|key| VERBS.contains(&key.as_str())).cloned().collect()
+                });
+                Err(RouteError::UnknownVerb(verbs))
+            }
+            None => Err(RouteError::UnknownPath),
+        }
+    }
+}
+
+impl<'a> Template<'a> {
+    // The values of the path parameters when the path matches the template.
+    fn matches(&self, path: &str) -> Option<Vec<(String, String)>> {
+        let captures = self.expression.captures(path)?;
+        Some(self.names.iter().enumerate().map(|(index, name)| (name.clone(), decode(&captures[index + 1]))).collect())
+    }
+}
+
+// /pets/{pet_id} -> (^/pets/([^/]+)/?$, [pet_id])
+fn expression(parameter: &Regex, template: &str) -> (String, Vec<String>) {
+    let mut names = vec![];
+    let mut expression = "^".to_string();
+    let mut last = 0;
+    for captures in parameter.captures_iter(template) {
+        let whole = captures.get(0).unwrap();
+        expression.push_str(&regex::escape(&template[last..whole.start()]));
+        expression.push_str("([^/]+)");
+        names.push(captures[1].to_string());
+        last = whole.end();
+    }
+    expression.push_str(&regex::escape(&template[last..]));
+    expression.push_str("/?$");
+    (expression, names)
+}
+
+// Every problem of the request: missing required parameters, parameters and
+// bodies which do not match their schemas.
+pub fn check_request(document: &Value, route: &Route, request: &Request) -> Vec<String> {
+    let mut problems = vec![];
+    for (_, parameter) in openapi_examples::parameters(document, route.path, route.verb) {
+        let name = parameter.get("name").and_then(|name| name.as_str()).unwrap_or("");
+        let location = parameter.get("in").and_then(|location| location.as_str()).unwrap_or("");
+        let required = parameter.get("required").and_then(|required| required.as_bool()).unwrap_or(false);
+        let place = format!("{}.{}", location, name);
+
+        let texts: Vec<&str> = match location {
+            "path" => route.path_parameters.iter().filter(|&&(ref key, _)| key == name).map(|&(_, ref value)| value.as_str()).collect(),
+            "query" => request.query.iter().filter(|&&(ref key, _)| key == name).map(|&(_, ref value)| value.as_str()).collect(),
+            "header" => request.headers
+                .iter()
+                .filter(|&&(ref key, _)| key.eq_ignore_ascii_case(name))
+                .map(|&(_, ref value)| value.as_str())
+                .collect(),
+            "body" => {
+                match (request.body.as_ref(), parameter.get("schema")) {
+                    (None, _) if required => problems.push("The body is required".to_string()),
+                    (Some(body), Some(schema)) => problems.extend(check_value(document, schema, body, "body")),
+                    _ => {}
+                }
+                continue;
+            }
+            "formData" => {
+                let field = request.body.as_ref().and_then(|body| body.get(name));
+                match field {
+                    Some(&Value::String(ref text)) => vec![text.as_str()],
+                    Some(value) => {
+                        problems.extend(check_value(document, parameter, value, &place));
+                        continue;
+                    }
+                    None => vec![],
+                }
+            }
+            _ => continue,
+        };
+
+        if texts.is_empty() {
+            if required {
+                problems.push(format!("The {} parameter {} is required", location, name));
+            }
+            continue;
+        }
+        let value = parameter_value(parameter, &texts);
+        problems.extend(check_value(document, parameter, &value, &place));
+    }
+    problems
+}
+
+// Problems of the response of an operation: a status the spec does not list
+// or a body which does not match the schema of its status.
+pub fn check_response(document: &Value, route: &Route, response: &Response) -> Vec<String> {
+    let responses = route.operation.get("responses");
+    let status = response.status.to_string();
+    let declared = responses.and_then(|responses| responses.get(&status).or_else(|| responses.get("default")));
+    let declared = match declared {
+        Some(declared) => openapi_examples::resolve(document, declared),
+        None => return vec![format!("The status {} is not documented", status)],
+    };
+    match (declared.get("schema"), response.body.as_ref()) {
+        (Some(schema), Some(body)) => check_value(document, schema, body, "response"),
+        _ => vec![],
+    }
+}
+
+// Every problem of a recorded exchange, the route included.
+pub fn check_exchange(document: &Value, router: &Router, exchange: &Exchange) -> Vec<String> {
+    let route = match router.route(&exchange.request.method, &exchange.request.path) {
+        Ok(route) => route,
+        Err(error) => return vec![error.to_string()],
+    };
+    let mut problems = check_request(document, &route, &exchange.request);
+    problems.extend(check_response(document, &route, &exchange.response));
+    problems
+}
+
+// a=1&b=two%20words -> [(a, 1), (b, two words)]
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            let key = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("");
+            (decode(&key.replace('+', " ")), decode(&value.replace('+', " ")))
+        })
+        .collect()
+}
+
+// Percent decoding, invalid escapes are kept as they are.
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = if bytes[index] == b'%' && index + 2 < bytes.len() {
+            ::std::str::from_utf8(&bytes[index + 1..index + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> Value {
+        json(r##"{
+            "swagger": "2.0",
+            "basePath": "/v1/",
+            "paths": {
+                "/pets/{pet_id}": {
+                    "parameters": [{"name": "pet_id", "in": "path", "required": true, "type": "integer"}],
+                    "get": {"responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Pet"}}}},
+                    "delete": {"responses": {"204": {"description": "gone"}}}
+                },
+                "/pets/mine": {"get": {"responses": {"200": {"description": "ok"}}}},
+                "/pets": {
+                    "post": {
+                        "parameters": [
+                            {"name": "body", "in": "body", "required": true, "schema": {"$ref": "#/definitions/Pet"}},
+                            {"name": "tags", "in": "query", "type": "array", "items": {"type": "integer"}, "collectionFormat": "pipes"},
+                            {"name": "X-Trace", "in": "header", "required": true, "type": "string", "minLength": 3}
+                        ],
+                        "responses": {"201": {"description": "made"}, "default": {"description": "error", "schema": {"type": "object", "required": ["message"]}}}
+                    }
+                }
+            },
+            "definitions": {
+                "Pet": {"type": "object", "required": ["name"], "additionalProperties": false, "properties": {
+                    "name": {"type": "string", "pattern": "^[A-Z]"},
+                    "age": {"type": "integer", "minimum": 0, "exclusiveMinimum": true},
+                    "tags": {"type": "array", "maxItems": 2, "uniqueItems": true, "items": {"type": "string", "enum": ["a", "b"]}},
+                    "owner": {"$ref": "#/definitions/Missing"},
+                    "note": {"type": "string", "x-nullable": true}
+                }}
+            }
+        }"##)
+    }
+
+    fn request(method: &str, path: &str, query: &str, headers: &[(&str, &str)], body: Option<&str>) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: parse_query(query),
+            headers: headers.iter().map(|&(name, value)| (name.to_string(), value.to_string())).collect(),
+            body: body.map(json),
+        }
+    }
+
+    #[test]
+    fn routes_by_the_base_path_literal_text_and_verb() {
+        let document = document();
+        let router = Router::new(&document);
+        let route = router.route("GET", "/v1/pets/a%20b/").unwrap();
+        assert_eq!((route.path, route.verb), ("/pets/{pet_id}", "get"));
+        assert_eq!(route.path_parameters, vec![("pet_id".to_string(), "a b".to_string())]);
+        assert_eq!(router.route("get", "/v1/pets/mine").unwrap().path, "/pets/mine");
+        assert_eq!(router.route("DELETE", "/v1/pets/mine").unwrap().path, "/pets/{pet_id}");
+
+        let error = |method, path| router.route(method, path).unwrap_err();
+        assert_eq!(error("PUT", "/v1/pets/1"), RouteError::UnknownVerb(vec!["get".to_string(), "delete".to_string()]));
+        assert_eq!(error("PUT", "/v1/pets/1").to_string(), "The path only has GET, DELETE");
+        assert_eq!(error("GET", "/pets/1"), RouteError::UnknownPath);
+        assert_eq!(error("GET", "/v1pets/1"), RouteError::UnknownPath);
+        assert_eq!(error("GET", "/v1/pets/1/toys"), RouteError::UnknownPath);
+    }
+
+    #[test]
+    fn checks_parameters_and_bodies() {
+        let document = document();
+        let router = Router::new(&document);
+        let check = |request: Request| {
+            let route = router.route(&request.method, &request.path).unwrap();
+            check_request(&document, &route, &request)
+        };
+        assert!(check(request("POST", "/v1/pets", "tags=1%7C2", &[("x-trace", "abc")], Some(r#"{"name": "Rex"}"#))).is_empty());
+        assert_eq!(check(request("POST", "/v1/pets", "tags=1|two", &[("X-Trace", "ab")], None)), vec![
+            "The body is required",
+            "query.tags[1]: expected integer but it was the string \"two\"",
+            "header.X-Trace: \"ab\" is shorter than 3",
+        ]);
+        assert_eq!(check(request("POST", "/v1/pets", "", &[], Some("{}"))), vec![
+            "body: the property name is required",
+            "The header parameter X-Trace is required",
+        ]);
+        assert_eq!(check(request("GET", "/v1/pets/one", "", &[], None)), vec!["path.pet_id: expected integer but it was the string \"one\""]);
+    }
+
+    #[test]
+    fn checks_values_against_schemas() {
+        let document = document();
+        let pet = json(r##"{"$ref": "#/definitions/Pet"}"##);
+        let check = |value: &str| check_value(&document, &pet, &json(value), "body");
+        assert!(check(r#"{"name": "Rex", "age": 1, "tags": ["a"], "note": null}"#).is_empty());
+        assert_eq!(check(r#"{"name": "rex", "age": 0, "tags": ["a", "a", "c"], "color": 1, "owner": {}}"#), vec![
+            "body.name: \"rex\" does not match ^[A-Z]",
+            "body.age: 0 is less than the minimum 0",
+            "body.tags: expected at most 2 items but there are 3",
+            "body.tags: the items are not unique",
+            "body.tags[2]: \"c\" is not one of [\"a\",\"b\"]",
+            "body: the property color is not allowed",
+            "body.owner: the schema #/definitions/Missing is missing from the spec",
+        ]);
+        assert_eq!(check("[]"), vec!["body: expected object but it was an array"]);
+        assert_eq!(check_value(&document, &json(r#"{"type": "integer"}"#), &json("1.5"), "id"), vec!["id: expected integer but it was the number 1.5"]);
+    }
+
+    #[test]
+    fn checks_exchanges() {
+        let document = document();
+        let router = Router::new(&document);
+        let exchange = |method: &str, path: &str, status: u16, body: Option<&str>| Exchange {
+            request: request(method, path, "", &[], None),
+            response: Response { status: status, headers: vec![], body: body.map(json) },
+        };
+        assert!(check_exchange(&document, &router, &exchange("GET", "/v1/pets/1", 200, Some(r#"{"name": "Rex"}"#))).is_empty());
+        assert_eq!(check_exchange(&document, &router, &exchange("GET", "/v1/pets/1", 404, None)), vec!["The status 404 is not documented"]);
+        assert_eq!(check_exchange(&document, &router, &exchange("GET", "/v2/pets", 200, None)), vec!["No path of the spec matches"]);
+
+        let route = router.route("POST", "/v1/pets").unwrap();
+        let response = Response { status: 500, headers: vec![], body: Some(json("{}")) };
+        assert_eq!(check_response(&document, &route, &response), vec!["response: the property message is required"]);
+    }
+
+    #[test]
+    fn decodes_queries() {
+        assert_eq!(parse_query("a=1&&b=two+words%21&c&d=%zz%4"), vec![
+            ("a".to_string(), "1".to_string()),
+            ("b".to_string(), "two words!".to_string()),
+            ("c".to_string(), "".to_string()),
+            ("d".to_string(), "%zz%4".to_string()),
+        ]);
+    }
+
+    // Also for the tests of traffic.
+    pub fn json(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+}
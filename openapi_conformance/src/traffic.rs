@@ -0,0 +1,264 @@
+// Reads recorded exchanges: HAR files, as browsers and proxies save them, or
+// JSON lines with one exchange each:
+//
+// {"method": "GET", "path": "/v1/pets?limit=5", "headers": {...}, "body": ...,
+//  "status": 200, "response_headers": {...}, "response_body": ...}
+//
+// Bodies in text are parsed as JSON when they are JSON, form bodies become an
+// object of strings and anything else stays text. Aborted requests, with the
+// status 0, have no response to check and are left out.
+
+use serde_json::{self, Map, Value};
+use {parse_query, Exchange, Request, Response};
+
+// HAR when the text is a JSON object with a log, JSON lines otherwise.
+pub fn read(text: &str) -> Result<Vec<Exchange>, String> {
+    match serde_json::from_str::<Value>(text) {
+        Ok(ref har) if har.get("log").is_some() => from_har(har),
+        _ => from_json_lines(text),
+    }
+}
+
+pub fn from_har(har: &Value) -> Result<Vec<Exchange>, String> {
+    let entries = har.pointer("/log/entries").and_then(|entries| entries.as_array()).ok_or("The HAR file has no log.entries")?;
+    let exchanges = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| har_entry(entry).map_err(|e| format!("Entry {}: {}", index, e)))
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(exchanges.into_iter().flatten().collect())
+}
+
+pub fn from_json_lines(text: &str) -> Result<Vec<Exchange>, String> {
+    let exchanges = text
+        .lines()
+        .enumerate()
+        .filter(|&(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| e.to_string())
+                .and_then(|line: Value| json_line(&line))
+                .map_err(|e| format!("Line {}: {}", index + 1, e))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(exchanges.into_iter().flatten().collect())
+}
+
+fn har_entry(entry: &Value) -> Result<Option<Exchange>, String> {
+    let request = entry.get("request").ok_or("There is no request")?;
+    let response = entry.get("response").ok_or("There is no response")?;
+    let status = status(response.get("status"))?;
+    if status == ABORTED {
+        return Ok(None);
+    }
+    let url = text(request, "url").ok_or("The request has no url")?;
+    let (path, query) = split_url(url);
+
+    let headers = headers(request.get("headers"));
+    let body = request.get("postData").and_then(|post_data| {
+        // Some tools leave the text out of forms and only record their params.
+        match post_data.get("params").and_then(|params| params.as_array()) {
+            Some(params) if text(post_data, "text").is_none() => {
+                let fields: Map<String, Value> = params
+                    .iter()
+                    .filter_map(|param| Some((text(param, "name")?.to_string(), Value::String(text(param, "value")?.to_string()))))
+                    .collect();
+                Some(Value::Object(fields))
+            }
+            _ => body(text(post_data, "text"), content_type(&headers).or_else(|| text(post_data, "mimeType"))),
+        }
+    });
+
+    let response_headers = self::headers(response.get("headers"));
+    let response_body = response.get("content").and_then(|content| {
+        // Binary content is base64, it is not a body to check.
+        if text(content, "encoding") == Some("base64") {
+            return None;
+        }
+        self::body(text(content, "text"), content_type(&response_headers).or_else(|| text(content, "mimeType")))
+    });
+
+    Ok(Some(Exchange {
+        request: Request {
+            method: text(request, "method").ok_or("The request has no method")?.to_string(),
+            path: path.to_string(),
+            query: parse_query(query),
+            headers: headers,
+            body: body,
+        },
+        response: Response {
+            status: status,
+            headers: response_headers,
+            body: response_body,
+        },
+    }))
+}
+
+fn json_line(line: &Value) -> Result<Option<Exchange>, String> {
+    let status = status(line.get("status"))?;
+    if status == ABORTED {
+        return Ok(None);
+    }
+    let target = text(line, "path").or_else(|| text(line, "url")).ok_or("There is no path")?;
+    let (path, query) = split_url(target);
+    let headers = headers(line.get("headers"));
+    let response_headers = self::headers(line.get("response_headers"));
+    let body = json_line_body(line.get("body"), content_type(&headers));
+    let response_body = json_line_body(line.get("response_body"), content_type(&response_headers));
+
+    Ok(Some(Exchange {
+        request: Request {
+            method: text(line, "method").ok_or("There is no method")?.to_string(),
+            path: path.to_string(),
+            query: parse_query(query),
+            headers: headers,
+            body: body,
+        },
+        response: Response {
+            status: status,
+            headers: response_headers,
+            body: response_body,
+        },
+    }))
+}
+
+// Bodies in JSON lines are values already, unless they were recorded as text.
+fn json_line_body(value: Option<&Value>, content_type: Option<&str>) -> Option<Value> {
+    match value {
+        None | Some(&Value::Null) => None,
+        Some(&Value::String(ref text)) => body(Some(text), content_type),
+        Some(value) => Some(value.clone()),
+    }
+}
+
+fn body(text: Option<&str>, content_type: Option<&str>) -> Option<Value> {
+    let text = text.filter(|text| !text.trim().is_empty())?;
+    if content_type.map_or(false, |content_type| content_type.starts_with("application/x-www-form-urlencoded")) {
+        let fields: Map<String, Value> = parse_query(text).into_iter().map(|(name, value)| (name, Value::String(value))).collect();
+        return Some(Value::Object(fields));
+    }
+    Some(serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())))
+}
+
+// {"name": "value"} or [{"name": "name", "value": "value"}], as HAR has them.
+fn headers(value: Option<&Value>) -> Vec<(String, String)> {
+    match value {
+        Some(&Value::Object(ref map)) => map
+            .iter()
+            .filter_map(|(name, value)| value.as_str().map(|value| (name.to_string(), value.to_string())))
+            .collect(),
+        Some(&Value::Array(ref list)) => list
+            .iter()
+            .filter_map(|header| Some((text(header, "name")?.to_string(), text(header, "value")?.to_string())))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn content_type(headers: &[(String, String)]) -> Option<&str> {
+    headers.iter().find(|&&(ref name, _)| name.eq_ignore_ascii_case("content-type")).map(|&(_, ref value)| value.as_str())
+}
+
+// HAR files record the status 0 when the response never came.
+const ABORTED: u16 = 0;
+
+fn status(value: Option<&Value>) -> Result<u16, String> {
+    let status = match value {
+        Some(&Value::String(ref text)) => text.parse().ok(),
+        Some(value) => value.as_u64(),
+        None => None,
+    };
+    match status {
+        Some(0) => Ok(ABORTED),
+        Some(status @ 100..=599) => Ok(status as u16),
+        Some(status) => Err(format!("The status {} is not an HTTP status", status)),
+        None => Err("There is no valid status".to_string()),
+    }
+}
+
+// https://example.com/v1/pets?limit=5 -> (/v1/pets, limit=5), also without the host.
+// Without a path it is /, https://example.com?limit=5 -> (/, limit=5).
+fn split_url(url: &str) -> (&str, &str) {
+    let without_host = match url.find("://") {
+        Some(index) => {
+            let rest = &url[index + 3..];
+            rest.find(&['/', '?', '#'][..]).map_or("", |start| &rest[start..])
+        }
+        None => url,
+    };
+    let without_fragment = without_host.split('#').next().unwrap_or("");
+    let mut parts = without_fragment.splitn(2, '?');
+    let path = parts.next().unwrap_or("");
+    (if path.is_empty() { "/" } else { path }, parts.next().unwrap_or(""))
+}
+
+fn text<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(|value| value.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tests::json;
+
+    #[test]
+    fn reads_har_entries() {
+        let har = r#"{"log": {"entries": [
+            {
+                "request": {"method": "POST", "url": "https://example.com/v1/pets?limit=5#top", "headers": [{"name": "Content-Type", "value": "application/json"}], "postData": {"mimeType": "application/json", "text": "{\"name\": \"Rex\"}"}},
+                "response": {"status": 201, "headers": [], "content": {"mimeType": "application/json", "text": "[1]"}}
+            },
+            {
+                "request": {"method": "POST", "url": "https://example.com", "headers": [], "postData": {"mimeType": "application/x-www-form-urlencoded", "params": [{"name": "name", "value": "Rex"}]}},
+                "response": {"status": 200, "headers": [], "content": {"text": "AAAA", "encoding": "base64"}}
+            }
+        ]}}"#;
+        let exchanges = read(har).unwrap();
+        assert_eq!(exchanges.len(), 2);
+        let first = &exchanges[0];
+        assert_eq!((first.request.method.as_str(), first.request.path.as_str()), ("POST", "/v1/pets"));
+        assert_eq!(first.request.query, vec![("limit".to_string(), "5".to_string())]);
+        assert_eq!(first.request.body, Some(json(r#"{"name": "Rex"}"#)));
+        assert_eq!((first.response.status, first.response.body.clone()), (201, Some(json("[1]"))));
+        assert_eq!(exchanges[1].request.path, "/");
+        assert_eq!(exchanges[1].request.body, Some(json(r#"{"name": "Rex"}"#)));
+        assert_eq!(exchanges[1].response.body, None);
+
+        assert_eq!(read(r#"{"log": {}}"#).unwrap_err(), "The HAR file has no log.entries");
+        assert!(read(r#"{"log": {"entries": [{"request": {}, "response": {"status": 0}}]}}"#).unwrap().is_empty());
+        assert_eq!(
+            read(r#"{"log": {"entries": [{"request": {}, "response": {"status": 70000}}]}}"#).unwrap_err(),
+            "Entry 0: The status 70000 is not an HTTP status"
+        );
+        assert_eq!(read(r#"{"log": {"entries": [{"request": {}}]}}"#).unwrap_err(), "Entry 0: There is no response");
+    }
+
+    #[test]
+    fn reads_json_lines() {
+        let text = [
+            r#"{"method": "GET", "path": "/pets?tag=a+b", "status": "200", "response_body": [{"name": "Rex"}]}"#,
+            "",
+            r#"{"method": "POST", "url": "/pets", "headers": {"content-type": "application/x-www-form-urlencoded"}, "body": "name=Rex", "status": 201, "response_body": "not json"}"#,
+        ].join("\n");
+        let exchanges = read(&text).unwrap();
+        assert_eq!(exchanges[0].request.query, vec![("tag".to_string(), "a b".to_string())]);
+        assert_eq!(exchanges[0].response.status, 200);
+        assert_eq!(exchanges[0].response.body, Some(json(r#"[{"name": "Rex"}]"#)));
+        assert_eq!(exchanges[1].request.body, Some(json(r#"{"name": "Rex"}"#)));
+        assert_eq!(exchanges[1].response.body, Some(json(r#""not json""#)));
+
+        assert_eq!(read("{\"method\": \"GET\", \"path\": \"/\"}\n").unwrap_err(), "Line 1: There is no valid status");
+        assert!(read("\n{").unwrap_err().starts_with("Line 2: "));
+        assert_eq!(read("{\"method\": \"GET\", \"path\": \"/\", \"status\": \"99\"}").unwrap_err(), "Line 1: The status 99 is not an HTTP status");
+        assert!(read("{\"method\": \"GET\", \"path\": \"/\", \"status\": 0}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn splits_urls() {
+        assert_eq!(split_url("https://example.com/v1/pets?limit=5#top"), ("/v1/pets", "limit=5"));
+        assert_eq!(split_url("https://example.com?limit=5"), ("/", "limit=5"));
+        assert_eq!(split_url("https://example.com#top"), ("/", ""));
+        assert_eq!(split_url("https://example.com"), ("/", ""));
+        assert_eq!(split_url("/pets?tag=a"), ("/pets", "tag=a"));
+    }
+}
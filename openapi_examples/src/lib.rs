@@ -0,0 +1,316 @@
+extern crate openapi_extensions;
+extern crate regex;
+extern crate serde_json;
+
+// Makes up example values from the schemas of a spec, for documentation, mocks
+// and tests. It works on the untyped document because the typed schemas do not
+// keep examples nor the bounds.
+//
+// An explicit example (or default) is used as it is. Otherwise the value follows
+// the type, format, enum, bounds and items of the schema, and the name of the
+// property when the schema says nothing else about strings.
+
+use openapi_extensions::{pointer, VERBS};
+use serde_json::{Map, Value};
+
+// Only needs regex and serde_json, the generated servers have a copy.
+pub mod schema;
+use schema::bound;
+
+// The text of the schema module, for the copy.
+pub const SCHEMA_SOURCE: &str = include_str!("schema.rs");
+
+pub struct Examples<'a> {
+    document: &'a Value,
+    // Definitions used inside themselves are expanded this many times, then left out.
+    pub max_repeats: usize,
+}
+
+impl<'a> Examples<'a> {
+    pub fn new(document: &'a Value) -> Examples<'a> {
+        Examples {
+            document: document,
+            max_repeats: 1,
+        }
+    }
+
+    // Also for parameters which are not in the body, they have the same keys as schemas.
+    pub fn schema(&self, schema: &Value) -> Value {
+        self.example(schema, None, &mut vec![])
+    }
+
+    // The definition counts as used once, like when a $ref leads to it.
+    pub fn definition(&self, name: &str) -> Option<Value> {
+        let at = pointer(&["definitions", name]);
+        let definition = self.document.pointer(&at)?;
+        Some(self.example(definition, None, &mut vec![format!("#{}", at)]))
+    }
+
+    // The body of a response of an operation, None when it has no schema.
+    pub fn response(&self, path: &str, verb: &str, status: &str) -> Option<Value> {
+        let response = self.resolve(self.document.pointer(&pointer(&["paths", path, verb, "responses", status]))?);
+        response.get("schema").map(|schema| self.schema(schema))
+    }
+
+    // The body of the request of an operation, None when it has no body parameter.
+    pub fn request(&self, path: &str, verb: &str) -> Option<Value> {
+        let (_, body) = parameters(self.document, path, verb).into_iter().find(|&(_, parameter)| {
+            parameter.get("in").and_then(|location| location.as_str()) == Some("body")
+        })?;
+        body.get("schema").map(|schema| self.schema(schema))
+    }
+
+    fn resolve(&self, value: &'a Value) -> &'a Value {
+        resolve(self.document, value)
+    }
+
+    fn example(&self, schema: &Value, name: Option<&str>, stack: &mut Vec<String>) -> Value {
+        if let Some(ref_path) = schema.get("$ref").and_then(|ref_path| ref_path.as_str()) {
+            let definition = match self.document.pointer(ref_path.trim_start_matches('#')) {
+                Some(definition) => definition,
+                None => return Value::Null,
+            };
+            if stack.iter().filter(|seen| *seen == ref_path).count() > self.max_repeats {
+                return Value::Null;
+            }
+            stack.push(ref_path.to_string());
+            let value = self.example(definition, name, stack);
+            stack.pop();
+            return value;
+        }
+
+        if let Some(example) = schema.get("example").or_else(|| schema.get("default")) {
+            return example.clone();
+        }
+        if let Some(value) = schema.get("enum").and_then(|values| values.as_array()).and_then(|values| values.first()) {
+            return value.clone();
+        }
+
+        if let Some(parts) = schema.get("allOf").and_then(|parts| parts.as_array()) {
+            let mut merged = Map::new();
+            for part in parts {
+                if let Value::Object(map) = self.example(part, name, stack) {
+                    merged.extend(map);
+                }
+            }
+            return Value::Object(merged);
+        }
+
+        match schema.get("type").and_then(|schema_type| schema_type.as_str()) {
+            Some("string") => Value::String(string_example(schema, name)),
+            Some("integer") => Value::from(number_example(schema, 1.0) as i64),
+            Some("number") => Value::from(number_example(schema, 1.5)),
+            Some("boolean") => Value::Bool(true),
+            Some("array") => {
+                let item = schema.get("items").map_or(Value::Null, |items| self.example(items, name, stack));
+                if item.is_null() {
+                    return Value::Array(vec![]);
+                }
+                let count = bound(schema, "minItems").map_or(1, |min| (min as usize).max(1));
+                Value::Array(vec![item; count])
+            }
+            Some("object") | None => self.object_example(schema, stack),
+            Some(_) => Value::Null,
+        }
+    }
+
+    fn object_example(&self, schema: &Value, stack: &mut Vec<String>) -> Value {
+        let mut map = Map::new();
+        let properties = schema.get("properties").and_then(|properties| properties.as_object());
+        for (name, property) in properties.into_iter().flat_map(|properties| properties.iter()) {
+            let value = self.example(property, Some(name), stack);
+            // Cycles end here.
+            if !value.is_null() {
+                map.insert(name.to_string(), value);
+            }
+        }
+        if let Some(additional) = schema.get("additionalProperties").filter(|additional| additional.is_object()) {
+            let value = self.example(additional, None, stack);
+            if !value.is_null() {
+                map.insert("key".to_string(), value);
+            }
+        }
+        Value::Object(map)
+    }
+}
+
+// (path, verb, operation) of the operation with this operationId.
+pub fn operation<'a>(document: &'a Value, operation_id: &str) -> Option<(&'a str, &'a str, &'a Value)> {
+    let paths = document.get("paths").and_then(|paths| paths.as_object())?;
+    paths.iter().filter_map(|(path, item)| item.as_object().map(|item| (path, item))).flat_map(|(path, item)| {
+        item.iter()
+            .filter(|&(verb, _)| VERBS.contains(&verb.as_str()))
+            .map(move |(verb, operation)| (path.as_str(), verb.as_str(), operation))
+    }).find(|&(_, _, operation)| operation.get("operationId").and_then(|id| id.as_str()) == Some(operation_id))
+}
+
+// (pointer, declaration) of the parameters of an operation with the references
+// resolved, the pointer is where the declaration is. The operation ones override
+// the ones of the path.
+pub fn parameters<'a>(document: &'a Value, path: &str, verb: &str) -> Vec<(String, &'a Value)> {
+    let declared = |at: String| -> Vec<(String, &'a Value)> {
+        let parameters = document.pointer(&at).and_then(|parameters| parameters.as_array());
+        parameters
+            .into_iter()
+            .flat_map(|parameters| parameters.iter().enumerate())
+            .map(|(index, parameter)| {
+                let target = parameter.get("$ref").and_then(|ref_path| ref_path.as_str()).map(|ref_path| ref_path.trim_start_matches('#'));
+                match target.and_then(|target| document.pointer(target).map(|resolved| (target, resolved))) {
+                    Some((target, resolved)) => (target.to_string(), resolved),
+                    None => (format!("{}/{}", at, index), parameter),
+                }
+            })
+            .collect()
+    };
+    let mut parameters = declared(pointer(&["paths", path, verb, "parameters"]));
+    for (at, parameter) in declared(pointer(&["paths", path, "parameters"])) {
+        let same = |&(_, p): &(String, &Value)| p.get("name") == parameter.get("name") && p.get("in") == parameter.get("in");
+        if !parameters.iter().any(same) {
+            parameters.push((at, parameter));
+        }
+    }
+    parameters
+}
+
+// The status of the first 2xx response of an operation, the one to show.
+pub fn success_status(operation: &Value) -> Option<&str> {
+    let responses = operation.get("responses").and_then(|responses| responses.as_object())?;
+    responses.keys().find(|status| status.starts_with('2')).map(|status| status.as_str())
+}
+
+// $ref to #/parameters, #/responses or #/definitions, the value itself otherwise.
+pub fn resolve<'a>(document: &'a Value, value: &'a Value) -> &'a Value {
+    value
+        .get("$ref")
+        .and_then(|ref_path| ref_path.as_str())
+        .and_then(|ref_path| document.pointer(ref_path.trim_start_matches('#')))
+        .unwrap_or(value)
+}
+
+// Within minimum and maximum, exclusive or not.
+fn number_example(schema: &Value, fallback: f64) -> f64 {
+    let exclusive = |name| schema.get(name).and_then(|exclusive: &Value| exclusive.as_bool()).unwrap_or(false);
+    match (bound(schema, "minimum"), bound(schema, "maximum")) {
+        (Some(minimum), _) if exclusive("exclusiveMinimum") => minimum + 1.0,
+        (Some(minimum), _) => minimum,
+        (None, Some(maximum)) if maximum < fallback => if exclusive("exclusiveMaximum") { maximum - 1.0 } else { maximum },
+        _ => fallback,
+    }
+}
+
+fn string_example(schema: &Value, name: Option<&str>) -> String {
+    let format = schema.get("format").and_then(|format| format.as_str());
+    let name = name.unwrap_or("").to_lowercase();
+    let text = match format {
+        Some("date-time") => "2017-07-21T17:32:28Z".to_string(),
+        Some("date") => "2017-07-21".to_string(),
+        Some("uuid") => "3fa85f64-5717-4562-b3fc-2c963f66afa6".to_string(),
+        Some("email") => "user@example.com".to_string(),
+        Some("uri") | Some("url") => "https://example.com".to_string(),
+        Some("hostname") => "example.com".to_string(),
+        Some("ipv4") => "192.0.2.1".to_string(),
+        Some("ipv6") => "2001:db8::1".to_string(),
+        Some("byte") => "ZXhhbXBsZQ==".to_string(),
+        Some("password") => "secret".to_string(),
+        // Formats say it all, without one the name of the property is the best hint.
+        _ if name.contains("email") => "user@example.com".to_string(),
+        _ if name.ends_with("url") || name.ends_with("uri") => "https://example.com".to_string(),
+        _ if name.is_empty() => "string".to_string(),
+        _ => name,
+    };
+    let length = text.chars().count();
+    match (bound(schema, "minLength"), bound(schema, "maxLength")) {
+        (Some(min), _) if (length as f64) < min => format!("{}{}", text, "x".repeat(min as usize - length)),
+        (_, Some(max)) if (length as f64) > max => text.chars().take(max as usize).collect(),
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> Value {
+        json(r##"{
+            "swagger": "2.0",
+            "parameters": {"Body": {"name": "body", "in": "body", "schema": {"$ref": "#/definitions/Pet"}}},
+            "responses": {"Found": {"description": "ok", "schema": {"$ref": "#/definitions/Pet"}}},
+            "paths": {
+                "/pets": {
+                    "parameters": [{"name": "limit", "in": "query", "type": "integer"}],
+                    "post": {"operationId": "pets.create", "parameters": [{"$ref": "#/parameters/Body"}], "responses": {"201": {"$ref": "#/responses/Found"}}},
+                    "get": {"operationId": "pets.list", "parameters": [{"name": "limit", "in": "query", "type": "integer", "maximum": 5}], "responses": {"default": {"description": "error"}, "200": {"description": "ok", "schema": {"type": "array", "minItems": 2, "items": {"type": "string", "format": "uuid"}}}}}
+                }
+            },
+            "definitions": {
+                "Pet": {"type": "object", "properties": {
+                    "name": {"type": "string", "example": "Rex"},
+                    "kind": {"type": "string", "enum": ["cat", "dog"]},
+                    "owner_email": {"type": "string"},
+                    "code": {"type": "string", "minLength": 6, "maxLength": 8},
+                    "age": {"type": "integer", "minimum": 0, "exclusiveMinimum": true},
+                    "weight": {"type": "number", "maximum": 1},
+                    "born": {"type": "string", "format": "date-time"},
+                    "parent": {"$ref": "#/definitions/Pet"}
+                }}
+            }
+        }"##)
+    }
+
+    #[test]
+    fn follows_the_schemas() {
+        let document = document();
+        let examples = Examples::new(&document);
+        let schema = |text: &str| examples.schema(&json(text));
+        assert_eq!(schema(r#"{"type": "string", "default": "x"}"#), json("\"x\""));
+        assert_eq!(schema(r#"{"type": "string", "maxLength": 3}"#), json("\"str\""));
+        assert_eq!(schema(r#"{"type": "integer", "maximum": 0, "exclusiveMaximum": true}"#), json("-1"));
+        assert_eq!(schema(r#"{"type": "file"}"#), Value::Null);
+        assert_eq!(schema(r##"{"type": "array", "items": {"$ref": "#/definitions/Missing"}}"##), json("[]"));
+        assert_eq!(
+            schema(r#"{"allOf": [{"properties": {"a": {"type": "boolean"}}}, {"additionalProperties": {"type": "string", "format": "email"}}]}"#),
+            json(r#"{"a": true, "key": "user@example.com"}"#)
+        );
+    }
+
+    #[test]
+    fn expands_cycles_a_limited_number_of_times() {
+        let document = document();
+        let mut examples = Examples::new(&document);
+        let pet = |parent| {
+            let mut pet = json(r#"{"name": "Rex", "kind": "cat", "owner_email": "user@example.com", "code": "codexx", "age": 1, "weight": 1.0, "born": "2017-07-21T17:32:28Z"}"#);
+            if let Some(parent) = parent {
+                pet.as_object_mut().unwrap().insert("parent".to_string(), parent);
+            }
+            pet
+        };
+        assert_eq!(examples.definition("Pet"), Some(pet(Some(pet(None)))));
+        examples.max_repeats = 0;
+        assert_eq!(examples.definition("Pet"), Some(pet(None)));
+        assert_eq!(examples.definition("Missing"), None);
+    }
+
+    #[test]
+    fn finds_operations_their_parameters_and_bodies() {
+        let document = document();
+        let examples = Examples::new(&document);
+        let (path, verb, list) = operation(&document, "pets.list").unwrap();
+        assert_eq!((path, verb), ("/pets", "get"));
+        assert_eq!(success_status(list), Some("200"));
+        assert_eq!(examples.response(path, verb, "200"), Some(json(r#"["3fa85f64-5717-4562-b3fc-2c963f66afa6", "3fa85f64-5717-4562-b3fc-2c963f66afa6"]"#)));
+        assert_eq!(examples.response(path, verb, "default"), None);
+        assert_eq!(examples.request(path, verb), None);
+
+        let limits: Vec<&Value> = parameters(&document, path, verb).into_iter().filter_map(|(_, parameter)| parameter.get("maximum")).collect();
+        assert_eq!(limits, vec![&json("5")]);
+        let pointers: Vec<String> = parameters(&document, "/pets", "post").into_iter().map(|(at, _)| at).collect();
+        assert_eq!(pointers, vec!["/parameters/Body", "/paths/~1pets/parameters/0"]);
+        assert!(examples.request("/pets", "post").unwrap().get("name").is_some());
+        assert_eq!(examples.response("/pets", "post", "201"), examples.definition("Pet"));
+        assert!(operation(&document, "missing").is_none());
+    }
+
+    fn json(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+}
@@ -0,0 +1,184 @@
+// Checks values against the schemas of OpenAPI 2.0, the subset of JSON Schema
+// they use. Problems are described with the place of the value: body.pets[0].name
+// Parameters which arrive as text are typed first.
+
+use regex::Regex;
+use serde_json::Value;
+
+// document has the definitions the references point to.
+pub fn check(document: &Value, schema: &Value, value: &Value, place: &str) -> Vec<String> {
+    let mut problems = vec![];
+    check_in(document, schema, value, place, &mut problems, 0);
+    problems
+}
+
+// References deeper than this are not followed, a value can not be that nested.
+const MAX_DEPTH: usize = 64;
+
+fn check_in(document: &Value, schema: &Value, value: &Value, place: &str, problems: &mut Vec<String>, depth: usize) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    if let Some(ref_path) = schema.get("$ref").and_then(|ref_path| ref_path.as_str()) {
+        match document.pointer(ref_path.trim_start_matches('#')) {
+            Some(definition) => check_in(document, definition, value, place, problems, depth + 1),
+            None => problems.push(format!("{}: the schema {} is missing from the spec", place, ref_path)),
+        }
+        return;
+    }
+    for part in schema.get("allOf").and_then(|parts| parts.as_array()).into_iter().flat_map(|parts| parts.iter()) {
+        check_in(document, part, value, place, problems, depth + 1);
+    }
+
+    let nullable = schema.get("x-nullable").and_then(|nullable| nullable.as_bool()).unwrap_or(false);
+    if value.is_null() && nullable {
+        return;
+    }
+    if let Some(schema_type) = schema.get("type").and_then(|schema_type| schema_type.as_str()) {
+        if !is_of_type(value, schema_type) {
+            problems.push(format!("{}: expected {} but it was {}", place, schema_type, describe(value)));
+            return;
+        }
+    }
+    if let Some(values) = schema.get("enum").and_then(|values| values.as_array()) {
+        if !values.contains(value) {
+            problems.push(format!("{}: {} is not one of {}", place, value, Value::Array(values.clone())));
+        }
+    }
+
+    match *value {
+        Value::String(ref text) => {
+            let length = text.chars().count() as f64;
+            if bound(schema, "minLength").map_or(false, |min| length < min) {
+                problems.push(format!("{}: {:?} is shorter than {}", place, text, schema["minLength"]));
+            }
+            if bound(schema, "maxLength").map_or(false, |max| length > max) {
+                problems.push(format!("{}: {:?} is longer than {}", place, text, schema["maxLength"]));
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(|pattern| pattern.as_str()) {
+                if Regex::new(pattern).map(|pattern| !pattern.is_match(text)).unwrap_or(false) {
+                    problems.push(format!("{}: {:?} does not match {}", place, text, pattern));
+                }
+            }
+        }
+        Value::Number(ref number) => {
+            let number = number.as_f64().unwrap_or(0.0);
+            let exclusive = |name| schema.get(name).and_then(|exclusive: &Value| exclusive.as_bool()).unwrap_or(false);
+            if let Some(minimum) = bound(schema, "minimum") {
+                if number < minimum || (exclusive("exclusiveMinimum") && number == minimum) {
+                    problems.push(format!("{}: {} is less than the minimum {}", place, number, minimum));
+                }
+            }
+            if let Some(maximum) = bound(schema, "maximum") {
+                if number > maximum || (exclusive("exclusiveMaximum") && number == maximum) {
+                    problems.push(format!("{}: {} is more than the maximum {}", place, number, maximum));
+                }
+            }
+        }
+        Value::Array(ref items) => {
+            let length = items.len() as f64;
+            if bound(schema, "minItems").map_or(false, |min| length < min) {
+                problems.push(format!("{}: expected at least {} items but there are {}", place, schema["minItems"], items.len()));
+            }
+            if bound(schema, "maxItems").map_or(false, |max| length > max) {
+                problems.push(format!("{}: expected at most {} items but there are {}", place, schema["maxItems"], items.len()));
+            }
+            let unique = schema.get("uniqueItems").and_then(|unique| unique.as_bool()).unwrap_or(false);
+            if unique && items.iter().enumerate().any(|(index, item)| items[..index].contains(item)) {
+                problems.push(format!("{}: the items are not unique", place));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check_in(document, item_schema, item, &format!("{}[{}]", place, index), problems, depth + 1);
+                }
+            }
+        }
+        Value::Object(ref map) => {
+            let required = schema.get("required").and_then(|required| required.as_array());
+            for name in required.into_iter().flat_map(|required| required.iter()).filter_map(|name| name.as_str()) {
+                if !map.contains_key(name) {
+                    problems.push(format!("{}: the property {} is required", place, name));
+                }
+            }
+            let properties = schema.get("properties").and_then(|properties| properties.as_object());
+            let additional = schema.get("additionalProperties");
+            for (name, property) in map {
+                let child = format!("{}.{}", place, name);
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(property_schema) => check_in(document, property_schema, property, &child, problems, depth + 1),
+                    None => match additional {
+                        Some(&Value::Bool(false)) => problems.push(format!("{}: the property {} is not allowed", place, name)),
+                        Some(additional) if additional.is_object() => {
+                            check_in(document, additional, property, &child, problems, depth + 1)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// A numeric keyword of a schema: minimum, maxLength, minItems, ...
+pub fn bound(schema: &Value, name: &str) -> Option<f64> {
+    schema.get(name).and_then(|bound| bound.as_f64())
+}
+
+// Whether value is of the type of a schema.
+pub fn is_of_type(value: &Value, schema_type: &str) -> bool {
+    match schema_type {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        // file, the content is not checked.
+        _ => true,
+    }
+}
+
+// Parameters outside the body arrive as text, they are typed as their declaration says.
+pub fn parameter_value(parameter: &Value, texts: &[&str]) -> Value {
+    let parameter_type = parameter.get("type").and_then(|parameter_type| parameter_type.as_str()).unwrap_or("string");
+    if parameter_type != "array" {
+        return typed(texts[0], parameter_type);
+    }
+    let items_type = parameter.pointer("/items/type").and_then(|items_type| items_type.as_str()).unwrap_or("string");
+    let separator = match parameter.get("collectionFormat").and_then(|format| format.as_str()) {
+        Some("multi") => None,
+        Some("ssv") => Some(' '),
+        Some("tsv") => Some('\t'),
+        Some("pipes") => Some('|'),
+        _ => Some(','),
+    };
+    let items: Vec<Value> = match separator {
+        None => texts.iter().map(|text| typed(text, items_type)).collect(),
+        Some(separator) => texts[0].split(separator).map(|text| typed(text, items_type)).collect(),
+    };
+    Value::Array(items)
+}
+
+// Text which is not of the type stays text, the schema check reports it.
+fn typed(text: &str, value_type: &str) -> Value {
+    let parsed = match value_type {
+        "integer" => text.parse::<i64>().ok().map(Value::from),
+        "number" => text.parse::<f64>().ok().map(Value::from),
+        "boolean" => text.parse::<bool>().ok().map(Value::Bool),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::String(text.to_string()))
+}
+
+fn describe(value: &Value) -> String {
+    match *value {
+        Value::Null => "null".to_string(),
+        Value::Bool(_) => format!("the boolean {}", value),
+        Value::Number(_) => format!("the number {}", value),
+        Value::String(_) => format!("the string {}", value),
+        Value::Array(_) => "an array".to_string(),
+        Value::Object(_) => "an object".to_string(),
+    }
+}
@@ -0,0 +1,279 @@
+extern crate json_path;
+extern crate openapi;
+extern crate serde_json;
+
+// The openapi crate types drop every key they do not know, vendor extensions
+// (x-*) included. Extensions are taken out of the untyped document before it is
+// deserialized, kept by the JSON pointer of the object which had them and put
+// back when the document is serialized again.
+
+use json_path::{Key, Location};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::mem;
+
+pub type ExtensionMap = Map<String, Value>;
+
+// Objects whose keys are names chosen by the user, an x- key there is an element.
+const NAME_MAPS: [&str; 5] = ["definitions", "properties", "securityDefinitions", "scopes", "headers"];
+// Values which are data, not OpenAPI objects.
+const DATA: [&str; 4] = ["example", "examples", "default", "enum"];
+
+// Keys of a path item which are operations, in the order of the specification.
+pub const VERBS: [&str; 7] = ["get", "put", "post", "delete", "options", "head", "patch"];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Extensions(BTreeMap<String, ExtensionMap>);
+
+// A spec together with the extensions its typed structure cannot hold.
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub spec: openapi::Spec,
+    pub extensions: Extensions,
+    // The document as it was read. It has the keys the typed spec does not
+    // know (minimum, maximum, ...) for the validations which need them.
+    pub source: Value,
+}
+
+impl Document {
+    pub fn new(spec: openapi::Spec) -> Document {
+        let source = serde_json::to_value(&spec).unwrap_or(Value::Null);
+        Document {
+            spec: spec,
+            extensions: Extensions::default(),
+            source: source,
+        }
+    }
+
+    pub fn from_value(mut value: Value) -> Result<Document, serde_json::Error> {
+        let source = value.clone();
+        let extensions = Extensions::take(&mut value);
+        Ok(Document {
+            spec: serde_json::from_value(value)?,
+            extensions: extensions,
+            source: source,
+        })
+    }
+
+    pub fn to_value(&self) -> Result<Value, serde_json::Error> {
+        let mut value = serde_json::to_value(&self.spec)?;
+        self.extensions.apply(&mut value);
+        Ok(value)
+    }
+}
+
+impl Extensions {
+    // Removes the extensions from the document and returns them.
+    pub fn take(document: &mut Value) -> Extensions {
+        let mut extensions = Extensions::default();
+        take_from(document, &mut vec![], false, &mut extensions.0);
+        extensions
+    }
+
+    // Writes the extensions back. Objects which no longer exist are skipped.
+    pub fn apply(&self, document: &mut Value) {
+        for (pointer, extensions) in &self.0 {
+            if let Some(&mut Value::Object(ref mut map)) = document.pointer_mut(pointer) {
+                for (name, value) in extensions {
+                    map.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+    }
+
+    pub fn insert(&mut self, pointer: &str, name: &str, value: Value) {
+        self.0.entry(pointer.to_string()).or_insert_with(Map::new).insert(name.to_string(), value);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // (JSON pointer of the object, its extensions)
+    pub fn iter<'a>(&'a self) -> ::std::collections::btree_map::Iter<'a, String, ExtensionMap> {
+        self.0.iter()
+    }
+
+    pub fn at(&self, pointer: &str) -> Option<&ExtensionMap> {
+        self.0.get(pointer)
+    }
+
+    pub fn spec(&self) -> Option<&ExtensionMap> {
+        self.at("")
+    }
+
+    pub fn info(&self) -> Option<&ExtensionMap> {
+        self.at("/info")
+    }
+
+    pub fn operation(&self, path: &str, verb: &str) -> Option<&ExtensionMap> {
+        self.at(&pointer(&["paths", path, verb]))
+    }
+
+    // index is the position in the parameters list of the operation.
+    pub fn parameter(&self, path: &str, verb: &str, index: usize) -> Option<&ExtensionMap> {
+        self.at(&format!("{}/{}", pointer(&["paths", path, verb, "parameters"]), index))
+    }
+
+    pub fn response(&self, path: &str, verb: &str, status: &str) -> Option<&ExtensionMap> {
+        self.at(&pointer(&["paths", path, verb, "responses", status]))
+    }
+
+    pub fn definition(&self, name: &str) -> Option<&ExtensionMap> {
+        self.at(&pointer(&["definitions", name]))
+    }
+}
+
+// The operations of a typed path item with their verbs, in the order of VERBS.
+pub fn operations(item: &openapi::Operations) -> Vec<(&'static str, &openapi::Operation)> {
+    VERBS
+        .iter()
+        .filter_map(|&verb| {
+            let operation = match verb {
+                "get" => &item.get,
+                "put" => &item.put,
+                "post" => &item.post,
+                "delete" => &item.delete,
+                "options" => &item.options,
+                "head" => &item.head,
+                "patch" => &item.patch,
+                _ => return None,
+            };
+            operation.as_ref().map(|operation| (verb, operation))
+        })
+        .collect()
+}
+
+// ["paths", "/users/{id}", "get"] -> /paths/~1users~1{id}/get
+pub fn pointer(keys: &[&str]) -> String {
+    let location: Location = keys.iter().map(|key| Key::Field(key.to_string())).collect();
+    json_path::to_pointer(&location)
+}
+
+fn take_from(value: &mut Value, location: &mut Location, name_map: bool, found: &mut BTreeMap<String, ExtensionMap>) {
+    match *value {
+        Value::Object(ref mut map) => {
+            if !name_map && map.keys().any(|key| key.starts_with("x-")) {
+                // Rebuilt instead of removing keys one by one to keep the order of the rest.
+                let (extensions, rest): (ExtensionMap, ExtensionMap) = mem::replace(map, Map::new())
+                    .into_iter()
+                    .partition(|&(ref key, _)| key.starts_with("x-"));
+                *map = rest;
+                found.insert(json_path::to_pointer(location), extensions);
+            }
+            for (key, child) in map.iter_mut() {
+                if !name_map && DATA.contains(&key.as_str()) {
+                    continue;
+                }
+                // The parameters and responses of the root are maps of names,
+                // the ones of an operation are a list and a map of status codes.
+                let child_name_map = !name_map
+                    && (NAME_MAPS.contains(&key.as_str())
+                        || (location.is_empty() && (key == "parameters" || key == "responses")));
+                location.push(Key::Field(key.to_string()));
+                take_from(child, location, child_name_map, found);
+                location.pop();
+            }
+        }
+        Value::Array(ref mut array) => {
+            for (index, child) in array.iter_mut().enumerate() {
+                location.push(Key::Index(index));
+                take_from(child, location, false, found);
+                location.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    fn source() -> Value {
+        json(r##"{
+            "swagger": "2.0",
+            "x-root": 1,
+            "info": {"title": "t", "version": "1", "x-logo": {"url": "logo.png"}},
+            "paths": {
+                "/users/{id}": {
+                    "get": {
+                        "x-rate": 10,
+                        "parameters": [{"name": "id", "in": "path", "type": "string", "x-example": "1"}],
+                        "responses": {"200": {"description": "ok", "x-cache": true}}
+                    }
+                }
+            },
+            "definitions": {
+                "x-User": {"type": "object", "x-table": "users", "properties": {"x-id": {"type": "string"}}, "example": {"x-data": 1}}
+            },
+            "parameters": {"x-shared": {"name": "x-trace", "in": "header", "type": "string"}}
+        }"##)
+    }
+
+    #[test]
+    fn takes_extensions_of_objects_only() {
+        let mut document = source();
+        let extensions = Extensions::take(&mut document);
+
+        assert_eq!(extensions.spec(), json(r#"{"x-root": 1}"#).as_object());
+        assert_eq!(extensions.info(), json(r#"{"x-logo": {"url": "logo.png"}}"#).as_object());
+        assert_eq!(extensions.operation("/users/{id}", "get"), json(r#"{"x-rate": 10}"#).as_object());
+        assert_eq!(extensions.parameter("/users/{id}", "get", 0), json(r#"{"x-example": "1"}"#).as_object());
+        assert_eq!(extensions.response("/users/{id}", "get", "200"), json(r#"{"x-cache": true}"#).as_object());
+        assert_eq!(extensions.definition("x-User"), json(r#"{"x-table": "users"}"#).as_object());
+        assert_eq!(extensions.iter().count(), 6);
+
+        // Names in maps and data values are left alone.
+        assert!(document["definitions"]["x-User"]["properties"].get("x-id").is_some());
+        assert_eq!(document["definitions"]["x-User"]["example"], json(r#"{"x-data": 1}"#));
+        assert!(document["parameters"].get("x-shared").is_some());
+        assert!(document["info"].get("x-logo").is_none());
+    }
+
+    #[test]
+    fn puts_extensions_back_in_place() {
+        let mut document = source();
+        let extensions = Extensions::take(&mut document);
+        extensions.apply(&mut document);
+        assert_eq!(document, source());
+
+        // The keys the extensions were between keep their order.
+        let keys: Vec<&String> = document["info"].as_object().unwrap().keys().collect();
+        assert_eq!(keys, vec!["title", "version", "x-logo"]);
+    }
+
+    #[test]
+    fn skips_objects_which_are_gone() {
+        let mut extensions = Extensions::default();
+        assert!(extensions.is_empty());
+        extensions.insert("/info", "x-logo", json("1"));
+        extensions.insert("/missing", "x-gone", json("2"));
+        let mut document = json(r#"{"info": {}}"#);
+        extensions.apply(&mut document);
+        assert_eq!(document, json(r#"{"info": {"x-logo": 1}}"#));
+    }
+
+    #[test]
+    fn escapes_pointers() {
+        assert_eq!(pointer(&["paths", "/users/{id}", "get"]), "/paths/~1users~1{id}/get");
+        assert_eq!(pointer(&["definitions", "a~b"]), "/definitions/a~0b");
+        assert_eq!(pointer(&[]), "");
+    }
+
+    #[test]
+    fn lists_operations_in_the_order_of_verbs() {
+        let item: openapi::Operations = serde_json::from_value(json(r#"{
+            "patch": {"responses": {}},
+            "post": {"responses": {}},
+            "get": {"responses": {}},
+            "delete": {"responses": {}}
+        }"#)).unwrap();
+        let verbs: Vec<&str> = operations(&item).into_iter().map(|(verb, _)| verb).collect();
+        assert_eq!(verbs, vec!["get", "post", "delete", "patch"]);
+    }
+}
@@ -0,0 +1,414 @@
+// Rules written by the users in the style of Spectral rulesets:
+//
+// rules:
+//   admin-security:
+//     description: Every operation under /admin must declare security
+//     given: "$.paths[?(@property =~ '^/admin')][*]"
+//     then:
+//       field: security
+//       function: truthy
+//     severity: error
+//
+// given selects nodes with JSONPath, then checks a field of each of them (or
+// the node itself) with one of the functions. The name of the rule is the rule
+// of its findings.
+
+use json_path::{self, JsonPath};
+use openapi_examples::schema::{bound, is_of_type};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use {Case, OpenAPIValidation, ValidationOptions};
+use validation_results::ValidationResults;
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn to_vec(&self) -> Vec<&T> {
+        match *self {
+            OneOrMany::One(ref one) => vec![one],
+            OneOrMany::Many(ref many) => many.iter().collect(),
+        }
+    }
+}
+
+// A rule file, the rules are added to the ones of the profile.
+#[derive(Debug, Deserialize)]
+pub struct Ruleset {
+    #[serde(default)]
+    pub rules: BTreeMap<String, CustomRule>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CustomRule {
+    pub description: Option<String>,
+    // Shown instead of the description when given.
+    pub message: Option<String>,
+    pub given: OneOrMany<String>,
+    pub then: OneOrMany<Then>,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Then {
+    // A key of the given node, a dotted path under it or @key for the name of the node itself.
+    pub field: Option<String>,
+    pub function: Function,
+    #[serde(rename = "functionOptions", default)]
+    pub function_options: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Function {
+    Truthy,
+    Falsy,
+    Defined,
+    Undefined,
+    // functionOptions: match and/or notMatch, regular expressions
+    Pattern,
+    // functionOptions: values
+    Enumeration,
+    // functionOptions: min and/or max, of strings, arrays, objects or numbers
+    Length,
+    // functionOptions: type, one of the cases of the naming rules
+    Casing,
+    // functionOptions: schema, a JSON schema
+    Schema,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warn,
+    Info,
+    Hint,
+}
+
+impl Default for Severity {
+    fn default() -> Severity {
+        Severity::Warn
+    }
+}
+
+pub struct CustomRules<'a> {
+    pub rules: &'a BTreeMap<String, CustomRule>,
+    pub document: &'a Value,
+}
+
+impl<'a> OpenAPIValidation for CustomRules<'a> {
+    fn validate(&self, _: &ValidationOptions) -> ValidationResults {
+        let mut r = ValidationResults::new();
+
+        for (name, rule) in self.rules {
+            for given in rule.given.to_vec() {
+                let path = match JsonPath::parse(given) {
+                    Ok(path) => path,
+                    Err(error) => {
+                        r.assert(name, "", &Err(format!("The given of the rule is not valid. {}", error)));
+                        continue;
+                    }
+                };
+                for (location, node) in path.select(self.document) {
+                    for then in rule.then.to_vec() {
+                        let key = location.last().map(|key| match *key {
+                            json_path::Key::Field(ref field) => Value::String(field.to_string()),
+                            json_path::Key::Index(index) => Value::from(index),
+                        });
+                        let target = match then.field.as_ref().map(|field| field.as_str()) {
+                            None => Some(node),
+                            Some("@key") => key.as_ref(),
+                            Some(field) => field.split('.').fold(Some(node), |value, key| value.and_then(|value| value.get(key))),
+                        };
+                        if let Err(problem) = check(then, target) {
+                            let message = rule.message.as_ref().or(rule.description.as_ref()).map_or(problem, |m| m.to_string());
+                            let finding = Err(format!("{} in {}", message, json_path::to_string(&location)));
+                            let at = json_path::to_pointer(&location);
+                            if rule.severity == Severity::Error {
+                                r.assert(name, &at, &finding);
+                            } else {
+                                r.assert_warn(name, &at, &finding);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        r
+    }
+}
+
+// Missing values only fail truthy and defined, like in Spectral.
+fn check(then: &Then, value: Option<&Value>) -> Result<(), String> {
+    let options = &then.function_options;
+    let value = match (then.function, value) {
+        (Function::Truthy, value) if !value.map_or(false, is_truthy) => return Err("the value is not truthy".to_string()),
+        (Function::Falsy, Some(value)) if is_truthy(value) => return Err("the value is not falsy".to_string()),
+        (Function::Defined, None) => return Err("the value is not defined".to_string()),
+        (Function::Undefined, Some(_)) => return Err("the value is defined".to_string()),
+        (_, None) => return Ok(()),
+        (_, Some(value)) => value,
+    };
+
+    match then.function {
+        Function::Pattern => {
+            let text = value.as_str().map(|text| text.to_string()).unwrap_or_else(|| value.to_string());
+            if let Some(expression) = options.get("match").and_then(|e| e.as_str()) {
+                if !regex(expression)?.is_match(&text) {
+                    return Err(format!("{} does not match {}", text, expression));
+                }
+            }
+            if let Some(expression) = options.get("notMatch").and_then(|e| e.as_str()) {
+                if regex(expression)?.is_match(&text) {
+                    return Err(format!("{} matches {}", text, expression));
+                }
+            }
+            Ok(())
+        }
+        Function::Enumeration => {
+            let values = options.get("values").and_then(|values| values.as_array()).ok_or("enumeration needs values")?;
+            if values.contains(value) {
+                Ok(())
+            } else {
+                Err(format!("{} is not one of {}", value, Value::Array(values.clone())))
+            }
+        }
+        Function::Length => {
+            let length = match *value {
+                Value::String(ref text) => text.chars().count() as f64,
+                Value::Array(ref array) => array.len() as f64,
+                Value::Object(ref map) => map.len() as f64,
+                Value::Number(ref number) => number.as_f64().unwrap_or(0.0),
+                _ => return Ok(()),
+            };
+            match (bound(options, "min"), bound(options, "max")) {
+                (Some(min), _) if length < min => Err(format!("the length {} is less than {}", length, min)),
+                (_, Some(max)) if length > max => Err(format!("the length {} is more than {}", length, max)),
+                _ => Ok(()),
+            }
+        }
+        Function::Casing => {
+            let case = options
+                .get("type")
+                .cloned()
+                .and_then(|case| ::serde_json::from_value::<Case>(case).ok())
+                .ok_or("casing needs a type: snake, camel, kebab, pascal or train")?;
+            match value.as_str() {
+                Some(text) if !case.matches(text) => Err(format!("{} is not {}", text, case)),
+                _ => Ok(()),
+            }
+        }
+        Function::Schema => {
+            let schema = options.get("schema").ok_or("schema needs a schema")?;
+            match_schema(schema, value)
+        }
+        Function::Truthy | Function::Falsy | Function::Defined | Function::Undefined => Ok(()),
+    }
+}
+
+fn regex(expression: &str) -> Result<Regex, String> {
+    Regex::new(expression).map_err(|e| format!("{} is not a valid regular expression: {}", expression, e))
+}
+
+// As in JavaScript, empty arrays and objects are truthy.
+fn is_truthy(value: &Value) -> bool {
+    match *value {
+        Value::Null => false,
+        Value::Bool(value) => value,
+        Value::Number(ref number) => number.as_f64().map_or(false, |number| number != 0.0),
+        Value::String(ref text) => !text.is_empty(),
+        _ => true,
+    }
+}
+
+// The part of JSON Schema which rules need: type, enum, required, properties,
+// items, contains, pattern and the length bounds.
+fn match_schema(schema: &Value, value: &Value) -> Result<(), String> {
+    if let Some(schema_type) = schema.get("type").and_then(|schema_type| schema_type.as_str()) {
+        if !is_of_type(value, schema_type) {
+            return Err(format!("{} is not of type {}", value, schema_type));
+        }
+    }
+    if let Some(values) = schema.get("enum").and_then(|values| values.as_array()) {
+        if !values.contains(value) {
+            return Err(format!("{} is not one of {}", value, Value::Array(values.clone())));
+        }
+    }
+    if let Some(expression) = schema.get("pattern").and_then(|expression| expression.as_str()) {
+        if let Some(text) = value.as_str() {
+            if !regex(expression)?.is_match(text) {
+                return Err(format!("{} does not match {}", text, expression));
+            }
+        }
+    }
+
+    let length = match *value {
+        Value::String(ref text) => Some((text.chars().count(), "minLength", "maxLength")),
+        Value::Array(ref array) => Some((array.len(), "minItems", "maxItems")),
+        Value::Object(ref map) => Some((map.len(), "minProperties", "maxProperties")),
+        _ => None,
+    };
+    if let Some((length, min, max)) = length {
+        let length = length as f64;
+        if bound(schema, min).map_or(false, |min| length < min) || bound(schema, max).map_or(false, |max| length > max) {
+            return Err(format!("the length of {} is out of {} and {}", value, min, max));
+        }
+    }
+
+    if let Value::Object(ref map) = *value {
+        let required = schema.get("required").and_then(|required| required.as_array());
+        for name in required.into_iter().flat_map(|required| required.iter()).filter_map(|name| name.as_str()) {
+            if !map.contains_key(name) {
+                return Err(format!("{} is required", name));
+            }
+        }
+        let properties = schema.get("properties").and_then(|properties| properties.as_object());
+        for (name, property) in properties.into_iter().flat_map(|properties| properties.iter()) {
+            if let Some(child) = map.get(name) {
+                match_schema(property, child).map_err(|e| format!("{}: {}", name, e))?;
+            }
+        }
+    }
+
+    if let Value::Array(ref array) = *value {
+        if let Some(items) = schema.get("items") {
+            for (index, item) in array.iter().enumerate() {
+                match_schema(items, item).map_err(|e| format!("[{}]: {}", index, e))?;
+            }
+        }
+        if let Some(contains) = schema.get("contains") {
+            if !array.iter().any(|item| match_schema(contains, item).is_ok()) {
+                return Err(format!("no element matches {}", contains));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    fn json(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    fn document() -> Value {
+        json(r#"{
+            "info": {"title": "Pets", "contact": {}},
+            "tags": [{"name": "pets"}, {"name": "Admin Tools"}],
+            "paths": {
+                "/pets": {"get": {"summary": "List", "security": [{"key": []}]}},
+                "/admin/users": {"get": {"summary": ""}, "delete": {"security": []}}
+            }
+        }"#)
+    }
+
+    // (rule, location, message) of each finding, errors first.
+    fn findings(rules: &str) -> (usize, Vec<(String, String, String)>) {
+        let ruleset: Ruleset = serde_json::from_value(json(rules)).unwrap();
+        let document = document();
+        let results = CustomRules { rules: &ruleset.rules, document: &document }.validate(&ValidationOptions::default());
+        let findings = results.findings().into_iter().map(|f| (f.rule.to_string(), f.location.to_string(), f.message.to_string())).collect();
+        (results.error_count(), findings)
+    }
+
+    fn finding(rule: &str, location: &str, message: &str) -> (String, String, String) {
+        (rule.to_string(), location.to_string(), message.to_string())
+    }
+
+    fn check_with(function: &str, options: &str, value: Option<&Value>) -> Result<(), String> {
+        let then: Then = serde_json::from_value(json(&format!(r#"{{"function": "{}", "functionOptions": {}}}"#, function, options))).unwrap();
+        check(&then, value)
+    }
+
+    #[test]
+    fn reports_nodes_failing_the_rules() {
+        let rules = r#"{"rules": {
+            "admin-security": {
+                "description": "Admin operations need security",
+                "given": "$.paths[?(@property =~ '^/admin')][*]",
+                "then": {"field": "security", "function": "truthy"},
+                "severity": "error"
+            },
+            "summaries": {
+                "given": ["$.paths.*.*"],
+                "then": [{"field": "summary", "function": "defined"}, {"field": "summary", "function": "length", "functionOptions": {"min": 1}}]
+            }
+        }}"#;
+        let (errors, findings) = findings(rules);
+        assert_eq!(errors, 1);
+        assert_eq!(findings, vec![
+            finding("admin-security", "/paths/~1admin~1users/get", "Admin operations need security in $['paths']['/admin/users']['get']"),
+            finding("summaries", "/paths/~1admin~1users/get", "the length 0 is less than 1 in $['paths']['/admin/users']['get']"),
+            finding("summaries", "/paths/~1admin~1users/delete", "the value is not defined in $['paths']['/admin/users']['delete']"),
+        ]);
+    }
+
+    #[test]
+    fn checks_keys_and_nested_fields() {
+        let rules = r#"{"rules": {
+            "tag-case": {"message": "Tags are kebab-case", "given": "$.tags[*].name", "then": {"function": "casing", "functionOptions": {"type": "kebab"}}},
+            "path-case": {"given": "$.paths.*", "then": {"field": "@key", "function": "pattern", "functionOptions": {"notMatch": "/admin"}}},
+            "contact": {"given": "$", "then": {"field": "info.contact.email", "function": "truthy"}}
+        }}"#;
+        let (_, findings) = findings(rules);
+        assert_eq!(findings, vec![
+            finding("contact", "", "the value is not truthy in $"),
+            finding("path-case", "/paths/~1admin~1users", "/admin/users matches /admin in $['paths']['/admin/users']"),
+            finding("tag-case", "/tags/1/name", "Tags are kebab-case in $['tags'][1]['name']"),
+        ]);
+    }
+
+    #[test]
+    fn reports_invalid_paths_as_findings() {
+        let (errors, findings) = findings(r#"{"rules": {"broken": {"given": "paths", "then": {"function": "truthy"}}}}"#);
+        assert_eq!(errors, 1);
+        assert_eq!(findings[0].0, "broken");
+        assert!(findings[0].2.starts_with("The given of the rule is not valid."));
+    }
+
+    #[test]
+    fn checks_values_with_each_function() {
+        let value = |text: &str| json(text);
+        assert!(check_with("truthy", "null", Some(&value("[]"))).is_ok());
+        assert!(check_with("truthy", "null", Some(&value("0"))).is_err());
+        assert!(check_with("falsy", "null", Some(&value("\"\""))).is_ok());
+        assert!(check_with("falsy", "null", None).is_ok());
+        assert!(check_with("undefined", "null", Some(&value("null"))).is_err());
+        assert!(check_with("pattern", r#"{"match": "^[a-z]+$"}"#, Some(&value("\"pets\""))).is_ok());
+        assert!(check_with("pattern", r#"{"match": "("}"#, Some(&value("\"pets\""))).is_err());
+        assert!(check_with("enumeration", r#"{"values": ["a", "b"]}"#, Some(&value("\"c\""))).is_err());
+        assert!(check_with("enumeration", "null", Some(&value("\"c\""))).is_err());
+        assert!(check_with("length", r#"{"max": 2}"#, Some(&value("[1, 2, 3]"))).is_err());
+        assert!(check_with("length", r#"{"max": 2}"#, Some(&value("true"))).is_ok());
+        assert!(check_with("casing", r#"{"type": "pascal"}"#, Some(&value("\"UserGroups\""))).is_ok());
+        assert!(check_with("casing", r#"{"type": "upper"}"#, Some(&value("\"A\""))).is_err());
+        // Only truthy and defined fail on missing values.
+        assert!(check_with("pattern", r#"{"match": "x"}"#, None).is_ok());
+    }
+
+    #[test]
+    fn matches_schemas() {
+        let schema = json(r#"{
+            "type": "object", "required": ["name"],
+            "properties": {"name": {"type": "string", "minLength": 2}, "tags": {"type": "array", "items": {"enum": ["a", "b"]}, "contains": {"const": "x", "enum": ["a"]}}}
+        }"#);
+        assert!(match_schema(&schema, &json(r#"{"name": "ab", "tags": ["b", "a"]}"#)).is_ok());
+        assert_eq!(match_schema(&schema, &json(r#"{"tags": []}"#)), Err("name is required".to_string()));
+        assert_eq!(match_schema(&schema, &json(r#"{"name": "a"}"#)), Err(r#"name: the length of "a" is out of minLength and maxLength"#.to_string()));
+        assert_eq!(match_schema(&schema, &json(r#"{"name": "ab", "tags": ["b", "c"]}"#)), Err(r#"tags: [1]: "c" is not one of ["a","b"]"#.to_string()));
+        assert_eq!(match_schema(&schema, &json(r#"{"name": "ab", "tags": ["b"]}"#)), Err(r#"tags: no element matches {"const":"x","enum":["a"]}"#.to_string()));
+        assert_eq!(match_schema(&schema, &json("[]")), Err("[] is not of type object".to_string()));
+    }
+}
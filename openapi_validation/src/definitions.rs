@@ -0,0 +1,173 @@
+use openapi_examples::schema::{bound, is_of_type};
+use openapi_extensions::pointer;
+use serde_json::Value;
+
+use {NamingRules, OpenAPIValidation, ValidationOptions};
+use validation_results::ValidationResults;
+
+// Formats of the OpenAPI specification plus the JSON Schema ones in common use.
+const KNOWN_FORMATS: [&str; 16] = [
+    "int32", "int64", "float", "double", "byte", "binary", "date", "date-time", "password",
+    "email", "hostname", "ipv4", "ipv6", "uri", "uuid", "time",
+];
+
+const BOUNDS: [(&str, &str); 4] = [
+    ("minimum", "maximum"),
+    ("minLength", "maxLength"),
+    ("minItems", "maxItems"),
+    ("minProperties", "maxProperties"),
+];
+
+// Works on the definitions as they were read because the typed schemas of the
+// openapi crate do not keep bounds and only keep enums of strings.
+pub struct Definitions<'a> {
+    pub definitions: Option<&'a Value>,
+}
+
+impl<'a> OpenAPIValidation for Definitions<'a> {
+    fn validate(&self, options: &ValidationOptions) -> ValidationResults {
+        let mut r = ValidationResults::new();
+        let definitions = self.definitions.and_then(|definitions| definitions.as_object());
+
+        for (name, definition) in definitions.into_iter().flat_map(|definitions| definitions.iter()) {
+            let at = pointer(&["definitions", name]);
+            let location = format!("#{}", at);
+            // The Google conversion needs a type and properties in every definition.
+            if definition.get("type").is_none() && definition.get("allOf").is_none() && definition.get("$ref").is_none() {
+                r.assert("definition-type", &at, &Err(format!("Expected a type in {}", location)));
+            }
+            if options.support_google_spec && definition.get("properties").is_none() {
+                r.assert("definition-properties", &at, &Err(format!("Expected properties in {}", location)));
+            }
+            let result = options.naming.check(options.naming.definitions, name, "the definition", "#/definitions");
+            r.assert("naming-definition", &at, &result);
+            validate_schema(definition, &at, &options.naming, &mut r);
+        }
+        r
+    }
+}
+
+// at is the JSON pointer of the schema.
+fn validate_schema(schema: &Value, at: &str, naming: &NamingRules, r: &mut ValidationResults) {
+    if !schema.is_object() || schema.get("$ref").is_some() {
+        return;
+    }
+    let location = format!("#{}", at);
+    let schema_type = schema.get("type").and_then(|schema_type| schema_type.as_str());
+    let properties = schema.get("properties").and_then(|properties| properties.as_object());
+
+    let required = schema.get("required").and_then(|required| required.as_array());
+    for name in required.into_iter().flat_map(|required| required.iter()).filter_map(|name| name.as_str()) {
+        if !properties.map_or(false, |properties| properties.contains_key(name)) {
+            r.assert("definition-required", at, &Err(format!("The required property {} in {} is not one of its properties", name, location)));
+        }
+    }
+
+    if schema_type == Some("array") && schema.get("items").is_none() {
+        r.assert("definition-array-items", at, &Err(format!("Expected items in {} because it is an array", location)));
+    }
+
+    if let (Some(schema_type), Some(values)) = (schema_type, schema.get("enum").and_then(|values| values.as_array())) {
+        for value in values.iter().filter(|value| !is_of_type(value, schema_type)) {
+            r.assert("definition-enum-type", at, &Err(format!("The enum value {} in {} is not of type {}", value, location, schema_type)));
+        }
+    }
+
+    if let Some(format) = schema.get("format").and_then(|format| format.as_str()) {
+        if !KNOWN_FORMATS.contains(&format) {
+            r.assert_warn("definition-format", at, &Err(format!("The format {} in {} is not a known format", format, location)));
+        }
+    }
+
+    for &(min, max) in &BOUNDS {
+        if let (Some(lower), Some(upper)) = (bound(schema, min), bound(schema, max)) {
+            if lower > upper {
+                r.assert("definition-bounds", at, &Err(format!("Expected {} to be at most {} in {} but {} > {}", min, max, location, lower, upper)));
+            }
+        }
+    }
+
+    for (name, property) in properties.into_iter().flat_map(|properties| properties.iter()) {
+        let property_at = format!("{}{}", at, pointer(&["properties", name]));
+        r.assert("naming-property", &property_at, &naming.check(naming.properties, name, "the property", &location));
+        validate_schema(property, &property_at, naming, r);
+    }
+    if let Some(items) = schema.get("items") {
+        validate_schema(items, &format!("{}/items", at), naming, r);
+    }
+    let all_of = schema.get("allOf").and_then(|all_of| all_of.as_array());
+    for (index, part) in all_of.into_iter().flat_map(|all_of| all_of.iter()).enumerate() {
+        validate_schema(part, &format!("{}/allOf/{}", at, index), naming, r);
+    }
+    if let Some(additional) = schema.get("additionalProperties") {
+        validate_schema(additional, &format!("{}/additionalProperties", at), naming, r);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    fn findings(definitions: &str, options: &ValidationOptions) -> Vec<(String, String)> {
+        let definitions: Value = serde_json::from_str(definitions).unwrap();
+        Definitions { definitions: Some(&definitions) }
+            .validate(options)
+            .findings()
+            .into_iter()
+            .map(|f| (f.rule.to_string(), f.location.to_string()))
+            .collect()
+    }
+
+    fn finding(rule: &str, location: &str) -> (String, String) {
+        (rule.to_string(), location.to_string())
+    }
+
+    #[test]
+    fn accepts_good_definitions() {
+        let definitions = r##"{
+            "Pet": {"type": "object", "required": ["name"], "properties": {
+                "name": {"type": "string", "minLength": 1, "maxLength": 10},
+                "kind": {"type": "string", "enum": ["cat", "dog"]},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "owner": {"$ref": "#/definitions/Owner"}
+            }},
+            "Owner": {"allOf": [{"$ref": "#/definitions/Pet"}]}
+        }"##;
+        assert!(findings(definitions, &ValidationOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn reports_each_rule_where_it_happens() {
+        let definitions = r#"{
+            "Pet": {"required": ["name"], "properties": {
+                "age": {"type": "integer", "enum": [1, 1.5, "2"], "minimum": 5, "maximum": 1},
+                "tags": {"type": "array"},
+                "born": {"type": "string", "format": "birthday"}
+            }}
+        }"#;
+        assert_eq!(findings(definitions, &ValidationOptions::default()), vec![
+            finding("definition-type", "/definitions/Pet"),
+            finding("definition-required", "/definitions/Pet"),
+            finding("definition-enum-type", "/definitions/Pet/properties/age"),
+            finding("definition-enum-type", "/definitions/Pet/properties/age"),
+            finding("definition-bounds", "/definitions/Pet/properties/age"),
+            finding("definition-array-items", "/definitions/Pet/properties/tags"),
+            finding("definition-format", "/definitions/Pet/properties/born"),
+        ]);
+    }
+
+    #[test]
+    fn checks_nested_schemas() {
+        let definitions = r#"{"Pets": {"type": "array", "items": {"type": "object", "additionalProperties": {"type": "array"}}}}"#;
+        assert_eq!(findings(definitions, &ValidationOptions::default()), vec![
+            finding("definition-array-items", "/definitions/Pets/items/additionalProperties"),
+        ]);
+    }
+
+    #[test]
+    fn asks_for_properties_for_google() {
+        let options = ValidationOptions { support_google_spec: true, ..ValidationOptions::default() };
+        assert_eq!(findings(r#"{"Empty": {"type": "object"}}"#, &options), vec![finding("definition-properties", "/definitions/Empty")]);
+    }
+}
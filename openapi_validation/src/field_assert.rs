@@ -81,6 +81,17 @@ impl<'a> Field<&'a Option<String>> {
         }
 
     }
+    pub fn not_placeholder(&self, placeholders: &[&str]) -> ValidationResult {
+        match self.data() {
+            Some(string) if placeholders.contains(&string.as_str()) => Err(format!(
+                "{} still has the placeholder value {}. Replace it with a real value.",
+                self.name(),
+                string
+            )),
+            _ => Ok(()),
+        }
+    }
+
     pub fn is_match(&self, regex: &Regex) -> ValidationResult {
         match self.data() {
             Some(string) => {
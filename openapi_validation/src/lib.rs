@@ -1,26 +1,121 @@
 extern crate openapi;
+extern crate openapi_examples;
+extern crate openapi_extensions;
+extern crate json_path;
 extern crate regex;
 extern crate inflector;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
 
 use regex::Regex;
 use std::collections::BTreeMap;
-use inflector::Inflector;
+use openapi_extensions::{Document, Extensions};
 
 mod validation_results;
-use validation_results::ValidationResults;
+pub use validation_results::{Finding, ValidationResults};
 
 mod field_assert;
 use field_assert::{Field, Assert};
 
-#[derive(Default)]
+mod responses;
+pub use responses::ResponseRules;
+use responses::PathResponses;
+
+mod parameters;
+use parameters::{OperationParameters, PathParameters, LOCATIONS};
+
+mod definitions;
+use definitions::Definitions;
+
+mod naming;
+pub use naming::{Case, NamingRules};
+
+mod custom_rules;
+pub use custom_rules::{CustomRule, Ruleset};
+use custom_rules::CustomRules;
+
+// Values written by the conversion from Google discovery files because there is
+// no way to know them. They need to be replaced by the user.
+pub const PLACEHOLDERS: [&str; 4] = ["PLEASE.SETHOST.com", "<YOUR NAME>", "EMAIL@YOURDOMAIN.COM", "NOTSET"];
+
+// The extension with the rules whose findings are not reported for an object:
+// x-oatool-ignore: [summary-length, operation-id-absent]
+const IGNORE: &str = "x-oatool-ignore";
+
+// Also the validation profile, a file with these same keys. Missing keys keep their default.
+#[derive(Default, Deserialize)]
+#[serde(default)]
 pub struct ValidationOptions {
-    pub support_google_spec: bool
+    pub support_google_spec: bool,
+    pub responses: ResponseRules,
+    pub naming: NamingRules,
+    // By name, see custom_rules.
+    pub rules: BTreeMap<String, CustomRule>,
 }
 
 pub trait OpenAPIValidation {
     fn validate(&self, options: &ValidationOptions) -> ValidationResults;
 }
 
+// The spec and the vendor extensions it came with.
+impl OpenAPIValidation for Document {
+    fn validate(&self, options: &ValidationOptions) -> ValidationResults {
+        let mut r = ValidationResults::new();
+        r.validate(&self.spec, options);
+        r.validate(&Definitions{ definitions: self.source.get("definitions") }, options);
+        r.validate(&self.extensions, options);
+        r.validate(&CustomRules{ rules: &options.rules, document: &self.source }, options);
+
+        let ignored = ignored_rules(&self.extensions);
+        r.suppress(|finding| {
+            ignored.iter().any(|&(pointer, ref rules)| is_under(&finding.location, pointer) && rules.contains(&finding.rule.as_str()))
+        });
+        r
+    }
+}
+
+// (JSON pointer, rules) of every x-oatool-ignore. They apply to the object and everything under it.
+fn ignored_rules(extensions: &Extensions) -> Vec<(&str, Vec<&str>)> {
+    extensions
+        .iter()
+        .filter_map(|(pointer, extensions)| {
+            extensions.get(IGNORE).and_then(|rules| rules.as_array()).map(|rules| {
+                (pointer.as_str(), rules.iter().filter_map(|rule| rule.as_str()).collect())
+            })
+        })
+        .collect()
+}
+
+fn is_under(location: &str, pointer: &str) -> bool {
+    location == pointer || location.starts_with(&format!("{}/", pointer))
+}
+
+impl OpenAPIValidation for Extensions {
+    fn validate(&self, _: &ValidationOptions) -> ValidationResults {
+        let mut r = ValidationResults::new();
+
+        for (pointer, extensions) in self.iter() {
+            let location = if pointer.is_empty() { "/" } else { pointer };
+            for (name, value) in extensions {
+                let extension = format!("{}{}", pointer, openapi_extensions::pointer(&[name]));
+                if name.len() == "x-".len() {
+                    r.assert("extension-name", &extension, &Err(format!("Expected a name after x- in the extension in '{}'", location)));
+                }
+                if value.is_null() {
+                    r.assert_warn("extension-value", &extension, &Err(format!("Expected a value for the extension {} in '{}'", name, location)));
+                }
+                let is_rule_list = value.as_array().map_or(false, |rules| rules.iter().all(|rule| rule.is_string()));
+                if name == IGNORE && !is_rule_list {
+                    r.assert("extension-value", &extension, &Err(format!("Expected {} in '{}' to be a list of rules", name, location)));
+                }
+            }
+        }
+        r
+    }
+}
+
 // Based on the openapi crate, there are some fields we know they exist or
 // deserialization itself would have failed. We do not test for those.
 impl OpenAPIValidation for openapi::Spec {
@@ -39,38 +134,37 @@ impl OpenAPIValidation for openapi::Spec {
         let parameters  = Field::new(&self.parameters, "parameters block");
 
 
-        r.assert(&swagger.eq("2.0"));
+        r.assert("swagger-version", "/swagger", &swagger.eq("2.0"));
 
-        r.assert_warn(&host.not_exist());
+        r.assert_warn("host-absent", "/host", &host.not_exist());
+        r.assert("host-placeholder", "/host", &host.not_placeholder(&PLACEHOLDERS));
 
-        r.assert(&base_path.exist());
-        r.assert(&base_path.is_match(&Regex::new(r"^/\w*(/\w+)*$").unwrap()));
+        r.assert("base-path-exists", "/basePath", &base_path.exist());
+        r.assert("base-path-format", "/basePath", &base_path.is_match(&Regex::new(r"^/\w*(/\w+)*$").unwrap()));
 
-        r.assert(&schemes.exist());
-        r.assert(&schemes.eq(["https"]));
+        r.assert("schemes-exist", "/schemes", &schemes.exist());
+        r.assert("schemes-https", "/schemes", &schemes.eq(["https"]));
 
-        r.assert(&schemes.exist());
-        r.assert(&schemes.eq(["https"]));
+        r.assert("consumes-exist", "/consumes", &consumes.exist());
+        r.assert("consumes-json", "/consumes", &consumes.eq(["application/json"]));
 
-        r.assert(&consumes.exist());
-        r.assert(&consumes.eq(["application/json"]));
+        r.assert("produces-exist", "/produces", &produces.exist());
+        r.assert("produces-json", "/produces", &produces.eq(["application/json"]));
 
-        r.assert(&produces.exist());
-        r.assert(&produces.eq(["application/json"]));
+        r.validate_at(&self.info, "/info", options);
 
-        r.validate(&self.info, options);
-
-        r.validate(&self.paths, options);
+        r.validate(&Paths{ spec: self }, options);
 
         // In theory this may not exist but maybe should be strait assertions
-        r.assert_warn(&definitions.exist());
-        r.assert_warn(&parameters.exist());
+        r.assert_warn("definitions-exist", "/definitions", &definitions.exist());
+        r.assert_warn("parameters-exist", "/parameters", &parameters.exist());
 
         r
     }
 }
 
 
+// Its locations are relative to the info block.
 impl OpenAPIValidation for openapi::Info {
     fn validate(&self, options: &ValidationOptions) -> ValidationResults {
         let mut r = ValidationResults::new();
@@ -81,21 +175,21 @@ impl OpenAPIValidation for openapi::Info {
         let terms_of_service = Field::new(&self.terms_of_service, "info.terms_of_service");
         let license          = Field::new(&self.license, "info.license");
 
-        r.assert(&title.exist());
+        r.assert("info-title", "/title", &title.exist());
 
-        r.assert(&description.exist());
+        r.assert("info-description", "/description", &description.exist());
 
         if options.support_google_spec {
-            r.assert(&version.exist());
+            r.assert("info-version", "/version", &version.exist());
         }
 
-        r.assert_warn(&terms_of_service.not_exist());
-        r.assert_warn(&license.not_exist());
+        r.assert_warn("info-terms-of-service-absent", "/termsOfService", &terms_of_service.not_exist());
+        r.assert_warn("info-license-absent", "/license", &license.not_exist());
 
-        r.assert(&contact.exist());
+        r.assert("info-contact", "/contact", &contact.exist());
 
         self.contact.as_ref().map(|contact| {
-            r.validate(contact, options)
+            r.validate_at(contact, "/contact", options)
         });
 
         r
@@ -103,57 +197,79 @@ impl OpenAPIValidation for openapi::Info {
 }
 
 
+// Its locations are relative to the contact block.
 impl OpenAPIValidation for openapi::Contact {
     fn validate(&self, _: &ValidationOptions) -> ValidationResults {
         let mut r = ValidationResults::new();
         let contact_name  = Field::new(&self.name, "info.contact.name");
         let contact_email = Field::new(&self.email, "info.contact.email");
 
-        r.assert(&contact_name.exist());
-        r.assert(&contact_email.exist());
+        r.assert("contact-name", "/name", &contact_name.exist());
+        r.assert("contact-email", "/email", &contact_email.exist());
+        r.assert("contact-name-placeholder", "/name", &contact_name.not_placeholder(&PLACEHOLDERS));
+        r.assert("contact-email-placeholder", "/email", &contact_email.not_placeholder(&PLACEHOLDERS));
 
         r
     }
 }
 
+struct Paths<'a> {
+    spec: &'a openapi::Spec,
+}
+
 struct PathOperation<'a> {
+    spec: &'a openapi::Spec,
     path: &'a str,
+    verb: &'a str,
     operation: &'a Option<openapi::Operation>,
+    // Shared by every operation of the path.
+    parameters: &'a Option<Vec<openapi::ParameterOrRef>>,
 }
 
-// Paths
-impl OpenAPIValidation for BTreeMap<String, openapi::Operations> { //openapi::Operations {
+impl<'a> OpenAPIValidation for Paths<'a> {
     fn validate(&self, options: &ValidationOptions) -> ValidationResults {
         let mut r = ValidationResults::new();
 
-        for (the_path, the_oper) in self {
-            let path = the_path.to_string(); // TODO
-            let operations = the_oper.clone();
-            r.assert(&Field::new(&path, "path name").eq(&path.to_snake_case()));
-
-            r.validate(&PathOperation{ path:&path, operation: &operations.get }, options);
-            r.validate(&PathOperation{ path:&path, operation: &operations.post }, options);
-            r.validate(&PathOperation{ path:&path, operation: &operations.put }, options);
-            r.validate(&PathOperation{ path:&path, operation: &operations.patch }, options);
-            r.validate(&PathOperation{ path:&path, operation: &operations.delete }, options);
-            r.validate(&PathOperation{ path:&path, operation: &operations.options }, options);
-            r.validate(&PathOperation{ path:&path, operation: &operations.head }, options);
+        for (path, operations) in &self.spec.paths {
+            let location = openapi_extensions::pointer(&["paths", path]);
+            // Templated segments are parameters, checked with them.
+            let segments = path.split('/').filter(|segment| !segment.is_empty() && !segment.starts_with('{'));
+            for segment in segments {
+                let result = options.naming.check(options.naming.path_segments, segment, "the path segment", &format!("'{}'", path));
+                r.assert("naming-path-segment", &location, &result);
+            }
+            r.validate(&PathParameters{ spec: self.spec, path: path, parameters: &operations.parameters }, options);
+
+            let verb_operations = [
+                ("get", &operations.get),
+                ("post", &operations.post),
+                ("put", &operations.put),
+                ("patch", &operations.patch),
+                ("delete", &operations.delete),
+                ("options", &operations.options),
+                ("head", &operations.head),
+            ];
+            for &(verb, operation) in &verb_operations {
+                r.validate(&PathOperation{
+                    spec: self.spec,
+                    path: path,
+                    verb: verb,
+                    operation: operation,
+                    parameters: &operations.parameters,
+                }, options);
+            }
         }
         r
     }
 }
 
 
-// struct PathResponses<'a> {
-//     path: &'a str,
-//     results: &'a BTreeMap<String, openapi::Response>,
-// }
-
-
 impl<'a> OpenAPIValidation for PathOperation<'a> {
     fn validate(&self, options: &ValidationOptions) -> ValidationResults {
         let mut r = ValidationResults::new();
         let path = self.path;
+        let location = openapi_extensions::pointer(&["paths", path, self.verb]);
+        let at = |key: &str| format!("{}/{}", location, key);
         let operation = self.operation.clone();
         // TODO: avoid clone
         match operation {
@@ -166,22 +282,34 @@ impl<'a> OpenAPIValidation for PathOperation<'a> {
                 let produces     = Field::new(&operation.produces, &format!("produces in '{}'", path));
                 let operation_id = Field::new(&operation.operation_id, &format!("operation_id in '{}'", path));
 
-                r.assert(&summary.exist());
-                r.assert(&summary.length_less_than(120));
+                r.assert("summary-exists", &at("summary"), &summary.exist());
+                r.assert("summary-length", &at("summary"), &summary.length_less_than(120));
                 // if operation.summary.is_none() {
                 //     r.assert(&description.exist());
                 // }
-                r.assert_warn(&schemes.not_exist());
-                r.assert_warn(&consumes.not_exist());
-                r.assert_warn(&produces.not_exist());
+                r.assert_warn("operation-schemes-absent", &at("schemes"), &schemes.not_exist());
+                r.assert_warn("operation-consumes-absent", &at("consumes"), &consumes.not_exist());
+                r.assert_warn("operation-produces-absent", &at("produces"), &produces.not_exist());
                 if options.support_google_spec {
-                    r.assert(&operation_id.exist());
+                    r.assert("operation-id-exists", &at("operationId"), &operation_id.exist());
                 } else {
-                    r.assert_warn(&operation_id.not_exist());
+                    r.assert_warn("operation-id-absent", &at("operationId"), &operation_id.not_exist());
+                }
+                if let Some(ref id) = operation.operation_id {
+                    for part in id.split('.') {
+                        let result = options.naming.check(options.naming.operation_ids, part, "the operationId part", id);
+                        r.assert("naming-operation-id", &at("operationId"), &result);
+                    }
                 }
 
-                // responses
-                // parameters
+                r.validate(&PathResponses{ spec: self.spec, path: path, verb: self.verb, responses: &operation.responses }, options);
+                r.validate(&OperationParameters{
+                    spec: self.spec,
+                    path: path,
+                    verb: self.verb,
+                    path_parameters: self.parameters,
+                    operation_parameters: &operation.parameters,
+                }, options);
 
                 r
             },
@@ -190,35 +318,31 @@ impl<'a> OpenAPIValidation for PathOperation<'a> {
 }
 
 
-// impl<'a> OpenAPIValidation for PathResponses<'a> {
-//     fn validate(&self, options: &ValidationOptions) -> ValidationResults {
-//         let mut r = ValidationResults::new();
-//         let path = self.path;
-//         let results = self.results.clone();
-
-
-
-//         r
-//     }
-// }
-
-
+// Its locations are relative to the parameter.
 impl OpenAPIValidation for openapi::Parameter {
     fn validate(&self, _: &ValidationOptions) -> ValidationResults {
         let mut r = ValidationResults::new();
-        let name  = Field::new(&self.name, &format!("parameter.name {}", &self.name));
-        let location = Field::new(&self.location, &format!("parameter.in {}", &self.name));
        // let required = Field::new(&self.required, &format!("parameter.required {}", &self.name));
         let param_type = Field::new(&self.param_type, &format!("parameter.param_type {}", &self.name));
         let description = Field::new(&self.description, &format!("parameter.description {}", &self.name));
         //  let format =
         // TODO : default (openapi library does not support?)
-        if location.data().is_some() && location.data().unwrap() != "header" {
-            r.assert(&name.eq(&self.name.to_snake_case()));
+        // The case of the name is checked with the operation, see OperationParameters.
+        if !LOCATIONS.contains(&self.location.as_str()) {
+            r.assert("parameter-location", "/in", &Err(format!(
+                "Expected parameter.in {} to be one of {:?} but it was {:?}",
+                &self.name,
+                LOCATIONS,
+                &self.location
+            )));
+        }
+        // Body parameters are described by their schema.
+        if self.location == "body" {
+            r.assert("parameter-schema", "/schema", &Field::new(&self.schema, &format!("parameter.schema {}", &self.name)).exist());
+        } else {
+            r.assert("parameter-type", "/type", &param_type.exist());
         }
-       // r.assert(&location.included_in(["header", "body", "query", "path"]));
-        r.assert(&param_type.exist());
-        r.assert(&description.exist());
+        r.assert("parameter-description", "/description", &description.exist());
 
         r
     }
@@ -240,4 +364,91 @@ impl OpenAPIValidation for openapi::Parameter {
 //     t.reset().unwrap();
 //     t
 //     //format!("{} {}", key_name, location)
-// }
\ No newline at end of file
+// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (rule, location) of the findings of a document with the findings of rules.
+    fn findings(document: &str, options: &ValidationOptions, rules: &[&str]) -> Vec<(String, String)> {
+        let document = Document::from_value(serde_json::from_str(document).unwrap()).unwrap();
+        document
+            .validate(options)
+            .findings()
+            .into_iter()
+            .filter(|f| rules.contains(&f.rule.as_str()))
+            .map(|f| (f.rule.to_string(), f.location.to_string()))
+            .collect()
+    }
+
+    fn finding(rule: &str, location: &str) -> (String, String) {
+        (rule.to_string(), location.to_string())
+    }
+
+    const NAMES: &str = r#"{
+        "swagger": "2.0", "info": {"title": "t", "version": "1"},
+        "paths": {"/userGroups/{id}/members": {"get": {"operationId": "userGroups.listMembers", "responses": {"200": {"description": "ok"}}}}}
+    }"#;
+
+    #[test]
+    fn checks_names_of_paths_and_operation_ids() {
+        let rules = ["naming-path-segment", "naming-operation-id"];
+        assert_eq!(findings(NAMES, &ValidationOptions::default(), &rules), vec![
+            finding("naming-path-segment", "/paths/~1userGroups~1{id}~1members"),
+        ]);
+
+        let options = ValidationOptions {
+            naming: NamingRules { path_segments: Case::Camel, operation_ids: Case::Snake, ..NamingRules::default() },
+            ..ValidationOptions::default()
+        };
+        assert_eq!(findings(NAMES, &options, &rules), vec![
+            finding("naming-operation-id", "/paths/~1userGroups~1{id}~1members/get/operationId"),
+            finding("naming-operation-id", "/paths/~1userGroups~1{id}~1members/get/operationId"),
+        ]);
+    }
+
+    const IGNORED: &str = r#"{
+        "swagger": "2.0", "info": {"title": "t", "version": "1"},
+        "x-oatool-ignore": ["host-absent"],
+        "host": "example.com",
+        "paths": {
+            "/pets": {
+                "x-oatool-ignore": ["summary-exists"],
+                "get": {"responses": {"200": {"description": "ok"}}}
+            },
+            "/petsFood": {
+                "get": {"x-oatool-ignore": "summary-exists", "responses": {"200": {"description": "ok"}}}
+            }
+        }
+    }"#;
+
+    #[test]
+    fn ignores_rules_under_the_objects_which_say_so() {
+        let rules = ["host-absent", "summary-exists", "extension-value"];
+        assert_eq!(findings(IGNORED, &ValidationOptions::default(), &rules), vec![
+            finding("summary-exists", "/paths/~1petsFood/get/summary"),
+            finding("extension-value", "/paths/~1petsFood/get/x-oatool-ignore"),
+        ]);
+    }
+
+    #[test]
+    fn matches_locations_under_a_pointer() {
+        assert!(is_under("/paths/~1pets/get", "/paths/~1pets"));
+        assert!(is_under("/paths/~1pets", "/paths/~1pets"));
+        assert!(!is_under("/paths/~1petsFood", "/paths/~1pets"));
+        assert!(is_under("/host", ""));
+    }
+
+    #[test]
+    fn suppresses_known_findings() {
+        let document = Document::from_value(serde_json::from_str(IGNORED).unwrap()).unwrap();
+        let mut results = document.validate(&ValidationOptions::default());
+        let known: Vec<Finding> = serde_json::from_str(r#"[
+            {"rule": "summary-exists", "location": "/paths/~1petsFood/get/summary", "message": "Changed since"}
+        ]"#).unwrap();
+        let count = results.findings().len();
+        results.suppress(|finding| known.iter().any(|known| known.same_as(finding)));
+        assert_eq!(results.findings().len(), count - 1);
+        assert!(!results.findings().iter().any(|finding| finding.location == "/paths/~1petsFood/get/summary"));
+    }
+}
@@ -0,0 +1,133 @@
+use inflector::Inflector;
+use std::fmt;
+
+use validation_results::ValidationResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Case {
+    // Not checked.
+    Any,
+    // user_groups
+    Snake,
+    // userGroups
+    Camel,
+    // user-groups
+    Kebab,
+    // UserGroups
+    Pascal,
+    // User-Groups, usual for headers
+    Train,
+}
+
+impl Case {
+    pub fn matches(&self, name: &str) -> bool {
+        name == self.apply(name)
+    }
+
+    // The name written in this case, Any leaves it as it is.
+    pub fn apply(&self, name: &str) -> String {
+        match *self {
+            Case::Any => name.to_string(),
+            Case::Snake => name.to_snake_case(),
+            Case::Camel => name.to_camel_case(),
+            Case::Kebab => name.to_kebab_case(),
+            Case::Pascal => name.to_pascal_case(),
+            Case::Train => name.to_train_case(),
+        }
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Case::Any => "any case",
+            Case::Snake => "snake_case",
+            Case::Camel => "camelCase",
+            Case::Kebab => "kebab-case",
+            Case::Pascal => "PascalCase",
+            Case::Train => "Train-Case",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Each kind of name has its own case. The defaults are the rules oatool always had.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct NamingRules {
+    pub path_segments: Case,
+    // Query, path and formData parameters.
+    pub parameters: Case,
+    pub headers: Case,
+    pub definitions: Case,
+    pub properties: Case,
+    // Checked for each part of dotted ids (users.list).
+    pub operation_ids: Case,
+    // Names accepted whatever their case, for the exceptions.
+    pub allow: Vec<String>,
+}
+
+impl Default for NamingRules {
+    fn default() -> NamingRules {
+        NamingRules {
+            path_segments: Case::Snake,
+            parameters: Case::Snake,
+            headers: Case::Any,
+            definitions: Case::Any,
+            properties: Case::Any,
+            operation_ids: Case::Any,
+            allow: vec![],
+        }
+    }
+}
+
+impl NamingRules {
+    // what describes the name: "the path segment", "the header"...
+    pub fn check(&self, case: Case, name: &str, what: &str, location: &str) -> ValidationResult {
+        if case.matches(name) || self.allow.iter().any(|allowed| allowed == name) {
+            Ok(())
+        } else {
+            Err(format!("Expected {} {} in {} to be {}", what, name, location, case))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn matches_cases() {
+        assert!(Case::Snake.matches("user_groups"));
+        assert!(!Case::Snake.matches("userGroups"));
+        assert!(Case::Camel.matches("userGroups"));
+        assert!(Case::Kebab.matches("user-groups"));
+        assert!(Case::Pascal.matches("UserGroups"));
+        assert!(Case::Train.matches("User-Groups"));
+        assert!(!Case::Train.matches("user-groups"));
+        assert!(Case::Any.matches("whatEver_-"));
+        assert_eq!(Case::Kebab.apply("UserGroups"), "user-groups");
+    }
+
+    #[test]
+    fn allows_exceptions() {
+        let rules = NamingRules { allow: vec!["ETag".to_string()], ..NamingRules::default() };
+        assert!(rules.check(Case::Train, "ETag", "the header", "GET '/'").is_ok());
+        assert_eq!(
+            rules.check(Case::Train, "x_trace", "the header", "GET '/'"),
+            Err("Expected the header x_trace in GET '/' to be Train-Case".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_profiles_keeping_defaults() {
+        let rules: NamingRules = serde_json::from_str(r#"{"headers": "train", "operation_ids": "camel"}"#).unwrap();
+        assert_eq!(rules.headers, Case::Train);
+        assert_eq!(rules.operation_ids, Case::Camel);
+        assert_eq!(rules.path_segments, Case::Snake);
+        assert_eq!(rules.parameters, Case::Snake);
+        assert!(serde_json::from_str::<NamingRules>(r#"{"headers": "screaming"}"#).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use openapi;
+use openapi_extensions::pointer;
+use regex::Regex;
+
+use {OpenAPIValidation, ValidationOptions};
+use validation_results::ValidationResults;
+
+pub const LOCATIONS: [&str; 5] = ["query", "header", "path", "formData", "body"];
+
+// The parameters shared by every operation of a path, checked once for the path item.
+pub struct PathParameters<'a> {
+    pub spec: &'a openapi::Spec,
+    pub path: &'a str,
+    pub parameters: &'a Option<Vec<openapi::ParameterOrRef>>,
+}
+
+impl<'a> OpenAPIValidation for PathParameters<'a> {
+    fn validate(&self, options: &ValidationOptions) -> ValidationResults {
+        let mut r = ValidationResults::new();
+        let path = format!("'{}'", self.path);
+        let location = pointer(&["paths", self.path, "parameters"]);
+        let templated = templated(self.path);
+        for (parameter, at) in resolve_all(self.spec, self.parameters, &location, &path, &mut r) {
+            check(&parameter, &at, &path, &templated, options, &mut r);
+        }
+        r
+    }
+}
+
+// The parameters of an operation, including the ones shared by every operation of the path.
+pub struct OperationParameters<'a> {
+    pub spec: &'a openapi::Spec,
+    pub path: &'a str,
+    pub verb: &'a str,
+    pub path_parameters: &'a Option<Vec<openapi::ParameterOrRef>>,
+    pub operation_parameters: &'a Option<Vec<openapi::ParameterOrRef>>,
+}
+
+impl<'a> OpenAPIValidation for OperationParameters<'a> {
+    fn validate(&self, options: &ValidationOptions) -> ValidationResults {
+        let mut r = ValidationResults::new();
+        let operation = format!("{} '{}'", self.verb.to_uppercase(), self.path);
+        let location = pointer(&["paths", self.path, self.verb]);
+        let templated = templated(self.path);
+
+        let mut parameters = resolve_all(self.spec, self.operation_parameters, &format!("{}/parameters", location), &operation, &mut r);
+        for &(ref parameter, ref at) in &parameters {
+            check(parameter, at, &operation, &templated, options, &mut r);
+        }
+        // Operation parameters override the path ones with the same name and location.
+        // The path ones are checked with the path item, see PathParameters.
+        let path_location = pointer(&["paths", self.path, "parameters"]);
+        for (parameter, at) in resolve_all(self.spec, self.path_parameters, &path_location, &operation, &mut ValidationResults::new()) {
+            if !parameters.iter().any(|&(ref p, _)| p.name == parameter.name && p.location == parameter.location) {
+                parameters.push((parameter, at));
+            }
+        }
+
+        for name in &templated {
+            if !parameters.iter().any(|&(ref p, _)| p.location == "path" && &p.name == name) {
+                r.assert("path-parameter-missing", &location, &Err(format!(
+                    "Expected a path parameter {} for the template in {}",
+                    name,
+                    operation
+                )));
+            }
+        }
+
+        let count = |kind: &str| parameters.iter().filter(|&&(ref p, _)| p.location == kind).count();
+        if count("body") > 1 {
+            r.assert("parameter-single-body", &location, &Err(format!(
+                "Expected at most one body parameter in {} but there are {}",
+                operation,
+                count("body")
+            )));
+        }
+        if count("body") > 0 && count("formData") > 0 {
+            r.assert("parameter-body-form-data", &location, &Err(format!("{} mixes body and formData parameters", operation)));
+        }
+
+        r
+    }
+}
+
+// The checks of a parameter where it is declared: its own, the case of its
+// name and, for path parameters, the template of the path.
+fn check(parameter: &openapi::Parameter, at: &str, operation: &str, templated: &[&str], options: &ValidationOptions, r: &mut ValidationResults) {
+    r.validate_at(parameter, at, options);
+    let naming = &options.naming;
+    let naming_rule = match parameter.location.as_str() {
+        "header" => Some(("naming-header", naming.headers)),
+        // The name of the body parameter does not appear in the requests.
+        "body" => None,
+        _ => Some(("naming-parameter", naming.parameters)),
+    };
+    if let Some((rule, case)) = naming_rule {
+        let what = format!("the {} parameter", parameter.location);
+        r.assert(rule, &format!("{}/name", at), &naming.check(case, &parameter.name, &what, operation));
+    }
+
+    if parameter.location != "path" {
+        return;
+    }
+    if !templated.contains(&parameter.name.as_str()) {
+        r.assert("path-parameter-unused", at, &Err(format!(
+            "The path parameter {} in {} is not in the path template",
+            parameter.name,
+            operation
+        )));
+    }
+    if parameter.required != Some(true) {
+        r.assert("path-parameter-required", at, &Err(format!(
+            "The path parameter {} in {} must be required: true",
+            parameter.name,
+            operation
+        )));
+    }
+}
+
+// /users/{id}/groups/{group} -> [id, group]
+fn templated(path: &str) -> Vec<&str> {
+    Regex::new(r"\{([^}]+)\}")
+        .unwrap()
+        .captures_iter(path)
+        .map(|captures| captures.get(1).unwrap().as_str())
+        .collect()
+}
+
+// (parameter, JSON pointer where it is used). Also reports missing references
+// and parameters declared twice in the same list.
+fn resolve_all(
+    spec: &openapi::Spec,
+    declared: &Option<Vec<openapi::ParameterOrRef>>,
+    location: &str,
+    operation: &str,
+    r: &mut ValidationResults,
+) -> Vec<(openapi::Parameter, String)> {
+    let mut parameters: Vec<(openapi::Parameter, String)> = vec![];
+    for (index, parameter) in declared.iter().flat_map(|declared| declared.iter()).enumerate() {
+        let at = format!("{}/{}", location, index);
+        match resolve(spec, parameter) {
+            Ok(parameter) => {
+                if parameters.iter().any(|&(ref p, _)| p.name == parameter.name && p.location == parameter.location) {
+                    r.assert("parameter-duplicate", &at, &Err(format!(
+                        "The {} parameter {} is declared twice in {}",
+                        parameter.location,
+                        parameter.name,
+                        operation
+                    )));
+                } else {
+                    parameters.push((parameter, at));
+                }
+            }
+            Err(ref_path) => r.assert("parameter-reference", &at, &Err(format!(
+                "The parameter {} used in {} is missing from the spec",
+                ref_path,
+                operation
+            ))),
+        }
+    }
+    parameters
+}
+
+// "#/parameters/page" -> the page parameter of the spec, the reference when it is missing.
+fn resolve(spec: &openapi::Spec, parameter: &openapi::ParameterOrRef) -> Result<openapi::Parameter, String> {
+    match *parameter {
+        openapi::ParameterOrRef::Parameter {
+            ref name,
+            ref location,
+            ref required,
+            ref schema,
+            ref unique_items,
+            ref param_type,
+            ref format,
+            ref description,
+        } => Ok(openapi::Parameter {
+            name: name.clone(),
+            location: location.clone(),
+            required: *required,
+            schema: schema.clone(),
+            unique_items: *unique_items,
+            param_type: param_type.clone(),
+            format: format.clone(),
+            description: description.clone(),
+        }),
+        openapi::ParameterOrRef::Ref { ref ref_path } => {
+            let name = ref_path.trim_start_matches("#/parameters/");
+            spec.parameters
+                .as_ref()
+                .and_then(|parameters| parameters.get(name))
+                .cloned()
+                .ok_or_else(|| ref_path.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    // (rule, location) of the findings about the parameters list, the checks of each parameter aside.
+    fn findings(path: &str, path_parameters: &str, operation_parameters: &str) -> Vec<(String, String)> {
+        let spec: openapi::Spec = serde_json::from_str(r#"{
+            "swagger": "2.0", "info": {"title": "t", "version": "1"}, "paths": {},
+            "parameters": {"id": {"name": "id", "in": "path", "required": true, "type": "string", "description": "Id"}}
+        }"#).unwrap();
+        let path_parameters = serde_json::from_str(path_parameters).unwrap();
+        let operation_parameters = serde_json::from_str(operation_parameters).unwrap();
+        let item = PathParameters { spec: &spec, path: path, parameters: &path_parameters };
+        let operation = OperationParameters {
+            spec: &spec,
+            path: path,
+            verb: "post",
+            path_parameters: &path_parameters,
+            operation_parameters: &operation_parameters,
+        };
+        let mut results = item.validate(&ValidationOptions::default());
+        results.validate(&operation, &ValidationOptions::default());
+        results
+            .findings()
+            .into_iter()
+            .filter(|f| f.rule.starts_with("path-parameter") || f.rule.starts_with("parameter-"))
+            .map(|f| (f.rule.to_string(), f.location.to_string()))
+            .collect()
+    }
+
+    fn finding(rule: &str, location: &str) -> (String, String) {
+        (rule.to_string(), location.to_string())
+    }
+
+    #[test]
+    fn accepts_path_parameters_from_the_path_or_references() {
+        assert!(findings("/users/{id}", r##"[{"$ref": "#/parameters/id"}]"##, "null").is_empty());
+        let id = r#"[{"name": "id", "in": "path", "required": true, "type": "string", "description": "Id"}]"#;
+        assert!(findings("/users/{id}", "null", id).is_empty());
+    }
+
+    #[test]
+    fn checks_parameters_against_the_template() {
+        let parameters = r#"[
+            {"name": "name", "in": "path", "type": "string", "description": "Name"},
+            {"name": "name", "in": "path", "required": true, "type": "string", "description": "Name"}
+        ]"#;
+        assert_eq!(findings("/users/{id}", "null", parameters), vec![
+            finding("parameter-duplicate", "/paths/~1users~1{id}/post/parameters/1"),
+            finding("path-parameter-unused", "/paths/~1users~1{id}/post/parameters/0"),
+            finding("path-parameter-required", "/paths/~1users~1{id}/post/parameters/0"),
+            finding("path-parameter-missing", "/paths/~1users~1{id}/post"),
+        ]);
+    }
+
+    #[test]
+    fn lets_operations_override_path_parameters() {
+        let shared = r#"[{"name": "id", "in": "path", "type": "string", "description": "Id"}]"#;
+        let own = r#"[{"name": "id", "in": "path", "required": true, "type": "string", "description": "Id"}]"#;
+        assert_eq!(findings("/users/{id}", shared, "null"), vec![
+            finding("path-parameter-required", "/paths/~1users~1{id}/parameters/0"),
+        ]);
+        // The declaration of the path item is still wrong, the operation has what it needs.
+        assert_eq!(findings("/users/{id}", shared, own), vec![
+            finding("path-parameter-required", "/paths/~1users~1{id}/parameters/0"),
+        ]);
+        assert!(findings("/users/{id}", "null", own).is_empty());
+    }
+
+    #[test]
+    fn checks_path_parameters_once_for_the_path_item() {
+        let spec: openapi::Spec = serde_json::from_str(r#"{
+            "swagger": "2.0", "info": {"title": "t", "version": "1"},
+            "paths": {"/users/{id}": {
+                "parameters": [{"name": "id", "in": "path", "type": "string", "description": "Id"}],
+                "get": {"responses": {}},
+                "delete": {"responses": {}}
+            }}
+        }"#).unwrap();
+        let locations: Vec<String> = spec
+            .validate(&ValidationOptions::default())
+            .findings()
+            .into_iter()
+            .filter(|f| f.rule == "path-parameter-required")
+            .map(|f| f.location.to_string())
+            .collect();
+        assert_eq!(locations, vec!["/paths/~1users~1{id}/parameters/0"]);
+    }
+
+    #[test]
+    fn reports_missing_references_and_bodies() {
+        let parameters = r##"[
+            {"$ref": "#/parameters/missing"},
+            {"name": "a", "in": "body", "schema": {"type": "object"}, "description": "Body"},
+            {"name": "b", "in": "body", "schema": {"type": "object"}, "description": "Body"},
+            {"name": "c", "in": "formData", "type": "string", "description": "C"}
+        ]"##;
+        assert_eq!(findings("/users", "null", parameters), vec![
+            finding("parameter-reference", "/paths/~1users/post/parameters/0"),
+            finding("parameter-single-body", "/paths/~1users/post"),
+            finding("parameter-body-form-data", "/paths/~1users/post"),
+        ]);
+    }
+}
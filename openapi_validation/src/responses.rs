@@ -0,0 +1,195 @@
+use openapi;
+use openapi_extensions::pointer;
+use std::collections::BTreeMap;
+use inflector::Inflector;
+
+use {OpenAPIValidation, ValidationOptions};
+use validation_results::ValidationResults;
+
+// Every rule can be turned off from the validation profile.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ResponseRules {
+    // At least one 2xx response per operation.
+    pub success_required: bool,
+    // Status codes are HTTP codes (100 to 599) or default.
+    pub valid_status_codes: bool,
+    pub descriptions_required: bool,
+    // GET on a collection path (/users) returns an array.
+    pub collections_are_arrays: bool,
+    // 204 means there is no content, so there is no schema.
+    pub no_content_without_schema: bool,
+}
+
+impl Default for ResponseRules {
+    fn default() -> ResponseRules {
+        ResponseRules {
+            success_required: true,
+            valid_status_codes: true,
+            descriptions_required: true,
+            collections_are_arrays: true,
+            no_content_without_schema: true,
+        }
+    }
+}
+
+pub struct PathResponses<'a> {
+    pub spec: &'a openapi::Spec,
+    pub path: &'a str,
+    pub verb: &'a str,
+    pub responses: &'a BTreeMap<String, openapi::Response>,
+}
+
+impl<'a> OpenAPIValidation for PathResponses<'a> {
+    fn validate(&self, options: &ValidationOptions) -> ValidationResults {
+        let mut r = ValidationResults::new();
+        let rules = &options.responses;
+        let operation = format!("{} '{}'", self.verb.to_uppercase(), self.path);
+        let location = |status: &str| pointer(&["paths", self.path, self.verb, "responses", status]);
+
+        if rules.success_required && !self.responses.keys().any(|status| status.starts_with('2')) {
+            let responses = pointer(&["paths", self.path, self.verb, "responses"]);
+            r.assert("response-success", &responses, &Err(format!("Expected a 2xx response in {}", operation)));
+        }
+
+        for (status, response) in self.responses {
+            if rules.valid_status_codes && !is_status_code(status) {
+                r.assert("response-status-code", &location(status), &Err(format!(
+                    "The response {} in {} is not a valid HTTP status code or default",
+                    status,
+                    operation
+                )));
+            }
+            if rules.descriptions_required && response.description.trim().is_empty() {
+                r.assert("response-description", &location(status), &Err(format!(
+                    "Expected a description for the response {} in {}",
+                    status,
+                    operation
+                )));
+            }
+            if rules.no_content_without_schema && status == "204" && response.schema.is_some() {
+                r.assert("response-no-content", &location(status), &Err(format!(
+                    "The response 204 in {} has a schema but 204 responses have no content",
+                    operation
+                )));
+            }
+        }
+
+        if rules.collections_are_arrays && self.verb == "get" && is_collection(self.path) {
+            let success = self.responses
+                .iter()
+                .find(|&(status, response)| status.starts_with('2') && response.schema.is_some());
+            if let Some((status, response)) = success {
+                if !is_array(self.spec, response.schema.as_ref().unwrap()) {
+                    r.assert_warn("response-collection-array", &location(status), &Err(format!(
+                        "Expected the response {} in {} to be an array because the path is a collection",
+                        status,
+                        operation
+                    )));
+                }
+            }
+        }
+
+        r
+    }
+}
+
+fn is_status_code(status: &str) -> bool {
+    status == "default" || (status.len() == 3 && status.parse::<u16>().map_or(false, |code| code >= 100 && code < 600))
+}
+
+// /users and /groups/{id}/users are collections, /users/{id} and /status are not.
+fn is_collection(path: &str) -> bool {
+    let last = path.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    !last.is_empty() && !last.starts_with('{') && last.to_plural() == last && last.to_singular() != last
+}
+
+fn is_array(spec: &openapi::Spec, schema: &openapi::Schema) -> bool {
+    let schema = match schema.ref_path {
+        Some(ref ref_path) => {
+            let name = ref_path.trim_start_matches("#/definitions/");
+            match spec.definitions.as_ref().and_then(|definitions| definitions.get(name)) {
+                Some(definition) => definition,
+                // Broken references are reported somewhere else.
+                None => return true,
+            }
+        }
+        None => schema,
+    };
+    schema.schema_type.as_ref().map_or(false, |schema_type| schema_type == "array")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    fn spec() -> openapi::Spec {
+        serde_json::from_str(r##"{
+            "swagger": "2.0", "info": {"title": "t", "version": "1"}, "paths": {},
+            "definitions": {"Users": {"type": "array", "items": {"type": "object"}}, "User": {"type": "object"}}
+        }"##).unwrap()
+    }
+
+    // (rule, location) of the findings for a get of path with these responses.
+    fn findings(path: &str, responses: &str, options: &ValidationOptions) -> Vec<(String, String)> {
+        let spec = spec();
+        let responses: BTreeMap<String, openapi::Response> = serde_json::from_str(responses).unwrap();
+        let operation = PathResponses { spec: &spec, path: path, verb: "get", responses: &responses };
+        operation.validate(options).findings().into_iter().map(|f| (f.rule.to_string(), f.location.to_string())).collect()
+    }
+
+    fn rules(findings: Vec<(String, String)>) -> Vec<String> {
+        findings.into_iter().map(|(rule, _)| rule).collect()
+    }
+
+    #[test]
+    fn accepts_good_responses() {
+        let responses = r##"{"200": {"description": "ok", "schema": {"$ref": "#/definitions/Users"}}, "default": {"description": "error"}}"##;
+        assert!(findings("/users", responses, &ValidationOptions::default()).is_empty());
+        let responses = r##"{"200": {"description": "ok", "schema": {"$ref": "#/definitions/User"}}, "404": {"description": "missing"}}"##;
+        assert!(findings("/users/{id}", responses, &ValidationOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn reports_each_rule() {
+        let responses = r#"{"42": {"description": "odd"}, "204": {"description": " ", "schema": {"type": "string"}}}"#;
+        assert_eq!(findings("/status", responses, &ValidationOptions::default()), vec![
+            ("response-description".to_string(), "/paths/~1status/get/responses/204".to_string()),
+            ("response-no-content".to_string(), "/paths/~1status/get/responses/204".to_string()),
+            ("response-status-code".to_string(), "/paths/~1status/get/responses/42".to_string()),
+        ]);
+        assert_eq!(rules(findings("/status", r#"{"404": {"description": "missing"}}"#, &ValidationOptions::default())), vec!["response-success"]);
+    }
+
+    #[test]
+    fn expects_arrays_from_collections() {
+        let responses = r##"{"200": {"description": "ok", "schema": {"$ref": "#/definitions/User"}}}"##;
+        assert_eq!(rules(findings("/groups/{id}/users", responses, &ValidationOptions::default())), vec!["response-collection-array"]);
+        assert!(findings("/users/{id}", responses, &ValidationOptions::default()).is_empty());
+        assert!(findings("/status", responses, &ValidationOptions::default()).is_empty());
+        // Broken references are not this rule's concern.
+        let responses = r##"{"200": {"description": "ok", "schema": {"$ref": "#/definitions/Missing"}}}"##;
+        assert!(findings("/users", responses, &ValidationOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn follows_the_profile() {
+        let options = ValidationOptions {
+            responses: ResponseRules { success_required: false, valid_status_codes: false, ..ResponseRules::default() },
+            ..ValidationOptions::default()
+        };
+        assert!(findings("/status", r#"{"600": {"description": "odd"}}"#, &options).is_empty());
+    }
+
+    #[test]
+    fn recognizes_status_codes_and_collections() {
+        assert!(is_status_code("default"));
+        assert!(is_status_code("599"));
+        assert!(!is_status_code("099"));
+        assert!(!is_status_code("2XX"));
+        assert!(is_collection("/users/"));
+        assert!(!is_collection("/"));
+        assert!(!is_collection("/users/{id}"));
+    }
+}
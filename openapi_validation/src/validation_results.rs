@@ -3,10 +3,25 @@ use ValidationOptions;
 
 pub type ValidationResult = Result<(), String>;
 
+// rule identifies the check, location is the JSON pointer of what failed it.
+// Together they identify a finding for suppressions and baselines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub rule: String,
+    pub location: String,
+    pub message: String,
+}
+
+impl Finding {
+    pub fn same_as(&self, other: &Finding) -> bool {
+        self.rule == other.rule && self.location == other.location
+    }
+}
+
 #[derive(Debug)]
 pub struct ValidationResults {
-    errors: Vec<String>,
-    warnings: Vec<String>,
+    errors: Vec<Finding>,
+    warnings: Vec<Finding>,
 }
 
 use std::fmt;
@@ -16,14 +31,14 @@ impl fmt::Display for ValidationResults {
         if !self.errors.is_empty() {
             write!(f, "\nerrors\n")?;
             for error in &self.errors {
-                write!(f, "    {}\n", error)?;
+                write!(f, "    {} ({})\n", error.message, error.rule)?;
             }
         }
 
         if !self.warnings.is_empty() {
             write!(f, "\nwarnings\n")?;
             for warning in &self.warnings {
-                write!(f, "    {}\n", warning)?;
+                write!(f, "    {} ({})\n", warning.message, warning.rule)?;
             }
         }
 
@@ -40,21 +55,34 @@ impl ValidationResults {
         }
     }
 
-    pub fn assert(&mut self, result: &ValidationResult) {
-        result.as_ref().map_err(|e| self.append_error(e)).ok();
+    pub fn assert(&mut self, rule: &str, location: &str, result: &ValidationResult) {
+        if let Err(ref message) = *result {
+            self.errors.push(finding(rule, location, message));
+        }
     }
 
-    pub fn assert_warn(&mut self, result: &ValidationResult) {
-        result.as_ref().map_err(|e| self.append_warning(e)).ok();
+    pub fn assert_warn(&mut self, rule: &str, location: &str, result: &ValidationResult) {
+        if let Err(ref message) = *result {
+            self.warnings.push(finding(rule, location, message));
+        }
     }
 
     pub fn validate<T>(&mut self, element: &T, options: &ValidationOptions)
     where
         T: OpenAPIValidation,
     {
-        let mut result = element.validate(options);
-        self.errors.append(&mut result.errors);
-        self.warnings.append(&mut result.warnings);
+        self.validate_at(element, "", options);
+    }
+
+    // For elements which do not know where they are, their locations are relative to location.
+    pub fn validate_at<T>(&mut self, element: &T, location: &str, options: &ValidationOptions)
+    where
+        T: OpenAPIValidation,
+    {
+        let result = element.validate(options);
+        let relocate = |finding: Finding| Finding { location: format!("{}{}", location, finding.location), ..finding };
+        self.errors.extend(result.errors.into_iter().map(&relocate));
+        self.warnings.extend(result.warnings.into_iter().map(&relocate));
     }
 
     pub fn failed(&self) -> bool
@@ -62,12 +90,36 @@ impl ValidationResults {
         !self.errors.is_empty()
     }
 
-    fn append_error(&mut self, value: &str) {
-        self.errors.push(value.to_string());
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty() && self.warnings.is_empty()
     }
 
-    fn append_warning(&mut self, value: &str) {
-        self.warnings.push(value.to_string());
+    pub fn error_count(&self) -> usize {
+        self.errors.len()
     }
 
-}
\ No newline at end of file
+    pub fn warning_count(&self) -> usize {
+        self.warnings.len()
+    }
+
+    pub fn findings(&self) -> Vec<&Finding> {
+        self.errors.iter().chain(self.warnings.iter()).collect()
+    }
+
+    // Drops the findings for which suppressed is true.
+    pub fn suppress<F>(&mut self, suppressed: F)
+    where
+        F: Fn(&Finding) -> bool,
+    {
+        self.errors.retain(|finding| !suppressed(finding));
+        self.warnings.retain(|finding| !suppressed(finding));
+    }
+}
+
+fn finding(rule: &str, location: &str, message: &str) -> Finding {
+    Finding {
+        rule: rule.to_string(),
+        location: location.to_string(),
+        message: message.to_string(),
+    }
+}
@@ -0,0 +1,309 @@
+// This module validates many files at once: lists of files, directories and
+// globs. The format of each file is detected from its content and the files
+// are validated in parallel, the report is printed once all of them finished.
+
+use glob::glob;
+use google_discovery_spec;
+use rayon::prelude::*;
+use serde_json::{self, Value};
+use std::fmt;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use errors::*;
+use openapi_validation::{OpenAPIValidation, ValidationOptions, ValidationResults};
+use spec;
+
+const EXTENSIONS: [&str; 3] = ["yaml", "yml", "json"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    OpenAPI2,
+    OpenAPI3,
+    GoogleDiscovery,
+    Unknown,
+}
+
+impl Format {
+    pub fn detect(document: &Value) -> Format {
+        let version = |key| document.get(key).and_then(|version: &Value| version.as_str());
+        if version("swagger") == Some("2.0") {
+            Format::OpenAPI2
+        } else if version("openapi").map_or(false, |version| version.starts_with("3.")) {
+            Format::OpenAPI3
+        } else if version("kind") == Some("discovery#restDescription") || version("discoveryVersion").is_some() {
+            Format::GoogleDiscovery
+        } else {
+            Format::Unknown
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Format::OpenAPI2 => "OpenAPI 2.0",
+            Format::OpenAPI3 => "OpenAPI 3.x",
+            Format::GoogleDiscovery => "Google Discovery",
+            Format::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub enum Outcome {
+    Validated(ValidationResults),
+    // Files in a format oatool has no rules for, with the reason. They only fail
+    // with --fail_on_skipped.
+    Skipped(String),
+    Failed(Error),
+}
+
+pub struct FileReport {
+    pub filename: String,
+    pub format: Format,
+    pub outcome: Outcome,
+}
+
+impl FileReport {
+    pub fn failed(&self) -> bool {
+        match self.outcome {
+            Outcome::Validated(ref results) => results.failed(),
+            Outcome::Skipped(_) => false,
+            Outcome::Failed(_) => true,
+        }
+    }
+
+    pub fn skipped(&self) -> bool {
+        match self.outcome {
+            Outcome::Skipped(_) => true,
+            _ => false,
+        }
+    }
+
+    // (errors, warnings), a file which can not be read counts as one error.
+    fn counts(&self) -> (usize, usize) {
+        match self.outcome {
+            Outcome::Validated(ref results) => (results.error_count(), results.warning_count()),
+            Outcome::Skipped(_) => (0, 0),
+            Outcome::Failed(_) => (1, 0),
+        }
+    }
+}
+
+// Directories are walked for YAML and JSON files, arguments with * ? or [ are globs.
+pub fn expand(arguments: &[&str]) -> Result<Vec<String>> {
+    let mut files = vec![];
+    for argument in arguments {
+        if Path::new(argument).is_dir() {
+            for entry in WalkDir::new(argument).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+                let entry = entry.chain_err(|| format!("Unable to read the directory {}.", argument))?;
+                if entry.file_type().is_file() && is_spec_file(entry.path()) {
+                    add(&mut files, entry.path().display().to_string());
+                }
+            }
+        } else if argument.contains(|c| c == '*' || c == '?' || c == '[') {
+            let paths = glob(argument).chain_err(|| format!("{} is not a valid glob.", argument))?;
+            for path in paths {
+                let path = path.chain_err(|| format!("Unable to read the files of {}.", argument))?;
+                if path.is_file() {
+                    add(&mut files, path.display().to_string());
+                }
+            }
+        } else {
+            add(&mut files, argument.to_string());
+        }
+    }
+    Ok(files)
+}
+
+// Arguments may overlap, each file is validated once.
+fn add(files: &mut Vec<String>, file: String) {
+    if !files.contains(&file) {
+        files.push(file);
+    }
+}
+
+fn is_spec_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| EXTENSIONS.contains(&extension))
+}
+
+pub fn validate_all(files: &[String], options: &ValidationOptions) -> Vec<FileReport> {
+    files.par_iter().map(|filename| validate_file(filename, options)).collect()
+}
+
+// The format of a file, Unknown when it can not be read. Its validation says why.
+pub fn detect_file(filename: &str) -> Format {
+    spec::value_from_path(filename).map_or(Format::Unknown, |document| Format::detect(&document))
+}
+
+pub fn validate_file(filename: &str, options: &ValidationOptions) -> FileReport {
+    let document = match spec::value_from_path(filename) {
+        Ok(document) => document,
+        Err(e) => return FileReport { filename: filename.to_string(), format: Format::Unknown, outcome: Outcome::Failed(e) },
+    };
+    let format = Format::detect(&document);
+    let outcome = match format {
+        Format::OpenAPI2 => match spec::from_value(document) {
+            Ok(document) => Outcome::Validated(document.validate(options)),
+            Err(e) => Outcome::Failed(e),
+        },
+        // Discovery files have no rules of their own, they are only read.
+        Format::GoogleDiscovery => match serde_json::from_value::<google_discovery_spec::Spec>(document) {
+            Ok(_) => Outcome::Skipped("Google Discovery files have no rules, the file was only read".to_string()),
+            Err(e) => Outcome::Failed(Error::with_chain(e, "The file is not a valid Google Discovery file.")),
+        },
+        Format::OpenAPI3 => Outcome::Skipped("OpenAPI 3.x files are not validated yet".to_string()),
+        Format::Unknown => Outcome::Failed("The file is not OpenAPI or Google Discovery.".into()),
+    };
+    FileReport { filename: filename.to_string(), format: format, outcome: outcome }
+}
+
+// The findings of each file with any, then a table with the counts of every file.
+pub fn report(reports: &[FileReport]) -> String {
+    let mut text = String::new();
+    for report in reports {
+        match report.outcome {
+            Outcome::Validated(ref results) if !results.is_empty() => {
+                text.push_str(&format!("{}\n{}\n", report.filename, results));
+            }
+            Outcome::Skipped(ref reason) => {
+                text.push_str(&format!("{}\n    Skipped: {}\n\n", report.filename, reason));
+            }
+            Outcome::Failed(ref error) => {
+                text.push_str(&format!("{}\n", report.filename));
+                for (depth, e) in error.iter().enumerate() {
+                    text.push_str(&format!("{}↳ {}\n", " ".repeat(depth), e));
+                }
+                text.push('\n');
+            }
+            _ => {}
+        }
+    }
+
+    let width = reports.iter().map(|report| report.filename.chars().count()).max().unwrap_or(0).max("File".len());
+    text.push_str(&format!("{:width$}  {:16}  {:>6}  {:>8}\n", "File", "Format", "Errors", "Warnings", width = width));
+    let (mut errors, mut warnings) = (0, 0);
+    for report in reports {
+        let (file_errors, file_warnings) = report.counts();
+        errors += file_errors;
+        warnings += file_warnings;
+        text.push_str(&format!(
+            "{:width$}  {:16}  {:>6}  {:>8}\n",
+            report.filename,
+            report.format.to_string(),
+            file_errors,
+            file_warnings,
+            width = width
+        ));
+    }
+    let failed = reports.iter().filter(|report| report.failed()).count();
+    let skipped = reports.iter().filter(|report| report.skipped()).count();
+    text.push_str(&format!(
+        "\n{} files, {} failed, {} skipped, {} errors, {} warnings\n",
+        reports.len(),
+        failed,
+        skipped,
+        errors,
+        warnings
+    ));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    // A directory of its own for each test, removed when it ends.
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Scratch {
+            let directory = env::temp_dir().join(format!("oatool-batch-{}-{}", name, process::id()));
+            fs::create_dir_all(directory.join("nested")).unwrap();
+            Scratch(directory)
+        }
+
+        fn write(&self, name: &str, text: &str) -> String {
+            let path = self.0.join(name);
+            fs::write(&path, text).unwrap();
+            path.display().to_string()
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const OPENAPI2: &str = "swagger: '2.0'\ninfo: {title: t, version: '1'}\npaths: {}\n";
+
+    #[test]
+    fn detects_formats() {
+        let detect = |text: &str| Format::detect(&serde_json::from_str(text).unwrap());
+        assert_eq!(detect(r#"{"swagger": "2.0"}"#), Format::OpenAPI2);
+        assert_eq!(detect(r#"{"openapi": "3.1.0"}"#), Format::OpenAPI3);
+        assert_eq!(detect(r#"{"kind": "discovery#restDescription"}"#), Format::GoogleDiscovery);
+        assert_eq!(detect(r#"{"discoveryVersion": "v1"}"#), Format::GoogleDiscovery);
+        assert_eq!(detect(r#"{"swagger": 2}"#), Format::Unknown);
+    }
+
+    #[test]
+    fn expands_directories_and_globs_once() {
+        let scratch = Scratch::new("expand");
+        let b = scratch.write("b.yaml", OPENAPI2);
+        let a = scratch.write("a.json", "{}");
+        let nested = scratch.write("nested/c.yml", OPENAPI2);
+        scratch.write("notes.txt", "");
+
+        let directory = scratch.0.display().to_string();
+        let glob = format!("{}/*.yaml", directory);
+        let files = expand(&[&directory, &glob, &b]).unwrap();
+        assert_eq!(files, vec![a, b, nested]);
+        assert!(expand(&["[broken"]).is_err());
+    }
+
+    #[test]
+    fn validates_each_format() {
+        let scratch = Scratch::new("validate");
+        let options = ValidationOptions::default();
+        let outcome = |text: &str| {
+            let report = validate_file(&scratch.write("spec.yaml", text), &options);
+            (report.format, report.failed(), report.counts())
+        };
+
+        let (format, _, (errors, warnings)) = outcome(OPENAPI2);
+        assert_eq!(format, Format::OpenAPI2);
+        assert!(errors > 0 && warnings > 0);
+        assert_eq!(outcome("openapi: 3.0.0\n"), (Format::OpenAPI3, false, (0, 0)));
+        assert_eq!(outcome("kind: discovery#restDescription\n"), (Format::GoogleDiscovery, true, (1, 0)));
+        let discovery = "kind: discovery#restDescription\nid: pets:v1\nname: pets\nversion: v1\ntitle: Pets\ndescription: d\nprotocol: rest\nbasePath: /v1/\nschemas: {}\nresources: {}\n";
+        let report = validate_file(&scratch.write("discovery.yaml", discovery), &options);
+        assert!(report.skipped() && !report.failed());
+        assert_eq!(outcome("title: other\n"), (Format::Unknown, true, (1, 0)));
+        assert_eq!(detect_file(&scratch.0.join("missing.yaml").display().to_string()), Format::Unknown);
+    }
+
+    #[test]
+    fn reports_totals() {
+        let reports = vec![
+            FileReport { filename: "a.yaml".to_string(), format: Format::OpenAPI3, outcome: Outcome::Skipped("later".to_string()) },
+            FileReport { filename: "bb.yaml".to_string(), format: Format::Unknown, outcome: Outcome::Failed("Broken.".into()) },
+        ];
+        assert_eq!(report(&reports), [
+            "a.yaml\n    Skipped: later\n\n",
+            "bb.yaml\n↳ Broken.\n\n",
+            "File     Format            Errors  Warnings\n",
+            "a.yaml   OpenAPI 3.x            0         0\n",
+            "bb.yaml  unknown                1         0\n",
+            "\n2 files, 1 failed, 1 skipped, 1 errors, 0 warnings\n",
+        ].concat());
+    }
+}
@@ -0,0 +1,168 @@
+// This module generates code from a spec. It works on the untyped document,
+// which keeps what the typed spec drops (additionalProperties, allOf, x-nullable).
+// Google Discovery files are converted first, their schemas become definitions.
+
+pub mod rust;
+pub mod rust_client;
+pub mod rust_server;
+pub mod typescript;
+
+use inflector::Inflector;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+// The schemas of a Discovery document as definitions. The conversion to OpenAPI
+// keeps less of them than the types need: enums, items, references and which
+// properties are required.
+pub fn discovery_definitions(discovery: &Value) -> Value {
+    let schemas = discovery.get("schemas").and_then(|schemas| schemas.as_object());
+    Value::Object(
+        schemas
+            .into_iter()
+            .flat_map(|schemas| schemas.iter())
+            .map(|(name, schema)| (name.to_string(), from_discovery(schema)))
+            .collect(),
+    )
+}
+
+// Discovery $refs name the schema (Pet, or schemas/Pet/v1 in older files) and
+// required is a flag of each property.
+fn from_discovery(schema: &Value) -> Value {
+    let map = match schema.as_object() {
+        Some(map) => map,
+        None => return schema.clone(),
+    };
+    let mut converted = Map::new();
+    let mut required = vec![];
+    for (key, value) in map {
+        match key.as_str() {
+            "$ref" => {
+                let name = value.as_str().unwrap_or("");
+                let name = if name.starts_with("schemas/") { name.split('/').nth(1).unwrap_or(name) } else { name };
+                converted.insert(key.to_string(), Value::String(format!("#/definitions/{}", name)));
+            }
+            "properties" => {
+                let mut properties = Map::new();
+                for (name, property) in value.as_object().into_iter().flat_map(|properties| properties.iter()) {
+                    if property.get("required").and_then(|required| required.as_bool()) == Some(true) {
+                        required.push(Value::String(name.to_string()));
+                    }
+                    properties.insert(name.to_string(), from_discovery(property));
+                }
+                converted.insert(key.to_string(), Value::Object(properties));
+            }
+            "items" | "additionalProperties" => {
+                converted.insert(key.to_string(), from_discovery(value));
+            }
+            "required" | "id" | "resource" | "location" | "annotations" => {}
+            _ => {
+                converted.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+    if !required.is_empty() {
+        converted.insert("required".to_string(), Value::Array(required));
+    }
+    Value::Object(converted)
+}
+
+// The definitions of the document, in their order.
+fn definitions(document: &Value) -> Vec<(&str, &Value)> {
+    document
+        .get("definitions")
+        .and_then(|definitions| definitions.as_object())
+        .map_or(vec![], |definitions| definitions.iter().map(|(name, definition)| (name.as_str(), definition)).collect())
+}
+
+// #/definitions/Pet -> Pet
+fn ref_name(schema: &Value) -> Option<&str> {
+    schema.get("$ref").and_then(|ref_path| ref_path.as_str()).map(|ref_path| ref_path.rsplit('/').next().unwrap_or(ref_path))
+}
+
+// Text of the document as identifier words: letters and digits, the rest separates.
+fn words(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() { c } else { ' ' }).collect::<String>().trim().to_string()
+}
+
+// pet-owner.v1 -> PetOwnerV1, names starting with a digit get a prefix.
+fn type_name(name: &str) -> String {
+    let pascal = words(name).to_pascal_case();
+    match pascal.chars().next() {
+        None => "Unnamed".to_string(),
+        Some(c) if c.is_numeric() => format!("N{}", pascal),
+        Some(_) => pascal,
+    }
+}
+
+// createdAt -> created_at, type -> type_
+fn field_name(name: &str) -> String {
+    let snake = words(name).to_snake_case();
+    match snake.chars().next() {
+        None => "field".to_string(),
+        Some(c) if c.is_numeric() => format!("n{}", snake),
+        Some(_) if KEYWORDS.contains(&snake.as_str()) => format!("{}_", snake),
+        Some(_) => snake,
+    }
+}
+
+// Adds a number to names already taken.
+fn unique(name: String, taken: &mut BTreeSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut count = 2;
+    while taken.contains(&candidate) {
+        candidate = format!("{}{}", name, count);
+        count += 1;
+    }
+    taken.insert(candidate.clone());
+    candidate
+}
+
+// Strict and reserved keywords of Rust.
+const KEYWORDS: [&str; 50] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn", "else",
+    "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match", "mod",
+    "move", "mut", "override", "priv", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true",
+    "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    #[test]
+    fn makes_identifiers() {
+        assert_eq!(type_name("pet-owner.v1"), "PetOwnerV1");
+        assert_eq!(type_name("2fa"), "N2Fa");
+        assert_eq!(type_name("--"), "Unnamed");
+        assert_eq!(field_name("createdAt"), "created_at");
+        assert_eq!(field_name("type"), "type_");
+        assert_eq!(field_name("3d"), "n3d");
+        assert_eq!(field_name("$"), "field");
+
+        let mut taken = BTreeSet::new();
+        assert_eq!(unique("Pet".to_string(), &mut taken), "Pet");
+        assert_eq!(unique("Pet".to_string(), &mut taken), "Pet2");
+        assert_eq!(unique("Pet".to_string(), &mut taken), "Pet3");
+    }
+
+    #[test]
+    fn converts_discovery_schemas() {
+        let discovery: Value = json(r#"{"schemas": {
+            "Pet": {"id": "Pet", "type": "object", "properties": {
+                "name": {"type": "string", "required": true, "location": "query"},
+                "owner": {"$ref": "Person"},
+                "toys": {"type": "array", "items": {"$ref": "schemas/Toy/v1"}}
+            }}
+        }}"#);
+        let expected: Value = json(r##"{
+            "Pet": {"type": "object", "properties": {
+                "name": {"type": "string"},
+                "owner": {"$ref": "#/definitions/Person"},
+                "toys": {"type": "array", "items": {"$ref": "#/definitions/Toy"}}
+            }, "required": ["name"]}
+        }"##);
+        assert_eq!(discovery_definitions(&discovery), expected);
+        assert_eq!(ref_name(&expected["Pet"]["properties"]["owner"]), Some("Person"));
+    }
+}
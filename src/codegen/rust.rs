@@ -0,0 +1,359 @@
+// Rust types for the definitions of a spec, with serde attributes for their
+// JSON form. Objects become structs, string enums become enums and the rest
+// type aliases. Inline objects and enums get types named after their place.
+
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use super::{definitions, field_name, ref_name, type_name, unique};
+
+pub fn models(document: &Value) -> String {
+    Models::new(document).module()
+}
+
+struct Pending {
+    name: String,
+    schema: Value,
+    // The definition it comes from, to find recursive types.
+    definition: String,
+}
+
+pub struct Models<'a> {
+    document: &'a Value,
+    // Definition name -> Rust type name.
+    pub names: BTreeMap<String, String>,
+    // Put before the names of the types, for code outside their module: models::
+    pub prefix: String,
+    taken: BTreeSet<String>,
+    // Inline schemas, as JSON, -> the type made for them. The same schema in
+    // several places, as allOf makes them, is one type.
+    inline: BTreeMap<String, String>,
+    pending: VecDeque<Pending>,
+}
+
+impl<'a> Models<'a> {
+    pub fn new(document: &'a Value) -> Models<'a> {
+        let mut models = Models {
+            document: document,
+            names: BTreeMap::new(),
+            prefix: String::new(),
+            taken: BTreeSet::new(),
+            inline: BTreeMap::new(),
+            pending: VecDeque::new(),
+        };
+        for (name, definition) in definitions(document) {
+            let type_name = unique(type_name(name), &mut models.taken);
+            models.names.insert(name.to_string(), type_name.clone());
+            models.pending.push_back(Pending {
+                name: type_name,
+                schema: definition.clone(),
+                definition: name.to_string(),
+            });
+        }
+        models
+    }
+
+    // A name for a type outside the module which no type in it has.
+    pub fn reserve(&mut self, name: &str) -> String {
+        unique(type_name(name), &mut self.taken)
+    }
+
+    // The module with every type, those made for inline schemas until now included.
+    pub fn module(&mut self) -> String {
+        let prefix = ::std::mem::replace(&mut self.prefix, String::new());
+        let mut text = String::new();
+        while let Some(pending) = self.pending.pop_front() {
+            text.push('\n');
+            text.push_str(&self.item(&pending));
+        }
+        self.prefix = prefix;
+
+        let title = self.document.pointer("/info/title").and_then(|title| title.as_str()).unwrap_or("the spec");
+        let mut header = format!("// Types of {}, generated by oatool. Changes are lost when it is generated again.\n\n", title);
+        header.push_str("#![allow(dead_code)]\n\nuse serde::{Deserialize, Serialize};\n");
+        if text.contains("BTreeMap<") {
+            header.push_str("use std::collections::BTreeMap;\n");
+        }
+        header + &text
+    }
+
+    fn item(&mut self, pending: &Pending) -> String {
+        let schema = &pending.schema;
+        let mut text = doc_comment(schema, "");
+        if is_struct(schema) {
+            text.push_str("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n");
+            text.push_str(&format!("pub struct {} {{\n", pending.name));
+            let (properties, required) = self.properties(schema);
+            let mut fields = BTreeSet::new();
+            for (key, property) in properties {
+                let field = unique(field_name(&key), &mut fields);
+                let hint = format!("{}{}", pending.name, type_name(&key));
+                let mut field_type = self.type_of(&property, &hint, &pending.definition);
+                if self.is_recursive(&property, &pending.definition) {
+                    field_type = format!("Box<{}>", field_type);
+                }
+                let nullable = property.get("x-nullable").and_then(|nullable| nullable.as_bool()).unwrap_or(false);
+                let optional = !required.contains(&key) || nullable;
+
+                text.push_str(&doc_comment(&property, "    "));
+                if field != key {
+                    text.push_str(&format!("    #[serde(rename = \"{}\")]\n", key.replace('\\', "\\\\").replace('"', "\\\"")));
+                }
+                if optional {
+                    text.push_str("    #[serde(skip_serializing_if = \"Option::is_none\")]\n");
+                    text.push_str(&format!("    pub {}: Option<{}>,\n", field, field_type));
+                } else {
+                    text.push_str(&format!("    pub {}: {},\n", field, field_type));
+                }
+            }
+            text.push_str("}\n");
+        } else if let Some(values) = string_enum(schema) {
+            text.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]\n");
+            text.push_str(&format!("pub enum {} {{\n", pending.name));
+            let mut variants = BTreeSet::new();
+            for value in values {
+                let variant = unique(type_name(value), &mut variants);
+                if variant != value {
+                    text.push_str(&format!("    #[serde(rename = \"{}\")]\n", value.replace('\\', "\\\\").replace('"', "\\\"")));
+                }
+                text.push_str(&format!("    {},\n", variant));
+            }
+            text.push_str("}\n");
+        } else {
+            let aliased = self.type_of(schema, &format!("{}Item", pending.name), &pending.definition);
+            text.push_str(&format!("pub type {} = {};\n", pending.name, aliased));
+        }
+        text
+    }
+
+    // The properties of an object with those of its allOf parts, and the required ones.
+    fn properties(&self, schema: &Value) -> (Vec<(String, Value)>, BTreeSet<String>) {
+        let mut properties: Vec<(String, Value)> = vec![];
+        let mut required = BTreeSet::new();
+        self.collect_properties(schema, &mut properties, &mut required, 0);
+        (properties, required)
+    }
+
+    fn collect_properties(&self, schema: &Value, properties: &mut Vec<(String, Value)>, required: &mut BTreeSet<String>, depth: usize) {
+        if depth > 16 {
+            return;
+        }
+        if let Some(definition) = ref_name(schema).and_then(|name| self.definition(name)) {
+            return self.collect_properties(definition, properties, required, depth + 1);
+        }
+        for part in schema.get("allOf").and_then(|parts| parts.as_array()).into_iter().flat_map(|parts| parts.iter()) {
+            self.collect_properties(part, properties, required, depth + 1);
+        }
+        for (key, property) in schema.get("properties").and_then(|properties| properties.as_object()).into_iter().flat_map(|map| map.iter()) {
+            properties.retain(|&(ref existing, _)| existing != key);
+            properties.push((key.to_string(), property.clone()));
+        }
+        for name in schema.get("required").and_then(|names| names.as_array()).into_iter().flat_map(|names| names.iter()) {
+            if let Some(name) = name.as_str() {
+                required.insert(name.to_string());
+            }
+        }
+    }
+
+    fn definition(&self, name: &str) -> Option<&'a Value> {
+        self.document.get("definitions").and_then(|definitions| definitions.get(name))
+    }
+
+    // The Rust type of a schema. Inline objects and enums are queued as types named hint.
+    pub fn type_of(&mut self, schema: &Value, hint: &str, definition: &str) -> String {
+        if let Some(name) = ref_name(schema) {
+            return match self.names.get(name) {
+                Some(name) => format!("{}{}", self.prefix, name),
+                None => "serde_json::Value".to_string(),
+            };
+        }
+        if is_struct(schema) || string_enum(schema).is_some() {
+            let key = schema.to_string();
+            if let Some(name) = self.inline.get(&key) {
+                return format!("{}{}", self.prefix, name);
+            }
+            let name = unique(hint.to_string(), &mut self.taken);
+            self.inline.insert(key, name.clone());
+            self.pending.push_back(Pending {
+                name: name.clone(),
+                schema: schema.clone(),
+                definition: definition.to_string(),
+            });
+            return format!("{}{}", self.prefix, name);
+        }
+        let format = schema.get("format").and_then(|format| format.as_str());
+        match schema.get("type").and_then(|schema_type| schema_type.as_str()) {
+            Some("string") => "String".to_string(),
+            Some("integer") => match format {
+                Some("int32") => "i32".to_string(),
+                _ => "i64".to_string(),
+            },
+            Some("number") => match format {
+                Some("float") => "f32".to_string(),
+                _ => "f64".to_string(),
+            },
+            Some("boolean") => "bool".to_string(),
+            Some("array") => {
+                let items = schema.get("items").cloned().unwrap_or(Value::Null);
+                format!("Vec<{}>", self.type_of(&items, &format!("{}Item", hint), definition))
+            }
+            Some("object") | None => match schema.get("additionalProperties") {
+                Some(additional) if additional.is_object() => {
+                    format!("BTreeMap<String, {}>", self.type_of(additional, &format!("{}Value", hint), definition))
+                }
+                _ => "serde_json::Value".to_string(),
+            },
+            Some(_) => "serde_json::Value".to_string(),
+        }
+    }
+
+    // A field which holds a definition leading back to its own needs a Box,
+    // otherwise the type would have an infinite size. Vec and BTreeMap already
+    // hold theirs on the heap.
+    fn is_recursive(&self, property: &Value, definition: &str) -> bool {
+        match ref_name(property) {
+            Some(name) => self.reaches(name, definition, &mut BTreeSet::new()),
+            None => false,
+        }
+    }
+
+    fn reaches(&self, from: &str, to: &str, seen: &mut BTreeSet<String>) -> bool {
+        if from == to {
+            return true;
+        }
+        if !seen.insert(from.to_string()) {
+            return false;
+        }
+        let schema = match self.definition(from) {
+            Some(schema) => schema,
+            None => return false,
+        };
+        let (properties, _) = self.properties(schema);
+        let mut direct: Vec<String> = properties.iter().flat_map(|&(_, ref property)| direct_refs(property)).collect();
+        direct.extend(ref_name(schema).map(|name| name.to_string()));
+        direct.iter().any(|name| self.reaches(name, to, seen))
+    }
+}
+
+// The definitions a property holds by value, also through inline objects.
+fn direct_refs(property: &Value) -> Vec<String> {
+    if let Some(name) = ref_name(property) {
+        return vec![name.to_string()];
+    }
+    property
+        .get("properties")
+        .and_then(|properties| properties.as_object())
+        .map_or(vec![], |properties| properties.values().flat_map(direct_refs).collect())
+}
+
+fn is_struct(schema: &Value) -> bool {
+    schema.get("properties").map_or(false, |properties| properties.as_object().map_or(false, |map| !map.is_empty()))
+        || schema.get("allOf").is_some()
+}
+
+fn string_enum(schema: &Value) -> Option<Vec<&str>> {
+    let values = schema.get("enum").and_then(|values| values.as_array())?;
+    let strings: Vec<&str> = values.iter().filter_map(|value| value.as_str()).collect();
+    if strings.len() == values.len() && !strings.is_empty() {
+        Some(strings)
+    } else {
+        None
+    }
+}
+
+fn doc_comment(schema: &Value, indent: &str) -> String {
+    schema
+        .get("description")
+        .and_then(|description| description.as_str())
+        .map_or(String::new(), |description| {
+            description.trim().lines().map(|line| format!("{}/// {}\n", indent, line.trim_end())).collect()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    fn models_of(definitions: &str) -> String {
+        let document: Value = json(&format!(r#"{{"info": {{"title": "Pets"}}, "definitions": {}}}"#, definitions));
+        models(&document)
+    }
+
+    #[test]
+    fn makes_structs_with_optional_and_renamed_fields() {
+        let text = models_of(r##"{
+            "Pet": {"type": "object", "description": "A pet.\nOr two.", "required": ["name", "nickname"], "properties": {
+                "name": {"type": "string"},
+                "nickname": {"type": "string", "x-nullable": true},
+                "createdAt": {"type": "string", "format": "date-time", "description": "When"},
+                "age": {"type": "integer", "format": "int32"},
+                "tags": {"type": "array", "items": {"type": "number", "format": "float"}},
+                "extra": {"type": "object", "additionalProperties": {"type": "boolean"}},
+                "owner": {"$ref": "#/definitions/Missing"}
+            }}
+        }"##);
+        assert!(text.starts_with("// Types of Pets, generated by oatool."));
+        assert!(text.contains("use std::collections::BTreeMap;\n"));
+        assert!(text.contains(&[
+            "/// A pet.\n/// Or two.\n",
+            "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n",
+            "pub struct Pet {\n",
+            "    pub name: String,\n",
+            "    #[serde(skip_serializing_if = \"Option::is_none\")]\n",
+            "    pub nickname: Option<String>,\n",
+            "    /// When\n",
+            "    #[serde(rename = \"createdAt\")]\n",
+            "    #[serde(skip_serializing_if = \"Option::is_none\")]\n",
+            "    pub created_at: Option<String>,\n",
+            "    #[serde(skip_serializing_if = \"Option::is_none\")]\n",
+            "    pub age: Option<i32>,\n",
+            "    #[serde(skip_serializing_if = \"Option::is_none\")]\n",
+            "    pub tags: Option<Vec<f32>>,\n",
+            "    #[serde(skip_serializing_if = \"Option::is_none\")]\n",
+            "    pub extra: Option<BTreeMap<String, bool>>,\n",
+            "    #[serde(skip_serializing_if = \"Option::is_none\")]\n",
+            "    pub owner: Option<serde_json::Value>,\n",
+            "}\n",
+        ].concat()));
+    }
+
+    #[test]
+    fn makes_enums_aliases_and_inline_types() {
+        let text = models_of(r##"{
+            "Kind": {"type": "string", "enum": ["cat", "big-dog", "Cat"]},
+            "Names": {"type": "array", "items": {"type": "string"}},
+            "Pet": {"type": "object", "required": ["size"], "properties": {
+                "size": {"type": "string", "enum": ["small", "large"]},
+                "home": {"type": "object", "properties": {"street": {"type": "string"}}}
+            }},
+            "Dog": {"allOf": [{"$ref": "#/definitions/Pet"}, {"properties": {"barks": {"type": "boolean"}}}]}
+        }"##);
+        assert!(text.contains("pub enum Kind {\n    #[serde(rename = \"cat\")]\n    Cat,\n    #[serde(rename = \"big-dog\")]\n    BigDog,\n    #[serde(rename = \"Cat\")]\n    Cat2,\n}\n"));
+        assert!(text.contains("pub type Names = Vec<String>;\n"));
+        assert!(text.contains("    pub size: PetSize,\n"));
+        assert!(text.contains("    pub home: Option<PetHome>,\n"));
+        assert!(text.contains("pub enum PetSize {\n"));
+        assert!(text.contains("pub struct PetHome {\n"));
+        assert!(text.contains("pub struct Dog {\n    pub size: PetSize,\n"));
+        assert!(text.contains("    pub barks: Option<bool>,\n}\n"));
+        assert!(!text.contains("BTreeMap"));
+    }
+
+    #[test]
+    fn boxes_recursive_fields() {
+        let text = models_of(r##"{
+            "Node": {"type": "object", "properties": {
+                "parent": {"$ref": "#/definitions/Node"},
+                "children": {"type": "array", "items": {"$ref": "#/definitions/Node"}},
+                "pair": {"$ref": "#/definitions/Pair"}
+            }},
+            "Pair": {"type": "object", "properties": {"node": {"$ref": "#/definitions/Node"}}},
+            "Leaf": {"type": "object", "properties": {"node": {"$ref": "#/definitions/Node"}}}
+        }"##);
+        assert!(text.contains("    pub parent: Option<Box<Node>>,\n"));
+        assert!(text.contains("    pub children: Option<Vec<Node>>,\n"));
+        assert!(text.contains("    pub pair: Option<Box<Pair>>,\n"));
+        assert!(text.contains("pub struct Leaf {\n    #[serde(skip_serializing_if = \"Option::is_none\")]\n    pub node: Option<Node>,\n"));
+    }
+}
@@ -0,0 +1,621 @@
+// A Rust client crate for the operations of a spec. Operations are methods of
+// the client, grouped by resource when the operationId has one (pets.get is
+// client.pets().get()), as Google Discovery methods and the conversion to it do.
+// Responses are an enum per operation with a variant per status. Requests go
+// through a Transport trait, the crate has one made with ureq behind a feature.
+
+use inflector::Inflector;
+use openapi_examples;
+use openapi_extensions::VERBS;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::rust::Models;
+use super::{field_name, type_name, unique};
+
+struct Parameter {
+    name: String,
+    location: String,
+    argument: String,
+    // The type of the argument: &str, i64, &[String], &models::Pet...
+    argument_type: String,
+    required: bool,
+    multi: bool,
+}
+
+struct Operation {
+    method: String,
+    verb: String,
+    path: String,
+    summary: Option<String>,
+    parameters: Vec<Parameter>,
+    response_enum: String,
+    // (status, variant, type of the body)
+    responses: Vec<(String, String, Option<String>)>,
+}
+
+// (path in the crate, content) of every file of the crate.
+pub fn files(document: &Value) -> Vec<(String, String)> {
+    let title = document.pointer("/info/title").and_then(|title| title.as_str()).unwrap_or("api");
+    let crate_name = format!("{}_client", super::words(title).to_snake_case());
+
+    let mut models = Models::new(document);
+    models.prefix = "models::".to_string();
+    for reserved in &["Client", "Transport", "Request", "Response", "Error", "BoxError", "UreqTransport"] {
+        models.reserve(reserved);
+    }
+
+    // Resource -> operations, the ones without a resource under "".
+    let mut groups: BTreeMap<String, Vec<Operation>> = BTreeMap::new();
+    let mut methods: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let paths = document.get("paths").and_then(|paths| paths.as_object());
+    for (path, item) in paths.into_iter().flat_map(|paths| paths.iter()) {
+        for verb in VERBS.iter().filter(|verb| item.get(**verb).is_some()) {
+            let operation = &item[*verb];
+            let id = operation.get("operationId").and_then(|id| id.as_str()).map(|id| id.to_string());
+            let id = id.unwrap_or_else(|| format!("{} {}", verb, path));
+            let (group, method) = match id.rfind('.') {
+                Some(dot) => (id[..dot].to_string(), id[dot + 1..].to_string()),
+                None => (String::new(), id.clone()),
+            };
+            let method = unique(field_name(&method), methods.entry(group.clone()).or_insert_with(BTreeSet::new));
+            let operation = operation_of(document, &mut models, path, verb, operation, &id, method);
+            groups.entry(group).or_insert_with(Vec::new).push(operation);
+        }
+    }
+
+    let base_url = base_url(document);
+    let lib = lib(title, &base_url, &groups, &mut models);
+    vec![
+        ("Cargo.toml".to_string(), cargo_toml(&crate_name)),
+        ("README.md".to_string(), readme(title, &crate_name, &base_url, &groups)),
+        ("src/lib.rs".to_string(), lib),
+        ("src/models.rs".to_string(), models.module()),
+    ]
+}
+
+fn operation_of(document: &Value, models: &mut Models, path: &str, verb: &str, operation: &Value, id: &str, method: String) -> Operation {
+    let operation_type = type_name(id);
+    // The locals of the methods.
+    let mut arguments: BTreeSet<String> = ["request", "response", "url", "form"].iter().map(|name| name.to_string()).collect();
+    let mut parameters = vec![];
+    for (_, parameter) in openapi_examples::parameters(document, path, verb) {
+        let name = parameter.get("name").and_then(|name| name.as_str()).unwrap_or("");
+        let location = parameter.get("in").and_then(|location| location.as_str()).unwrap_or("");
+        let hint = format!("{}{}", operation_type, type_name(name));
+        let rust_type = match parameter.get("schema") {
+            Some(schema) if location == "body" => models.type_of(schema, &hint, ""),
+            _ => models.type_of(parameter, &hint, ""),
+        };
+        parameters.push(Parameter {
+            name: name.to_string(),
+            location: location.to_string(),
+            argument: unique(field_name(name), &mut arguments),
+            argument_type: argument_type(&rust_type),
+            required: location == "path" || parameter.get("required").and_then(|required| required.as_bool()).unwrap_or(false),
+            multi: parameter.get("collectionFormat").and_then(|format| format.as_str()) == Some("multi"),
+        });
+    }
+    // Path parameters first, in the order of the path, then the required ones.
+    parameters.sort_by_key(|parameter| {
+        let place = path.find(&format!("{{{}}}", parameter.name)).unwrap_or(path.len());
+        (parameter.location != "path", !parameter.required, place)
+    });
+
+    let response_enum = models.reserve(&format!("{}Response", operation_type));
+    let mut responses = vec![];
+    let mut variants = BTreeSet::new();
+    let declared = operation.get("responses").and_then(|responses| responses.as_object());
+    for (status, response) in declared.into_iter().flat_map(|responses| responses.iter()) {
+        let response = openapi_examples::resolve(document, response);
+        let variant = unique(variant_name(status), &mut variants);
+        let body = response
+            .get("schema")
+            .map(|schema| models.type_of(schema, &format!("{}{}", operation_type, variant), ""));
+        responses.push((status.to_string(), variant, body));
+    }
+
+    Operation {
+        method: method,
+        verb: verb.to_uppercase(),
+        path: path.to_string(),
+        summary: operation
+            .get("summary")
+            .or_else(|| operation.get("description"))
+            .and_then(|summary| summary.as_str())
+            .map(|summary| summary.trim().to_string()),
+        parameters: parameters,
+        response_enum: response_enum,
+        responses: responses,
+    }
+}
+
+// Arguments borrow what is not Copy.
+fn argument_type(rust_type: &str) -> String {
+    match rust_type {
+        "String" => "&str".to_string(),
+        "i32" | "i64" | "f32" | "f64" | "bool" => rust_type.to_string(),
+        _ if rust_type.starts_with("Vec<") => format!("&[{}]", &rust_type[4..rust_type.len() - 1]),
+        _ => format!("&{}", rust_type),
+    }
+}
+
+pub fn variant_name(status: &str) -> String {
+    let name = match status {
+        "200" => "Ok",
+        "201" => "Created",
+        "202" => "Accepted",
+        "204" => "NoContent",
+        "301" => "MovedPermanently",
+        "302" => "Found",
+        "304" => "NotModified",
+        "400" => "BadRequest",
+        "401" => "Unauthorized",
+        "403" => "Forbidden",
+        "404" => "NotFound",
+        "405" => "MethodNotAllowed",
+        "409" => "Conflict",
+        "410" => "Gone",
+        "412" => "PreconditionFailed",
+        "422" => "UnprocessableEntity",
+        "429" => "TooManyRequests",
+        "500" => "InternalServerError",
+        "502" => "BadGateway",
+        "503" => "ServiceUnavailable",
+        "default" => "Other",
+        _ => return format!("Status{}", status),
+    };
+    name.to_string()
+}
+
+pub fn base_url(document: &Value) -> String {
+    let base_path = document.get("basePath").and_then(|base_path| base_path.as_str()).unwrap_or("");
+    let base_path = base_path.trim_end_matches('/');
+    match document.get("host").and_then(|host| host.as_str()) {
+        Some(host) => {
+            let scheme = document.pointer("/schemes/0").and_then(|scheme| scheme.as_str()).unwrap_or("https");
+            format!("{}://{}{}", scheme, host, base_path)
+        }
+        None => base_path.to_string(),
+    }
+}
+
+fn lib(title: &str, base_url: &str, groups: &BTreeMap<String, Vec<Operation>>, models: &mut Models) -> String {
+    let mut text = format!("// Client of {}, generated by oatool. Changes are lost when it is generated again.\n", title);
+    text.push_str(SUPPORT.replace("{BASE_URL}", &quote(base_url)).as_str());
+
+    for (group, operations) in groups {
+        if group.is_empty() {
+            text.push_str("\nimpl<T: Transport> Client<T> {\n");
+        } else {
+            let resource = models.reserve(&format!("{}Resource", group));
+            text.push_str(&format!(
+                "\nimpl<T: Transport> Client<T> {{\n    pub fn {}(&self) -> {}<'_, T> {{\n        {} {{ client: self }}\n    }}\n}}\n",
+                field_name(group),
+                resource,
+                resource
+            ));
+            text.push_str(&format!("\npub struct {}<'a, T: Transport + 'a> {{\n    client: &'a Client<T>,\n}}\n", resource));
+            text.push_str(&format!("\nimpl<'a, T: Transport> {}<'a, T> {{\n", resource));
+        }
+        let client = if group.is_empty() { "self" } else { "self.client" };
+        for (index, operation) in operations.iter().enumerate() {
+            if index > 0 {
+                text.push('\n');
+            }
+            text.push_str(&method(operation, client));
+        }
+        text.push_str("}\n");
+    }
+
+    for operations in groups.values() {
+        for operation in operations {
+            text.push_str(&format!("\n#[derive(Debug, Clone, PartialEq)]\npub enum {} {{\n", operation.response_enum));
+            for &(ref status, ref variant, ref body) in &operation.responses {
+                let body = body.as_ref();
+                match (status.as_str(), body) {
+                    ("default", Some(body)) => text.push_str(&format!("    {} {{ status: u16, body: {} }},\n", variant, body)),
+                    ("default", None) => text.push_str(&format!("    {} {{ status: u16 }},\n", variant)),
+                    (_, Some(body)) => text.push_str(&format!("    {}({}),\n", variant, body)),
+                    (_, None) => text.push_str(&format!("    {},\n", variant)),
+                }
+            }
+            text.push_str("}\n");
+        }
+    }
+    if text.contains("BTreeMap<") {
+        text = text.replacen("\nuse std::fmt;\n", "\nuse std::collections::BTreeMap;\nuse std::fmt;\n", 1);
+    }
+    text
+}
+
+fn method(operation: &Operation, client: &str) -> String {
+    let mut text = String::new();
+    if let Some(ref summary) = operation.summary {
+        for line in summary.lines() {
+            text.push_str(&format!("    /// {}\n", line.trim_end()));
+        }
+    }
+    let arguments: Vec<String> = operation
+        .parameters
+        .iter()
+        .map(|parameter| {
+            if parameter.required {
+                format!("{}: {}", parameter.argument, parameter.argument_type)
+            } else {
+                format!("{}: Option<{}>", parameter.argument, parameter.argument_type)
+            }
+        })
+        .collect();
+    let mut signature = arguments.iter().fold("&self".to_string(), |signature, argument| format!("{}, {}", signature, argument));
+    if signature.len() > 80 {
+        signature = format!("\n        &self,\n{}    ", arguments.iter().map(|argument| format!("        {},\n", argument)).collect::<String>());
+    }
+    text.push_str(&format!(
+        "    /// {} {}\n    pub fn {}({}) -> Result<{}, Error> {{\n",
+        operation.verb, operation.path, operation.method, signature, operation.response_enum
+    ));
+
+    // The path, literal text and parameters in turn.
+    text.push_str(&format!("        let mut url = {}.base_url.clone();\n", client));
+    let mut rest = operation.path.as_str();
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}').map_or(rest.len(), |end| start + end);
+        if start > 0 {
+            text.push_str(&format!("        url.push_str({});\n", quote(&rest[..start])));
+        }
+        let name = &rest[start + 1..end];
+        match operation.parameters.iter().find(|parameter| parameter.location == "path" && parameter.name == name) {
+            Some(parameter) => text.push_str(&format!("        url.push_str(&encode(&to_text(&{})));\n", parameter.argument)),
+            None => text.push_str(&format!("        url.push_str({});\n", quote(&rest[start..end + 1]))),
+        }
+        rest = &rest[(end + 1).min(rest.len())..];
+    }
+    if !rest.is_empty() {
+        text.push_str(&format!("        url.push_str({});\n", quote(rest)));
+    }
+    let changed = operation.parameters.iter().any(|parameter| parameter.location != "path");
+    text.push_str(&format!("        let {}request = Request::new(\"{}\", url);\n", if changed { "mut " } else { "" }, operation.verb));
+
+    let has_form = operation.parameters.iter().any(|parameter| parameter.location == "formData");
+    if has_form {
+        text.push_str("        let mut form: Vec<(String, String)> = vec![];\n");
+    }
+    for parameter in &operation.parameters {
+        let value = if parameter.required { parameter.argument.as_str() } else { "value" };
+        let name = quote(&parameter.name);
+        let target = match parameter.location.as_str() {
+            "query" => "request.query",
+            "header" => "request.headers",
+            "formData" => "form",
+            "body" => "",
+            _ => continue,
+        };
+        let statements = if target.is_empty() {
+            vec![
+                "request.headers.push((\"Content-Type\".to_string(), \"application/json\".to_string()));".to_string(),
+                format!("request.body = Some(serde_json::to_vec(&{}).map_err(Error::Json)?);", value),
+            ]
+        } else if parameter.multi {
+            vec![
+                format!("for item in {}.iter() {{", value),
+                format!("    {}.push(({}.to_string(), to_text(item)));", target, name),
+                "}".to_string(),
+            ]
+        } else {
+            vec![format!("{}.push(({}.to_string(), to_text(&{})));", target, name, value)]
+        };
+        if parameter.required {
+            for statement in statements {
+                text.push_str(&format!("        {}\n", statement));
+            }
+        } else {
+            text.push_str(&format!("        if let Some(value) = {} {{\n", parameter.argument));
+            for statement in statements {
+                text.push_str(&format!("            {}\n", statement));
+            }
+            text.push_str("        }\n");
+        }
+    }
+    if has_form {
+        text.push_str("        request.headers.push((\"Content-Type\".to_string(), \"application/x-www-form-urlencoded\".to_string()));\n");
+        text.push_str("        request.body = Some(encode_form(&form).into_bytes());\n");
+    }
+
+    text.push_str(&format!("        let response = {}.transport.send(request).map_err(Error::Transport)?;\n", client));
+    text.push_str("        match response.status {\n");
+    let mut default = None;
+    for &(ref status, ref variant, ref body) in &operation.responses {
+        if status == "default" {
+            default = Some((variant, body));
+            continue;
+        }
+        let value = match *body {
+            Some(_) => format!("{}::{}(parse(&response.body)?)", operation.response_enum, variant),
+            None => format!("{}::{}", operation.response_enum, variant),
+        };
+        text.push_str(&format!("            {} => Ok({}),\n", status, value));
+    }
+    match default {
+        Some((variant, &Some(_))) => text.push_str(&format!(
+            "            status => Ok({}::{} {{ status, body: parse(&response.body)? }}),\n",
+            operation.response_enum, variant
+        )),
+        Some((variant, &None)) => text.push_str(&format!("            status => Ok({}::{} {{ status }}),\n", operation.response_enum, variant)),
+        None => text.push_str("            status => Err(Error::UnexpectedStatus { status, body: response.body }),\n"),
+    }
+    text.push_str("        }\n    }\n");
+    text
+}
+
+// A Rust string literal.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn cargo_toml(crate_name: &str) -> String {
+    format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n[dependencies]\nserde = {{ version = \"1\", features = [\"derive\"] }}\nserde_json = \"1\"\nureq = {{ version = \"2\", optional = true }}\n",
+        crate_name
+    )
+}
+
+fn readme(title: &str, crate_name: &str, base_url: &str, groups: &BTreeMap<String, Vec<Operation>>) -> String {
+    let mut text = format!("# {}\n\nClient of {}, generated by oatool from its spec.\n\n", crate_name, title);
+    text.push_str("## Using it\n\n");
+    text.push_str(&format!(
+        "With the `ureq` feature the crate has a transport ready to use:\n\n```rust\nlet client = {}::Client::new({}::BASE_URL, {}::UreqTransport::new());\n",
+        crate_name, crate_name, crate_name
+    ));
+    let example = groups.iter().flat_map(|(group, operations)| operations.iter().map(move |operation| (group, operation))).next();
+    if let Some((group, operation)) = example {
+        let call = if group.is_empty() { "client".to_string() } else { format!("client.{}()", field_name(group)) };
+        let arguments: Vec<&str> = operation.parameters.iter().filter(|parameter| parameter.required).map(|parameter| parameter.argument.as_str()).collect();
+        let optional = operation.parameters.iter().filter(|parameter| !parameter.required).count();
+        let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).chain((0..optional).map(|_| "None".to_string())).collect();
+        text.push_str(&format!("let response = {}.{}({})?;\n", call, operation.method, arguments.join(", ")));
+    }
+    text.push_str("```\n\n");
+    text.push_str(&format!(
+        "`BASE_URL` is `{}`, as the spec says. Each method returns an enum with a variant per status of the operation,\nwith the body parsed into its type. Statuses which the spec does not list are an `Error::UnexpectedStatus`.\n\n",
+        base_url
+    ));
+    text.push_str("## Transports\n\nRequests go through the `Transport` trait, to use any HTTP library, add authentication or record\nrequests in tests:\n\n");
+    text.push_str("```rust\nstruct MyTransport;\n\nimpl Transport for MyTransport {\n    fn send(&self, request: Request) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {\n        // request.method, request.url, request.query, request.headers and request.body\n        unimplemented!()\n    }\n}\n```\n\n");
+    text.push_str("## Operations\n\n| Method | Operation | Summary |\n|---|---|---|\n");
+    for (group, operations) in groups {
+        for operation in operations {
+            let call = if group.is_empty() { operation.method.clone() } else { format!("{}().{}", field_name(group), operation.method) };
+            let summary = operation.summary.as_ref().and_then(|summary| summary.lines().next()).unwrap_or("");
+            text.push_str(&format!("| `{}` | {} {} | {} |\n", call, operation.verb, operation.path, summary.replace('|', "\\|")));
+        }
+    }
+    text
+}
+
+// The part of lib.rs which is the same for every spec.
+const SUPPORT: &str = r#"
+#![allow(dead_code, clippy::all)]
+
+pub mod models;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+
+pub const BASE_URL: &str = {BASE_URL};
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: &'static str,
+    // With the path parameters, without the query.
+    pub url: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl Request {
+    pub fn new(method: &'static str, url: String) -> Request {
+        Request { method, url, query: vec![], headers: vec![], body: None }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+// Sends requests over HTTP. Any status is a response, errors are for requests
+// which got none.
+pub trait Transport {
+    fn send(&self, request: Request) -> Result<Response, BoxError>;
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Transport(BoxError),
+    Json(serde_json::Error),
+    UnexpectedStatus { status: u16, body: Vec<u8> },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Transport(error) => write!(f, "The request failed: {}", error),
+            Error::Json(error) => write!(f, "The body is not as the spec says: {}", error),
+            Error::UnexpectedStatus { status, .. } => write!(f, "The spec does not list the status {}", status),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub struct Client<T: Transport> {
+    pub base_url: String,
+    pub transport: T,
+}
+
+impl<T: Transport> Client<T> {
+    pub fn new(base_url: &str, transport: T) -> Client<T> {
+        Client { base_url: base_url.trim_end_matches('/').to_string(), transport }
+    }
+}
+
+#[cfg(feature = "ureq")]
+pub struct UreqTransport {
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "ureq")]
+impl UreqTransport {
+    pub fn new() -> UreqTransport {
+        UreqTransport { agent: ureq::Agent::new() }
+    }
+}
+
+#[cfg(feature = "ureq")]
+impl Transport for UreqTransport {
+    fn send(&self, request: Request) -> Result<Response, BoxError> {
+        use std::io::Read;
+        let mut call = self.agent.request(request.method, &request.url);
+        for (name, value) in &request.query {
+            call = call.query(name, value);
+        }
+        for (name, value) in &request.headers {
+            call = call.set(name, value);
+        }
+        let result = match request.body {
+            Some(body) => call.send_bytes(&body),
+            None => call.call(),
+        };
+        let response = match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(error) => return Err(Box::new(error)),
+        };
+        let status = response.status();
+        let mut body = vec![];
+        response.into_reader().read_to_end(&mut body)?;
+        Ok(Response { status, body })
+    }
+}
+
+fn parse<B: DeserializeOwned>(body: &[u8]) -> Result<B, Error> {
+    serde_json::from_slice(body).map_err(Error::Json)
+}
+
+// Parameters outside the body are text, arrays separated by commas.
+fn to_text<V: Serialize + ?Sized>(value: &V) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(text)) => text,
+        Ok(serde_json::Value::Array(items)) => items.iter().map(to_text).collect::<Vec<_>>().join(","),
+        Ok(value) => value.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn encode_form(fields: &[(String, String)]) -> String {
+    fields.iter().map(|(name, value)| format!("{}={}", encode(name), encode(value))).collect::<Vec<_>>().join("&")
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    fn document() -> Value {
+        json(r##"{
+            "swagger": "2.0",
+            "info": {"title": "Pet Shop", "version": "1"},
+            "host": "api.example.com", "basePath": "/v1/",
+            "paths": {
+                "/pets/{pet_id}": {
+                    "parameters": [{"name": "pet_id", "in": "path", "required": true, "type": "integer"}],
+                    "get": {
+                        "operationId": "pets.get",
+                        "summary": "Gets a pet.",
+                        "parameters": [
+                            {"name": "fields", "in": "query", "type": "array", "items": {"type": "string"}, "collectionFormat": "multi"},
+                            {"name": "X-Trace", "in": "header", "required": true, "type": "string"}
+                        ],
+                        "responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Pet"}}, "404": {"description": "missing"}}
+                    }
+                },
+                "/pets": {
+                    "post": {
+                        "operationId": "createPet",
+                        "parameters": [{"name": "body", "in": "body", "required": true, "schema": {"$ref": "#/definitions/Pet"}}],
+                        "responses": {"201": {"description": "made", "schema": {"$ref": "#/definitions/Pet"}}, "default": {"description": "error"}}
+                    }
+                }
+            },
+            "definitions": {"Pet": {"type": "object", "required": ["name"], "properties": {"name": {"type": "string"}}}, "Client": {"type": "string"}}
+        }"##)
+    }
+
+    fn file(files: &[(String, String)], name: &str) -> String {
+        files.iter().find(|&&(ref path, _)| path == name).map(|&(_, ref text)| text.clone()).unwrap()
+    }
+
+    #[test]
+    fn makes_a_crate() {
+        let files = files(&document());
+        let names: Vec<&str> = files.iter().map(|&(ref name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Cargo.toml", "README.md", "src/lib.rs", "src/models.rs"]);
+        assert!(file(&files, "Cargo.toml").contains("name = \"pet_shop_client\"\n"));
+        assert!(file(&files, "README.md").starts_with("# pet_shop_client\n"));
+        // The models have a module of their own, their names do not clash with the client.
+        assert!(file(&files, "src/models.rs").contains("pub type Client = String;\n"));
+    }
+
+    #[test]
+    fn makes_a_method_per_operation_grouped_by_resource() {
+        let lib = file(&files(&document()), "src/lib.rs");
+        assert!(lib.contains("pub const BASE_URL: &str = \"https://api.example.com/v1\";\n"));
+        assert!(lib.contains("    pub fn create_pet(&self, body: &models::Pet) -> Result<CreatePetResponse, Error> {\n"));
+        assert!(lib.contains("    pub fn pets(&self) -> PetsResource<'_, T> {\n"));
+        assert!(lib.contains(&[
+            "    /// Gets a pet.\n",
+            "    /// GET /pets/{pet_id}\n",
+            "    pub fn get(&self, pet_id: i64, x_trace: &str, fields: Option<&[String]>) -> Result<PetsGetResponse, Error> {\n",
+        ].concat()));
+        assert!(lib.contains("            for item in value.iter() {\n                request.query.push((\"fields\".to_string(), to_text(item)));\n"));
+        assert!(lib.contains("            404 => Ok(PetsGetResponse::NotFound),\n            status => Err(Error::UnexpectedStatus { status, body: response.body }),\n"));
+        assert!(lib.contains("            status => Ok(CreatePetResponse::Other { status }),\n"));
+        assert!(lib.contains("pub enum PetsGetResponse {\n    Ok(models::Pet),\n    NotFound,\n}\n"));
+    }
+
+    #[test]
+    fn names_statuses_and_arguments() {
+        assert_eq!(variant_name("204"), "NoContent");
+        assert_eq!(variant_name("default"), "Other");
+        assert_eq!(variant_name("418"), "Status418");
+        assert_eq!(argument_type("String"), "&str");
+        assert_eq!(argument_type("i32"), "i32");
+        assert_eq!(argument_type("Vec<models::Pet>"), "&[models::Pet]");
+        assert_eq!(argument_type("models::Pet"), "&models::Pet");
+    }
+
+    #[test]
+    fn makes_the_base_url() {
+        let mut document = document();
+        assert_eq!(base_url(&document), "https://api.example.com/v1");
+        document["schemes"] = json(r#"["http"]"#);
+        assert_eq!(base_url(&document), "http://api.example.com/v1");
+        document.as_object_mut().unwrap().remove("host");
+        assert_eq!(base_url(&document), "/v1");
+    }
+}
@@ -0,0 +1,507 @@
+// A Rust server skeleton for the operations of a spec, on axum. The crate has a
+// trait with an async method per operation to implement, and a router whose
+// handlers take the parameters and body out of the request, check them against
+// the spec and call the trait. The spec goes in the crate, the checks are those
+// of openapi_conformance: the schema module of openapi_examples is copied as it is.
+
+use inflector::Inflector;
+use openapi_examples;
+use openapi_extensions::VERBS;
+use serde_json::{self, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::rust::Models;
+use super::{field_name, type_name, unique};
+
+struct Parameter {
+    // Where the declaration is in the spec.
+    pointer: String,
+    name: String,
+    location: String,
+    // The name in the trait. The handler local is arg_ and this name, so it
+    // never takes the name of an extractor or a helper.
+    argument: String,
+    rust_type: String,
+    required: bool,
+}
+
+struct Operation {
+    method: String,
+    verb: String,
+    path: String,
+    summary: Option<String>,
+    handler: String,
+    parameters: Vec<Parameter>,
+    response_enum: String,
+    // (status, variant, type of the body)
+    responses: Vec<(String, String, Option<String>)>,
+}
+
+// (path in the crate, content) of every file of the crate.
+pub fn files(document: &Value) -> Vec<(String, String)> {
+    let title = document.pointer("/info/title").and_then(|title| title.as_str()).unwrap_or("api");
+    let crate_name = format!("{}_server", super::words(title).to_snake_case());
+
+    let mut models = Models::new(document);
+    models.prefix = "models::".to_string();
+    for reserved in &["Api", "Json", "Router", "State"] {
+        models.reserve(reserved);
+    }
+
+    let mut operations = vec![];
+    let mut methods = BTreeSet::new();
+    let paths = document.get("paths").and_then(|paths| paths.as_object());
+    for (path, item) in paths.into_iter().flat_map(|paths| paths.iter()) {
+        for verb in VERBS.iter().filter(|verb| item.get(**verb).is_some()) {
+            let operation = &item[*verb];
+            let id = operation.get("operationId").and_then(|id| id.as_str()).map(|id| id.to_string());
+            let id = id.unwrap_or_else(|| format!("{} {}", verb, path));
+            let method = unique(field_name(&id), &mut methods);
+            operations.push(operation_of(document, &mut models, path, verb, operation, &id, method));
+        }
+    }
+
+    let base_path = document.get("basePath").and_then(|base_path| base_path.as_str()).unwrap_or("").trim_end_matches('/');
+    let lib = lib(title, base_path, &operations);
+    vec![
+        ("Cargo.toml".to_string(), cargo_toml(&crate_name)),
+        ("README.md".to_string(), readme(title, &crate_name, &operations)),
+        ("src/lib.rs".to_string(), lib),
+        ("src/models.rs".to_string(), models.module()),
+        ("src/schema.rs".to_string(), format!("// Copied from oatool by oatool.\n\n{}", openapi_examples::SCHEMA_SOURCE)),
+        ("src/spec.json".to_string(), serde_json::to_string_pretty(document).unwrap_or_default() + "\n"),
+    ]
+}
+
+fn operation_of(document: &Value, models: &mut Models, path: &str, verb: &str, operation: &Value, id: &str, method: String) -> Operation {
+    let operation_type = type_name(id);
+    let mut arguments = BTreeSet::new();
+    let mut parameters = vec![];
+    for (at, parameter) in openapi_examples::parameters(document, path, verb) {
+        let name = parameter.get("name").and_then(|name| name.as_str()).unwrap_or("");
+        let location = parameter.get("in").and_then(|location| location.as_str()).unwrap_or("");
+        let hint = format!("{}{}", operation_type, type_name(name));
+        let rust_type = match parameter.get("schema") {
+            Some(schema) if location == "body" => models.type_of(schema, &hint, ""),
+            _ => models.type_of(parameter, &hint, ""),
+        };
+        parameters.push(Parameter {
+            pointer: at,
+            name: name.to_string(),
+            location: location.to_string(),
+            argument: unique(field_name(name), &mut arguments),
+            rust_type: rust_type,
+            required: location == "path" || parameter.get("required").and_then(|required| required.as_bool()).unwrap_or(false),
+        });
+    }
+    parameters.sort_by_key(|parameter| {
+        let place = path.find(&format!("{{{}}}", parameter.name)).unwrap_or(path.len());
+        (parameter.location != "path", !parameter.required, place)
+    });
+
+    let response_enum = models.reserve(&format!("{}Response", operation_type));
+    let mut responses = vec![];
+    let mut variants = BTreeSet::new();
+    let declared = operation.get("responses").and_then(|responses| responses.as_object());
+    for (status, response) in declared.into_iter().flat_map(|responses| responses.iter()) {
+        let response = openapi_examples::resolve(document, response);
+        let variant = unique(super::rust_client::variant_name(status), &mut variants);
+        let body = response
+            .get("schema")
+            .map(|schema| models.type_of(schema, &format!("{}{}", operation_type, variant), ""));
+        responses.push((status.to_string(), variant, body));
+    }
+
+    Operation {
+        handler: method.clone(),
+        method: method,
+        verb: verb.to_string(),
+        path: path.to_string(),
+        summary: operation
+            .get("summary")
+            .or_else(|| operation.get("description"))
+            .and_then(|summary| summary.as_str())
+            .map(|summary| summary.trim().to_string()),
+        parameters: parameters,
+        response_enum: response_enum,
+        responses: responses,
+    }
+}
+
+fn lib(title: &str, base_path: &str, operations: &[Operation]) -> String {
+    let mut text = format!("// Server of {}, generated by oatool. Changes are lost when it is generated again.\n", title);
+    text.push_str(SUPPORT);
+
+    text.push_str("\n// The operations of the API, to implement.\npub trait Api: Send + Sync + 'static {\n");
+    for (index, operation) in operations.iter().enumerate() {
+        if index > 0 {
+            text.push('\n');
+        }
+        if let Some(ref summary) = operation.summary {
+            for line in summary.lines() {
+                text.push_str(&format!("    /// {}\n", line.trim_end()));
+            }
+        }
+        text.push_str(&format!("    /// {} {}\n", operation.verb.to_uppercase(), operation.path));
+        let arguments: String = operation
+            .parameters
+            .iter()
+            .map(|parameter| {
+                if parameter.required {
+                    format!(", {}: {}", parameter.argument, parameter.rust_type)
+                } else {
+                    format!(", {}: Option<{}>", parameter.argument, parameter.rust_type)
+                }
+            })
+            .collect();
+        text.push_str(&format!(
+            "    fn {}(&self{}) -> impl Future<Output = {}> + Send;\n",
+            operation.method, arguments, operation.response_enum
+        ));
+    }
+    text.push_str("}\n");
+
+    // Routes, with the verbs of each path together.
+    let mut routes: BTreeMap<&str, Vec<&Operation>> = BTreeMap::new();
+    let mut order = vec![];
+    for operation in operations {
+        if !routes.contains_key(operation.path.as_str()) {
+            order.push(operation.path.as_str());
+        }
+        routes.entry(operation.path.as_str()).or_insert_with(Vec::new).push(operation);
+    }
+    text.push_str("\n// The routes of the spec, with the handlers which check requests and call the API.\n");
+    text.push_str("pub fn router<A: Api>(api: A) -> Router {\n");
+    text.push_str(if base_path.is_empty() { "    Router::new()\n" } else { "    let routes = Router::new()\n" });
+    for path in order {
+        let handlers: Vec<String> = routes[path]
+            .iter()
+            .map(|operation| format!("{}({}::<A>)", operation.verb, operation.handler))
+            .collect();
+        text.push_str(&format!("        .route({}, {})\n", quote(path), handlers.join(".")));
+    }
+    if base_path.is_empty() {
+        text.push_str("        .with_state(Arc::new(api))\n}\n");
+    } else {
+        text.push_str("        .with_state(Arc::new(api));\n");
+        text.push_str(&format!("    Router::new().nest({}, routes)\n}}\n", quote(base_path)));
+    }
+
+    for operation in operations {
+        text.push_str(&handler(operation));
+    }
+
+    for operation in operations {
+        text.push_str(&format!("\n#[derive(Debug, Clone, PartialEq)]\npub enum {} {{\n", operation.response_enum));
+        for &(ref status, ref variant, ref body) in &operation.responses {
+            match (status.as_str(), body.as_ref()) {
+                ("default", Some(body)) => text.push_str(&format!("    {} {{ status: u16, body: {} }},\n", variant, body)),
+                ("default", None) => text.push_str(&format!("    {} {{ status: u16 }},\n", variant)),
+                (_, Some(body)) => text.push_str(&format!("    {}({}),\n", variant, body)),
+                (_, None) => text.push_str(&format!("    {},\n", variant)),
+            }
+        }
+        text.push_str("}\n");
+
+        text.push_str(&format!("\nimpl IntoResponse for {} {{\n    fn into_response(self) -> Response {{\n        match self {{\n", operation.response_enum));
+        for &(ref status, ref variant, ref body) in &operation.responses {
+            let line = match (status.as_str(), body.is_some()) {
+                ("default", true) => format!("Self::{} {{ status, body }} => (status_code(status), Json(body)).into_response()", variant),
+                ("default", false) => format!("Self::{} {{ status }} => status_code(status).into_response()", variant),
+                (_, true) => format!("Self::{}(body) => (status_code({}), Json(body)).into_response()", variant, status),
+                (_, false) => format!("Self::{} => status_code({}).into_response()", variant, status),
+            };
+            text.push_str(&format!("            {},\n", line));
+        }
+        text.push_str("        }\n    }\n}\n");
+    }
+
+    if text.contains("BTreeMap<") {
+        text = text.replacen("\nuse std::collections::HashMap;\n", "\nuse std::collections::{BTreeMap, HashMap};\n", 1);
+    }
+    text
+}
+
+fn handler(operation: &Operation) -> String {
+    let has = |location: &str| operation.parameters.iter().any(|parameter| parameter.location == location);
+    let mut extractors = vec!["State(api): State<Arc<A>>".to_string()];
+    if has("path") {
+        extractors.push("Path(path): Path<HashMap<String, String>>".to_string());
+    }
+    if has("query") {
+        extractors.push("Query(query): Query<Vec<(String, String)>>".to_string());
+    }
+    if has("header") {
+        extractors.push("headers: HeaderMap".to_string());
+    }
+    if has("body") || has("formData") {
+        extractors.push("body: Bytes".to_string());
+    }
+
+    let mut text = format!("\nasync fn {}<A: Api>(\n", operation.handler);
+    for extractor in &extractors {
+        text.push_str(&format!("    {},\n", extractor));
+    }
+    text.push_str(") -> Response {\n");
+    if operation.parameters.is_empty() {
+        text.push_str(&format!("    api.{}().await.into_response()\n}}\n", operation.method));
+        return text;
+    }
+
+    text.push_str("    let mut errors = vec![];\n");
+    if has("formData") {
+        text.push_str("    let form: Vec<(String, String)> = serde_urlencoded::from_bytes(&body).unwrap_or_default();\n");
+    }
+    for parameter in &operation.parameters {
+        let texts = match parameter.location.as_str() {
+            "path" => format!("path.get({}).map(|text| vec![text.as_str()]).unwrap_or_default()", quote(&parameter.name)),
+            "query" => format!("texts(&query, {})", quote(&parameter.name)),
+            "formData" => format!("texts(&form, {})", quote(&parameter.name)),
+            "header" => format!("header_texts(&headers, {})", quote(&parameter.name)),
+            "body" => {
+                text.push_str(&format!(
+                    "    let arg_{}: Option<{}> = body_value(&mut errors, {}, &body);\n",
+                    parameter.argument,
+                    parameter.rust_type,
+                    quote(&parameter.pointer)
+                ));
+                continue;
+            }
+            _ => continue,
+        };
+        text.push_str(&format!(
+            "    let arg_{}: Option<{}> = parameter(&mut errors, {}, {});\n",
+            parameter.argument,
+            parameter.rust_type,
+            quote(&parameter.pointer),
+            texts
+        ));
+    }
+    text.push_str("    if !errors.is_empty() {\n        return invalid(errors);\n    }\n");
+    for parameter in operation.parameters.iter().filter(|parameter| parameter.required) {
+        text.push_str(&format!(
+            "    let Some(arg_{}) = arg_{} else {{\n        return invalid(errors);\n    }};\n",
+            parameter.argument, parameter.argument
+        ));
+    }
+    let arguments: Vec<String> = operation.parameters.iter().map(|parameter| format!("arg_{}", parameter.argument)).collect();
+    text.push_str(&format!("    api.{}({}).await.into_response()\n}}\n", operation.method, arguments.join(", ")));
+    text
+}
+
+// A Rust string literal.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn cargo_toml(crate_name: &str) -> String {
+    format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\naxum = \"0.8\"\nregex = \"1\"\nserde = {{ version = \"1\", features = [\"derive\"] }}\nserde_json = \"1\"\nserde_urlencoded = \"0.7\"\n\n[dev-dependencies]\ntokio = {{ version = \"1\", features = [\"macros\", \"rt-multi-thread\"] }}\n",
+        crate_name
+    )
+}
+
+fn readme(title: &str, crate_name: &str, operations: &[Operation]) -> String {
+    let mut text = format!("# {}\n\nServer skeleton of {}, generated by oatool from its spec, on axum.\n\n", crate_name, title);
+    text.push_str("## Using it\n\nImplement the `Api` trait, a method per operation, and serve its router:\n\n```rust\n");
+    text.push_str(&format!("use {}::{{models, router, Api}};\n\nstruct Service;\n\nimpl Api for Service {{\n", crate_name));
+    if let Some(operation) = operations.first() {
+        let arguments: String = operation
+            .parameters
+            .iter()
+            .map(|parameter| {
+                if parameter.required {
+                    format!(", {}: {}", parameter.argument, parameter.rust_type)
+                } else {
+                    format!(", {}: Option<{}>", parameter.argument, parameter.rust_type)
+                }
+            })
+            .collect();
+        text.push_str(&format!(
+            "    async fn {}(&self{}) -> {}::{} {{\n        todo!()\n    }}\n    // ...\n",
+            operation.method, arguments, crate_name, operation.response_enum
+        ));
+    }
+    text.push_str("}\n\n#[tokio::main]\nasync fn main() {\n    let listener = tokio::net::TcpListener::bind(\"127.0.0.1:8080\").await.unwrap();\n    axum::serve(listener, router(Service)).await.unwrap();\n}\n```\n\n");
+    text.push_str("Requests are checked against the spec before they get to the trait: path, query, header and form\nparameters, and bodies, with their types, enums, bounds, lengths and patterns. Invalid requests get a 400\nwith a JSON list of the problems. Methods return an enum with a variant per documented status.\n\n");
+    text.push_str("## Operations\n\n| Method | Operation | Summary |\n|---|---|---|\n");
+    for operation in operations {
+        let summary = operation.summary.as_ref().and_then(|summary| summary.lines().next()).unwrap_or("");
+        text.push_str(&format!(
+            "| `{}` | {} {} | {} |\n",
+            operation.method,
+            operation.verb.to_uppercase(),
+            operation.path,
+            summary.replace('|', "\\|")
+        ));
+    }
+    text
+}
+
+// The part of lib.rs which is the same for every spec.
+const SUPPORT: &str = r#"
+#![allow(dead_code, unused_imports)]
+
+pub mod models;
+// The schema checks of oatool, as they are there.
+#[allow(clippy::all)]
+mod schema;
+
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, head, options, patch, post, put};
+use axum::{Json, Router};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+
+// The spec the server was generated from, requests are checked against it.
+pub fn spec() -> &'static Value {
+    static SPEC: OnceLock<Value> = OnceLock::new();
+    SPEC.get_or_init(|| serde_json::from_str(include_str!("spec.json")).expect("The spec is JSON"))
+}
+
+fn status_code(status: u16) -> StatusCode {
+    StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn invalid(errors: Vec<String>) -> Response {
+    (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "errors": errors }))).into_response()
+}
+
+fn texts<'a>(pairs: &'a [(String, String)], name: &str) -> Vec<&'a str> {
+    pairs.iter().filter(|(key, _)| key == name).map(|(_, value)| value.as_str()).collect()
+}
+
+fn header_texts<'a>(headers: &'a HeaderMap, name: &str) -> Vec<&'a str> {
+    headers.get_all(name).iter().filter_map(|value| value.to_str().ok()).collect()
+}
+
+// A parameter outside the body, None when it is missing or wrong, with the problems in errors.
+fn parameter<T: DeserializeOwned>(errors: &mut Vec<String>, pointer: &str, texts: Vec<&str>) -> Option<T> {
+    let declaration = spec().pointer(pointer)?;
+    let name = declaration.get("name").and_then(|name| name.as_str()).unwrap_or("");
+    let location = declaration.get("in").and_then(|location| location.as_str()).unwrap_or("");
+    let place = format!("{}.{}", location, name);
+    if texts.is_empty() {
+        if location == "path" || declaration.get("required").and_then(|required| required.as_bool()).unwrap_or(false) {
+            errors.push(format!("The {} parameter {} is required", location, name));
+        }
+        return None;
+    }
+    let value = schema::parameter_value(declaration, &texts);
+    let problems = schema::check(spec(), declaration, &value, &place);
+    if !problems.is_empty() {
+        errors.extend(problems);
+        return None;
+    }
+    serde_json::from_value(value).map_err(|error| errors.push(format!("{}: {}", place, error))).ok()
+}
+
+// The body, None when it is missing or wrong, with the problems in errors.
+fn body_value<T: DeserializeOwned>(errors: &mut Vec<String>, pointer: &str, body: &Bytes) -> Option<T> {
+    let declaration = spec().pointer(pointer)?;
+    if body.iter().all(|byte| byte.is_ascii_whitespace()) {
+        if declaration.get("required").and_then(|required| required.as_bool()).unwrap_or(false) {
+            errors.push("The body is required".to_string());
+        }
+        return None;
+    }
+    let value: Value = match serde_json::from_slice(body) {
+        Ok(value) => value,
+        Err(error) => {
+            errors.push(format!("The body is not valid JSON: {}", error));
+            return None;
+        }
+    };
+    let problems = declaration.get("schema").map_or(vec![], |schema| schema::check(spec(), schema, &value, "body"));
+    if !problems.is_empty() {
+        errors.extend(problems);
+        return None;
+    }
+    serde_json::from_value(value).map_err(|error| errors.push(format!("body: {}", error))).ok()
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    fn document() -> Value {
+        json(r##"{
+            "swagger": "2.0",
+            "info": {"title": "Pet Shop", "version": "1"},
+            "basePath": "/v1/",
+            "paths": {
+                "/pets/{pet_id}": {
+                    "parameters": [{"name": "pet_id", "in": "path", "required": true, "type": "integer"}],
+                    "get": {
+                        "operationId": "pets.get",
+                        "summary": "Gets a pet.",
+                        "parameters": [
+                            {"name": "fields", "in": "query", "type": "array", "items": {"type": "string"}, "collectionFormat": "multi"},
+                            {"name": "X-Trace", "in": "header", "required": true, "type": "string"}
+                        ],
+                        "responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Pet"}}, "404": {"description": "missing"}}
+                    }
+                },
+                "/pets": {
+                    "post": {
+                        "operationId": "createPet",
+                        "parameters": [{"name": "body", "in": "body", "required": true, "schema": {"$ref": "#/definitions/Pet"}}],
+                        "responses": {"201": {"description": "made", "schema": {"$ref": "#/definitions/Pet"}}, "default": {"description": "error"}}
+                    }
+                }
+            },
+            "definitions": {"Pet": {"type": "object", "required": ["name"], "properties": {"name": {"type": "string"}}}}
+        }"##)
+    }
+
+    fn file(files: &[(String, String)], name: &str) -> String {
+        files.iter().find(|&&(ref path, _)| path == name).map(|&(_, ref text)| text.clone()).unwrap()
+    }
+
+    #[test]
+    fn makes_a_crate_with_the_spec_and_its_checks() {
+        let document = document();
+        let files = files(&document);
+        let names: Vec<&str> = files.iter().map(|&(ref name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Cargo.toml", "README.md", "src/lib.rs", "src/models.rs", "src/schema.rs", "src/spec.json"]);
+        assert!(file(&files, "Cargo.toml").contains("name = \"pet_shop_server\"\n"));
+        assert_eq!(file(&files, "src/schema.rs"), format!("// Copied from oatool by oatool.\n\n{}", openapi_examples::SCHEMA_SOURCE));
+        assert_eq!(json::<Value>(&file(&files, "src/spec.json")), document);
+
+        // The parameters are typed by the copy, not by a second one in lib.rs.
+        let lib = file(&files, "src/lib.rs");
+        assert!(lib.contains("let value = schema::parameter_value(declaration, &texts);"));
+        assert!(!lib.contains("fn typed"));
+    }
+
+    #[test]
+    fn makes_a_trait_method_and_a_handler_per_operation() {
+        let lib = file(&files(&document()), "src/lib.rs");
+        assert!(lib.contains(&[
+            "    /// Gets a pet.\n",
+            "    /// GET /pets/{pet_id}\n",
+            "    fn pets_get(&self, pet_id: i64, x_trace: String, fields: Option<Vec<String>>) -> impl Future<Output = PetsGetResponse> + Send;\n",
+        ].concat()));
+        // The body argument keeps its name, the handler locals do not take the ones of the extractors.
+        assert!(lib.contains("    fn create_pet(&self, body: models::Pet) -> impl Future<Output = CreatePetResponse> + Send;\n"));
+        assert!(lib.contains(&[
+            "        .route(\"/pets/{pet_id}\", get(pets_get::<A>))\n",
+            "        .route(\"/pets\", post(create_pet::<A>))\n",
+            "        .with_state(Arc::new(api));\n",
+            "    Router::new().nest(\"/v1\", routes)\n",
+        ].concat()));
+        assert!(lib.contains("    let arg_x_trace: Option<String> = parameter(&mut errors, \"/paths/~1pets~1{pet_id}/get/parameters/1\", header_texts(&headers, \"X-Trace\"));\n"));
+        assert!(lib.contains("    let arg_body: Option<models::Pet> = body_value(&mut errors, \"/paths/~1pets/post/parameters/0\", &body);\n"));
+        assert!(lib.contains("    api.create_pet(arg_body).await.into_response()\n"));
+        assert!(lib.contains("            Self::NotFound => status_code(404).into_response(),\n"));
+        assert!(lib.contains("            Self::Other { status } => status_code(status).into_response(),\n"));
+    }
+}
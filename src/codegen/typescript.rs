@@ -0,0 +1,547 @@
+// TypeScript for a spec, in one module: an interface per object definition,
+// string literal unions for enums and aliases for the rest, then a function per
+// operation which calls the API with fetch. Inline schemas stay inline, as object
+// literal types. Functions resolve to the body of the 2xx response and throw an
+// ApiError for other statuses.
+
+use inflector::Inflector;
+use openapi_examples;
+use openapi_extensions::VERBS;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{definitions, ref_name, type_name, unique, words};
+
+struct Types {
+    // Definition name -> TypeScript type name.
+    names: BTreeMap<String, String>,
+    taken: BTreeSet<String>,
+}
+
+pub fn module(document: &Value) -> String {
+    let title = document.pointer("/info/title").and_then(|title| title.as_str()).unwrap_or("the spec");
+    let mut text = format!("// Types and client of {}, generated by oatool. Changes are lost when it is generated again.\n", title);
+
+    // The globals the client uses can not be definition names.
+    let mut taken: BTreeSet<String> = ["ApiError", "ApiOptions", "Promise", "Record"].iter().map(|name| name.to_string()).collect();
+    let names = definitions(document)
+        .iter()
+        .map(|&(name, _)| (name.to_string(), unique(type_name(name), &mut taken)))
+        .collect();
+    let mut types = Types {
+        names: names,
+        taken: taken,
+    };
+    for (name, definition) in definitions(document) {
+        text.push('\n');
+        text.push_str(&doc_comment(definition.get("description").and_then(|description| description.as_str()), ""));
+        let type_name = &types.names[name];
+        if is_interface(definition) {
+            text.push_str(&format!("export interface {} {}\n", type_name, types.object(definition, "")));
+        } else {
+            text.push_str(&format!("export type {} = {};\n", type_name, types.type_of(definition, "")));
+        }
+    }
+
+    text.push_str(&SUPPORT.replace("{base_url}", &quote(&super::rust_client::base_url(document))));
+
+    let mut functions = BTreeSet::new();
+    let paths = document.get("paths").and_then(|paths| paths.as_object());
+    for (path, item) in paths.into_iter().flat_map(|paths| paths.iter()) {
+        for verb in VERBS.iter().filter(|verb| item.get(**verb).is_some()) {
+            let operation = &item[*verb];
+            let id = operation.get("operationId").and_then(|id| id.as_str()).map(|id| id.to_string());
+            let id = id.unwrap_or_else(|| format!("{} {}", verb, path));
+            let name = unique(identifier(&id), &mut functions);
+            text.push('\n');
+            text.push_str(&function(document, &mut types, path, verb, operation, &name));
+        }
+    }
+    text
+}
+
+impl Types {
+    // The TypeScript type of a schema, indent is the one of the line it starts on.
+    fn type_of(&self, schema: &Value, indent: &str) -> String {
+        let mut written = self.type_without_null(schema, indent);
+        if schema.get("x-nullable").and_then(|nullable| nullable.as_bool()).unwrap_or(false) {
+            written.push_str(" | null");
+        }
+        written
+    }
+
+    fn type_without_null(&self, schema: &Value, indent: &str) -> String {
+        if let Some(name) = ref_name(schema) {
+            return self.names.get(name).cloned().unwrap_or_else(|| "unknown".to_string());
+        }
+        if let Some(values) = schema.get("enum").and_then(|values| values.as_array()) {
+            if !values.is_empty() {
+                return values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(" | ");
+            }
+        }
+        if let Some(parts) = schema.get("allOf").and_then(|parts| parts.as_array()) {
+            let mut written: Vec<String> = parts.iter().map(|part| self.type_of(part, indent)).collect();
+            if has_properties(schema) {
+                written.push(self.object(schema, indent));
+            }
+            return written.join(" & ");
+        }
+        if has_properties(schema) {
+            return self.object(schema, indent);
+        }
+        match schema.get("type").and_then(|schema_type| schema_type.as_str()) {
+            Some("string") => "string".to_string(),
+            Some("integer") | Some("number") => "number".to_string(),
+            Some("boolean") => "boolean".to_string(),
+            Some("array") => {
+                let items = self.type_of(schema.get("items").unwrap_or(&Value::Null), indent);
+                if items.contains(" | ") || items.contains(" & ") {
+                    format!("Array<{}>", items)
+                } else {
+                    format!("{}[]", items)
+                }
+            }
+            Some("object") => match schema.get("additionalProperties") {
+                Some(additional) if additional.is_object() => format!("Record<string, {}>", self.type_of(additional, indent)),
+                _ => "Record<string, unknown>".to_string(),
+            },
+            _ => "unknown".to_string(),
+        }
+    }
+
+    // { name: string; tag?: string }, one property per line.
+    fn object(&self, schema: &Value, indent: &str) -> String {
+        let required: BTreeSet<&str> = schema
+            .get("required")
+            .and_then(|names| names.as_array())
+            .map_or(BTreeSet::new(), |names| names.iter().filter_map(|name| name.as_str()).collect());
+        let inner = format!("{}  ", indent);
+        let mut text = "{\n".to_string();
+        for (key, property) in schema.get("properties").and_then(|properties| properties.as_object()).into_iter().flat_map(|map| map.iter()) {
+            text.push_str(&doc_comment(property.get("description").and_then(|description| description.as_str()), &inner));
+            let optional = if required.contains(key.as_str()) { "" } else { "?" };
+            text.push_str(&format!("{}{}{}: {};\n", inner, property_name(key), optional, self.type_of(property, &inner)));
+        }
+        text.push_str(indent);
+        text.push('}');
+        text
+    }
+}
+
+fn is_interface(schema: &Value) -> bool {
+    has_properties(schema) && schema.get("allOf").is_none() && schema.get("x-nullable").is_none()
+}
+
+fn has_properties(schema: &Value) -> bool {
+    schema.get("properties").map_or(false, |properties| properties.as_object().map_or(false, |map| !map.is_empty()))
+}
+
+fn function(document: &Value, types: &mut Types, path: &str, verb: &str, operation: &Value, name: &str) -> String {
+    // Inline objects of bodies and results get a type before the function.
+    let mut text = String::new();
+    let mut named = |types: &mut Types, written: String, suffix: &str| {
+        if !written.contains('\n') {
+            return written;
+        }
+        let alias = unique(format!("{}{}", type_name(name), suffix), &mut types.taken);
+        text.push_str(&format!("export type {} = {};\n\n", alias, written));
+        alias
+    };
+
+    // The locals of the functions.
+    let mut taken: BTreeSet<String> = ["params", "options", "path", "query", "headers", "form"].iter().map(|name| name.to_string()).collect();
+    let mut path_arguments = vec![];
+    let mut body = None;
+    let mut fields = vec![];
+    for (_, parameter) in openapi_examples::parameters(document, path, verb) {
+        let parameter_name = parameter.get("name").and_then(|name| name.as_str()).unwrap_or("");
+        let location = parameter.get("in").and_then(|location| location.as_str()).unwrap_or("");
+        let required = location == "path" || parameter.get("required").and_then(|required| required.as_bool()).unwrap_or(false);
+        match location {
+            "path" => path_arguments.push((parameter_name, unique(identifier(parameter_name), &mut taken), types.type_of(parameter, ""))),
+            "body" => {
+                let schema = parameter.get("schema").unwrap_or(&Value::Null);
+                let body_type = named(types, types.type_of(schema, ""), "Body");
+                body = Some((unique(identifier(parameter_name), &mut taken), body_type, required));
+            }
+            _ => fields.push((parameter_name, location, parameter, required)),
+        }
+    }
+    path_arguments.sort_by_key(|&(parameter_name, _, _)| path.find(&format!("{{{}}}", parameter_name)).unwrap_or(path.len()));
+
+    let mut arguments: Vec<String> = path_arguments.iter().map(|&(_, ref argument, ref argument_type)| format!("{}: {}", argument, argument_type)).collect();
+    let params_required = fields.iter().any(|&(_, _, _, required)| required);
+    if let Some((ref argument, ref argument_type, required)) = body {
+        if required {
+            arguments.push(format!("{}: {}", argument, argument_type));
+        } else if params_required {
+            arguments.push(format!("{}: {} | undefined", argument, argument_type));
+        } else {
+            arguments.push(format!("{}?: {}", argument, argument_type));
+        }
+    }
+    if !fields.is_empty() {
+        let mut params = "params: {\n".to_string();
+        for &(parameter_name, _, parameter, required) in &fields {
+            let optional = if required { "" } else { "?" };
+            params.push_str(&format!("    {}{}: {};\n", property_name(parameter_name), optional, types.type_of(parameter, "    ")));
+        }
+        params.push_str("  }");
+        if !params_required {
+            params.push_str(" = {}");
+        }
+        arguments.push(params);
+    }
+    arguments.push("options: ApiOptions = {}".to_string());
+
+    // Bodies of the 2xx responses, void when none has one.
+    let declared = operation.get("responses").and_then(|responses| responses.as_object());
+    let mut results: Vec<String> = vec![];
+    for (status, response) in declared.into_iter().flat_map(|responses| responses.iter()) {
+        if !status.starts_with('2') {
+            continue;
+        }
+        let response = openapi_examples::resolve(document, response);
+        let result = match response.get("schema") {
+            Some(schema) => {
+                let written = types.type_of(schema, "");
+                named(types, written, "Result")
+            }
+            None => "void".to_string(),
+        };
+        if !results.contains(&result) {
+            results.push(result);
+        }
+    }
+    if results.len() > 1 {
+        results.retain(|result| result != "void");
+    }
+    let result = if results.is_empty() { "void".to_string() } else { results.join(" | ") };
+
+    let summary = operation.get("summary").or_else(|| operation.get("description")).and_then(|summary| summary.as_str());
+    text.push_str(&doc_comment(Some(format!("{}\n{} {}", summary.unwrap_or("").trim(), verb.to_uppercase(), path).trim()), ""));
+    text.push_str(&format!("export async function {}(\n", name));
+    for argument in &arguments {
+        text.push_str(&format!("  {},\n", argument));
+    }
+    text.push_str(&format!("): Promise<{}> {{\n", result));
+
+    // The path, literal text and parameters in turn.
+    let mut pieces = vec![];
+    let mut rest = path;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}').map_or(rest.len(), |end| start + end);
+        let parameter_name = &rest[start + 1..end];
+        if start > 0 {
+            pieces.push(quote(&rest[..start]));
+        }
+        match path_arguments.iter().find(|&&(name, _, _)| name == parameter_name) {
+            Some(&(_, ref argument, _)) => pieces.push(format!("encodeURIComponent(String({}))", argument)),
+            None => pieces.push(quote(&rest[start..(end + 1).min(rest.len())])),
+        }
+        rest = &rest[(end + 1).min(rest.len())..];
+    }
+    if !rest.is_empty() || pieces.is_empty() {
+        pieces.push(quote(rest));
+    }
+    text.push_str(&format!("  const path = {};\n", pieces.join(" + ")));
+
+    text.push_str("  const query = new URLSearchParams();\n");
+    text.push_str("  const headers: Record<string, string> = {};\n");
+    let is_form = fields.iter().any(|&(_, location, _, _)| location == "formData");
+    if is_form {
+        text.push_str("  const form = new URLSearchParams();\n");
+    }
+    for &(parameter_name, location, parameter, _) in &fields {
+        let target = match location {
+            "query" => "query",
+            "header" => "headers",
+            "formData" => "form",
+            _ => continue,
+        };
+        let separator = match parameter.get("collectionFormat").and_then(|format| format.as_str()) {
+            Some("multi") if location != "header" => "",
+            Some("ssv") => "\" \"",
+            Some("tsv") => "\"\\t\"",
+            Some("pipes") => "\"|\"",
+            _ => "\",\"",
+        };
+        let value = format!("params{}", member(parameter_name));
+        if parameter.get("type").and_then(|parameter_type| parameter_type.as_str()) == Some("array") && !separator.is_empty() {
+            text.push_str(&format!("  add({}, {}, {}, {});\n", target, quote(parameter_name), value, separator));
+        } else {
+            text.push_str(&format!("  add({}, {}, {});\n", target, quote(parameter_name), value));
+        }
+    }
+    let sent = match body {
+        Some((ref argument, _, required)) => {
+            text.push_str("  headers[\"Content-Type\"] = \"application/json\";\n");
+            if required {
+                format!("JSON.stringify({})", argument)
+            } else {
+                format!("{} === undefined ? undefined : JSON.stringify({})", argument, argument)
+            }
+        }
+        None if is_form => {
+            text.push_str("  headers[\"Content-Type\"] = \"application/x-www-form-urlencoded\";\n");
+            "form.toString()".to_string()
+        }
+        None => "undefined".to_string(),
+    };
+    let call = format!("send(options, {}, path, query, headers, {})", quote(&verb.to_uppercase()), sent);
+    if result == "void" {
+        text.push_str(&format!("  await {};\n}}\n", call));
+    } else {
+        text.push_str(&format!("  return (await {}) as {};\n}}\n", call, result));
+    }
+    text
+}
+
+// pets.photos.upload -> petsPhotosUpload, JavaScript keywords get a _.
+fn identifier(name: &str) -> String {
+    let camel = words(name).to_camel_case();
+    match camel.chars().next() {
+        None => "unnamed".to_string(),
+        Some(c) if c.is_numeric() => format!("n{}", camel),
+        Some(_) if KEYWORDS.contains(&camel.as_str()) => format!("{}_", camel),
+        Some(_) => camel,
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+// Property names which are not identifiers are quoted: "X-Request-Id".
+fn property_name(name: &str) -> String {
+    if is_identifier(name) {
+        name.to_string()
+    } else {
+        quote(name)
+    }
+}
+
+// .limit or ["X-Request-Id"]
+fn member(name: &str) -> String {
+    if is_identifier(name) {
+        format!(".{}", name)
+    } else {
+        format!("[{}]", quote(name))
+    }
+}
+
+// A string literal, JSON ones are valid TypeScript.
+fn quote(text: &str) -> String {
+    Value::String(text.to_string()).to_string()
+}
+
+fn doc_comment(text: Option<&str>, indent: &str) -> String {
+    let text = match text.map(|text| text.trim()) {
+        Some(text) if !text.is_empty() => text.replace("*/", "*\\/"),
+        _ => return String::new(),
+    };
+    let lines: Vec<&str> = text.lines().map(|line| line.trim_end()).collect();
+    if lines.len() == 1 {
+        return format!("{}/** {} */\n", indent, lines[0]);
+    }
+    let mut comment = format!("{}/**\n", indent);
+    for line in lines {
+        if line.is_empty() {
+            comment.push_str(&format!("{} *\n", indent));
+        } else {
+            comment.push_str(&format!("{} * {}\n", indent, line));
+        }
+    }
+    comment.push_str(&format!("{} */\n", indent));
+    comment
+}
+
+// Reserved words of JavaScript and TypeScript, which can not name a function or an argument.
+const KEYWORDS: [&str; 45] = [
+    "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete", "do", "else",
+    "enum", "export", "extends", "false", "finally", "for", "function", "if", "implements", "import", "in",
+    "instanceof", "interface", "let", "new", "null", "package", "private", "protected", "public", "return",
+    "static", "super", "switch", "this", "throw", "true", "try", "typeof", "var", "void", "while", "with",
+];
+
+// The part of the module which is the same for every spec.
+const SUPPORT: &str = r#"
+export const BASE_URL = {base_url};
+
+export interface ApiOptions {
+  /** Defaults to BASE_URL. */
+  baseUrl?: string;
+  /** Defaults to the global fetch. */
+  fetch?: typeof fetch;
+  /** Sent with every request, for authorization for example. */
+  headers?: Record<string, string>;
+}
+
+/** The API answered with a status which is not 2xx. */
+export class ApiError extends globalThis.Error {
+  readonly status: number;
+  readonly body: unknown;
+
+  constructor(status: number, body: unknown) {
+    super(`The API answered with the status ${status}`);
+    this.status = status;
+    this.body = body;
+  }
+}
+
+function add(
+  target: URLSearchParams | Record<string, string>,
+  name: string,
+  value: unknown,
+  separator?: string,
+): void {
+  if (value === undefined || value === null) {
+    return;
+  }
+  const texts = Array.isArray(value) ? value.map(String) : [String(value)];
+  if (target instanceof URLSearchParams) {
+    if (separator === undefined) {
+      texts.forEach((text) => target.append(name, text));
+    } else {
+      target.append(name, texts.join(separator));
+    }
+  } else {
+    target[name] = texts.join(separator ?? ",");
+  }
+}
+
+async function send(
+  options: ApiOptions,
+  method: string,
+  path: string,
+  query: URLSearchParams,
+  headers: Record<string, string>,
+  body: string | undefined,
+): Promise<unknown> {
+  const search = query.toString();
+  const url = (options.baseUrl ?? BASE_URL) + path + (search ? "?" + search : "");
+  const response = await (options.fetch ?? fetch)(url, {
+    method,
+    headers: { ...options.headers, ...headers },
+    body,
+  });
+  const text = await response.text();
+  let parsed: unknown = undefined;
+  if (text.trim()) {
+    try {
+      parsed = JSON.parse(text);
+    } catch {
+      parsed = text;
+    }
+  }
+  if (response.status < 200 || response.status >= 300) {
+    throw new ApiError(response.status, parsed);
+  }
+  return parsed;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    fn module_of(text: &str) -> String {
+        module(&json(text))
+    }
+
+    #[test]
+    fn makes_types_for_definitions() {
+        let text = module_of(r##"{
+            "swagger": "2.0",
+            "info": {"title": "Pets"},
+            "paths": {},
+            "definitions": {
+                "Pet": {"type": "object", "description": "A pet.", "required": ["name"], "properties": {
+                    "name": {"type": "string"},
+                    "kind": {"$ref": "#/definitions/Kind"},
+                    "X-Tag": {"type": "array", "items": {"type": "integer"}},
+                    "home": {"type": "object", "properties": {"street": {"type": "string"}}},
+                    "extra": {"type": "object", "additionalProperties": {"type": "boolean"}}
+                }},
+                "Kind": {"type": "string", "enum": ["cat", "dog"]},
+                "Names": {"type": "array", "items": {"type": "string"}}
+            }
+        }"##);
+        assert!(text.starts_with("// Types and client of Pets, generated by oatool."));
+        assert!(text.contains(&[
+            "/** A pet. */\n",
+            "export interface Pet {\n",
+            "  name: string;\n",
+            "  kind?: Kind;\n",
+            "  \"X-Tag\"?: number[];\n",
+            "  home?: {\n    street?: string;\n  };\n",
+            "  extra?: Record<string, boolean>;\n",
+            "}\n",
+        ].concat()));
+        assert!(text.contains("export type Kind = \"cat\" | \"dog\";\n"));
+        assert!(text.contains("export type Names = string[];\n"));
+    }
+
+    #[test]
+    fn makes_a_function_per_operation() {
+        let text = module_of(r##"{
+            "swagger": "2.0",
+            "info": {"title": "Pet Shop"},
+            "host": "api.example.com", "basePath": "/v1",
+            "paths": {
+                "/pets/{pet_id}": {
+                    "parameters": [{"name": "pet_id", "in": "path", "required": true, "type": "integer"}],
+                    "get": {
+                        "operationId": "pets.get",
+                        "summary": "Gets a pet.",
+                        "parameters": [
+                            {"name": "fields", "in": "query", "type": "array", "items": {"type": "string"}, "collectionFormat": "multi"},
+                            {"name": "X-Trace", "in": "header", "required": true, "type": "string"}
+                        ],
+                        "responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Pet"}}}
+                    }
+                },
+                "/pets": {
+                    "post": {
+                        "operationId": "createPet",
+                        "parameters": [{"name": "body", "in": "body", "required": true, "schema": {"$ref": "#/definitions/Pet"}}],
+                        "responses": {"201": {"description": "made", "schema": {"$ref": "#/definitions/Pet"}}}
+                    }
+                }
+            },
+            "definitions": {"Pet": {"type": "object", "properties": {"name": {"type": "string"}}}}
+        }"##);
+        assert!(text.contains("export const BASE_URL = \"https://api.example.com/v1\";\n"));
+        assert!(text.contains(&[
+            "/**\n * Gets a pet.\n * GET /pets/{pet_id}\n */\n",
+            "export async function petsGet(\n",
+            "  petId: number,\n",
+            "  params: {\n    fields?: string[];\n    \"X-Trace\": string;\n  },\n",
+            "  options: ApiOptions = {},\n",
+            "): Promise<Pet> {\n",
+            "  const path = \"/pets/\" + encodeURIComponent(String(petId));\n",
+        ].concat()));
+        assert!(text.contains("  add(headers, \"X-Trace\", params[\"X-Trace\"]);\n"));
+        assert!(text.contains(&[
+            "/** POST /pets */\n",
+            "export async function createPet(\n  body: Pet,\n  options: ApiOptions = {},\n): Promise<Pet> {\n",
+        ].concat()));
+        assert!(text.contains("  return (await send(options, \"POST\", path, query, headers, JSON.stringify(body))) as Pet;\n"));
+    }
+
+    #[test]
+    fn makes_identifiers_and_members() {
+        assert_eq!(identifier("pets.photos.upload"), "petsPhotosUpload");
+        assert_eq!(identifier("delete"), "delete_");
+        assert_eq!(identifier("2fa"), "n2Fa");
+        assert_eq!(property_name("X-Trace"), "\"X-Trace\"");
+        assert_eq!(property_name("$ref_1"), "$ref_1");
+        assert_eq!(member("limit"), ".limit");
+        assert_eq!(member("X-Trace"), "[\"X-Trace\"]");
+        assert_eq!(doc_comment(Some("Ends a */ comment"), "  "), "  /** Ends a *\\/ comment */\n");
+        assert_eq!(doc_comment(Some("  "), ""), "");
+    }
+}
@@ -0,0 +1,422 @@
+// This module renders an HTML reference of the API in a single page.
+// Everything is inline so the page works offline: the style is in the page and
+// schemas fold with <details> instead of JavaScript.
+// The structure comes from the typed spec, the schemas from the untyped
+// document because the typed ones do not keep everything.
+
+use openapi;
+use openapi_extensions::{self, pointer};
+use serde_json::{self, Value};
+
+use openapi_examples::Examples;
+
+const STYLE: &str = "
+body { margin: 0; font: 15px/1.5 -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; color: #24292e; }
+nav { position: fixed; top: 0; bottom: 0; width: 260px; overflow-y: auto; padding: 16px; background: #f6f8fa; border-right: 1px solid #e1e4e8; box-sizing: border-box; }
+nav ul { list-style: none; padding-left: 8px; }
+nav a { color: #0366d6; text-decoration: none; }
+main { margin-left: 260px; padding: 16px 32px; max-width: 960px; }
+code, pre { font-family: SFMono-Regular, Consolas, Menlo, monospace; font-size: 13px; }
+pre { background: #f6f8fa; padding: 12px; overflow-x: auto; }
+table { border-collapse: collapse; margin: 8px 0; }
+th, td { border: 1px solid #dfe2e5; padding: 4px 10px; text-align: left; vertical-align: top; }
+.operation { border-top: 1px solid #e1e4e8; padding-top: 8px; margin-top: 24px; }
+.verb { display: inline-block; min-width: 64px; text-align: center; color: #fff; border-radius: 3px; font-size: 13px; }
+.get { background: #2188ff; } .post { background: #28a745; } .put { background: #f66a0a; }
+.patch { background: #6f42c1; } .delete { background: #d73a49; } .options, .head { background: #6a737d; }
+.type { color: #6a737d; } .required { color: #d73a49; font-size: 12px; }
+details ul { list-style: none; padding-left: 20px; border-left: 1px dotted #d1d5da; }
+";
+
+struct Operation<'a> {
+    path: &'a str,
+    verb: &'a str,
+    operation: &'a openapi::Operation,
+    // Shared by every operation of the path.
+    parameters: &'a Option<Vec<openapi::ParameterOrRef>>,
+}
+
+impl<'a> Operation<'a> {
+    fn anchor(&self) -> String {
+        match self.operation.operation_id {
+            Some(ref id) => format!("operation-{}", slug(id)),
+            None => format!("operation-{}-{}", self.verb, slug(self.path)),
+        }
+    }
+
+    // The first tag, or the resource: the first segment of the path.
+    fn group(&self) -> String {
+        let tag = self.operation.tags.as_ref().and_then(|tags| tags.first());
+        let resource = self.path.split('/').find(|segment| !segment.is_empty() && !segment.starts_with('{'));
+        tag.map(|tag| tag.as_str()).or(resource).unwrap_or("default").to_string()
+    }
+}
+
+// document is the spec as it was read, see Document.source.
+pub fn html(spec: &openapi::Spec, document: &Value) -> String {
+    let groups = groups(spec);
+    let title = spec.info.title.clone().unwrap_or_else(|| "API reference".to_string());
+    let definitions = document.get("definitions");
+    let examples = Examples::new(document);
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n", escape(&title), STYLE));
+
+    html.push_str("<nav>\n");
+    html.push_str(&format!("<strong>{}</strong>\n", escape(&title)));
+    for &(ref group, ref operations) in &groups {
+        html.push_str(&format!("<p><a href=\"#group-{}\">{}</a></p>\n<ul>\n", slug(group), escape(group)));
+        for operation in operations {
+            html.push_str(&format!(
+                "<li><a href=\"#{}\">{} {}</a></li>\n",
+                operation.anchor(),
+                operation.verb.to_uppercase(),
+                escape(operation.path)
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+    if let Some(ref definitions) = spec.definitions {
+        html.push_str("<p><a href=\"#definitions\">Definitions</a></p>\n<ul>\n");
+        for name in definitions.keys() {
+            html.push_str(&format!("<li><a href=\"#definition-{}\">{}</a></li>\n", slug(name), escape(name)));
+        }
+        html.push_str("</ul>\n");
+    }
+    html.push_str("</nav>\n<main>\n");
+
+    html.push_str(&format!("<h1>{}</h1>\n", escape(&title)));
+    if let Some(ref version) = spec.info.version {
+        html.push_str(&format!("<p>Version {}</p>\n", escape(version)));
+    }
+    if let Some(ref description) = spec.info.description {
+        html.push_str(&paragraphs(description));
+    }
+    html.push_str(&format!("<p>Base URL: <code>{}</code></p>\n", escape(&base_url(spec))));
+
+    for &(ref group, ref operations) in &groups {
+        html.push_str(&format!("<section id=\"group-{}\">\n<h2>{}</h2>\n", slug(group), escape(group)));
+        let description = spec.tags
+            .iter()
+            .flat_map(|tags| tags.iter())
+            .find(|tag| &tag.name == group)
+            .and_then(|tag| tag.description.as_ref());
+        if let Some(description) = description {
+            html.push_str(&paragraphs(description));
+        }
+        for operation in operations {
+            operation_html(spec, document, &examples, operation, &mut html);
+        }
+        html.push_str("</section>\n");
+    }
+
+    if let Some(map) = definitions.and_then(|definitions| definitions.as_object()) {
+        html.push_str("<section id=\"definitions\">\n<h2>Definitions</h2>\n");
+        for (name, definition) in map {
+            html.push_str(&format!("<h3 id=\"definition-{}\">{}</h3>\n", slug(name), escape(name)));
+            if let Some(description) = definition.get("description").and_then(|description| description.as_str()) {
+                html.push_str(&paragraphs(description));
+            }
+            schema_html(definition, &mut html);
+            example_html(definition, &examples, &mut html);
+        }
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</main>\n</body>\n</html>\n");
+    html
+}
+
+// Groups in the order of the tags of the spec, then in the order they appear.
+fn groups<'a>(spec: &'a openapi::Spec) -> Vec<(String, Vec<Operation<'a>>)> {
+    let mut groups: Vec<(String, Vec<Operation<'a>>)> = spec.tags
+        .iter()
+        .flat_map(|tags| tags.iter())
+        .map(|tag| (tag.name.clone(), vec![]))
+        .collect();
+
+    for (path, operations) in &spec.paths {
+        for (verb, operation) in openapi_extensions::operations(operations) {
+            let operation = Operation { path: path, verb: verb, operation: operation, parameters: &operations.parameters };
+            let group = operation.group();
+            match groups.iter().position(|&(ref name, _)| name == &group) {
+                Some(index) => groups[index].1.push(operation),
+                None => groups.push((group, vec![operation])),
+            }
+        }
+    }
+    groups.retain(|&(_, ref operations)| !operations.is_empty());
+    groups
+}
+
+fn operation_html(spec: &openapi::Spec, document: &Value, examples: &Examples, operation: &Operation, html: &mut String) {
+    html.push_str(&format!("<article class=\"operation\" id=\"{}\">\n", operation.anchor()));
+    html.push_str(&format!(
+        "<h3><span class=\"verb {}\">{}</span> <code>{}</code></h3>\n",
+        operation.verb,
+        operation.verb.to_uppercase(),
+        escape(operation.path)
+    ));
+    if let Some(ref summary) = operation.operation.summary {
+        html.push_str(&format!("<p><strong>{}</strong></p>\n", escape(summary)));
+    }
+    if let Some(ref id) = operation.operation.operation_id {
+        html.push_str(&format!("<p>operationId: <code>{}</code></p>\n", escape(id)));
+    }
+    if let Some(ref description) = operation.operation.description {
+        html.push_str(&paragraphs(description));
+    }
+
+    let parameters = parameters(spec, operation);
+    let (body, parameters): (Vec<_>, Vec<_>) = parameters.into_iter().partition(|&(ref p, _)| p.location == "body");
+    if !parameters.is_empty() {
+        html.push_str("<h4>Parameters</h4>\n<table>\n<tr><th>Name</th><th>In</th><th>Type</th><th>Required</th><th>Description</th></tr>\n");
+        for &(ref parameter, _) in &parameters {
+            let parameter_type = match (&parameter.param_type, &parameter.format) {
+                (&Some(ref parameter_type), &Some(ref format)) => format!("{} ({})", parameter_type, format),
+                (&Some(ref parameter_type), &None) => parameter_type.to_string(),
+                _ => String::new(),
+            };
+            html.push_str(&format!(
+                "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&parameter.name),
+                escape(&parameter.location),
+                escape(&parameter_type),
+                if parameter.required == Some(true) { "yes" } else { "no" },
+                escape(parameter.description.as_ref().map_or("", |description| description.as_str()))
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    for &(ref parameter, ref at) in &body {
+        html.push_str("<h4>Request body</h4>\n");
+        if let Some(ref description) = parameter.description {
+            html.push_str(&paragraphs(description));
+        }
+        if let Some(schema) = document.pointer(&format!("{}/schema", at)) {
+            schema_html(schema, html);
+            example_html(schema, examples, html);
+        }
+    }
+
+    html.push_str("<h4>Responses</h4>\n");
+    for (status, response) in &operation.operation.responses {
+        html.push_str(&format!("<p><code>{}</code> {}</p>\n", escape(status), escape(&response.description)));
+        let at = pointer(&["paths", operation.path, operation.verb, "responses", status, "schema"]);
+        if let Some(schema) = document.pointer(&at) {
+            schema_html(schema, html);
+            example_html(schema, examples, html);
+        }
+    }
+    html.push_str("</article>\n");
+}
+
+// (parameter, JSON pointer of its declaration), the operation ones override the path ones.
+fn parameters(spec: &openapi::Spec, operation: &Operation) -> Vec<(openapi::Parameter, String)> {
+    let mut parameters: Vec<(openapi::Parameter, String)> = vec![];
+    let lists = [
+        (&operation.operation.parameters, pointer(&["paths", operation.path, operation.verb, "parameters"])),
+        (operation.parameters, pointer(&["paths", operation.path, "parameters"])),
+    ];
+    for &(declared, ref location) in &lists {
+        for (index, parameter) in declared.iter().flat_map(|declared| declared.iter()).enumerate() {
+            let resolved = match *parameter {
+                openapi::ParameterOrRef::Ref { ref ref_path } => {
+                    let name = ref_path.trim_start_matches("#/parameters/");
+                    spec.parameters
+                        .as_ref()
+                        .and_then(|parameters| parameters.get(name))
+                        .map(|parameter| (parameter.clone(), pointer(&["parameters", name])))
+                }
+                _ => serde_json::to_value(parameter)
+                    .and_then(serde_json::from_value)
+                    .ok()
+                    .map(|parameter| (parameter, format!("{}/{}", location, index))),
+            };
+            if let Some((parameter, at)) = resolved {
+                if !parameters.iter().any(|&(ref p, _)| p.name == parameter.name && p.location == parameter.location) {
+                    parameters.push((parameter, at));
+                }
+            }
+        }
+    }
+    parameters
+}
+
+// A tree of the properties, each level folds. References link to their definition.
+fn schema_html(schema: &Value, html: &mut String) {
+    if has_children(schema) {
+        html.push_str(&format!("<details open>\n<summary>{}</summary>\n", type_html(schema)));
+        children_html(schema, html);
+        html.push_str("</details>\n");
+    } else {
+        html.push_str(&format!("<p>{}</p>\n", type_html(schema)));
+    }
+}
+
+fn children_html(schema: &Value, html: &mut String) {
+    let schema = match schema.get("items") {
+        Some(items) if schema.get("type").and_then(|t| t.as_str()) == Some("array") => items,
+        _ => schema,
+    };
+    let properties = match schema.get("properties").and_then(|properties| properties.as_object()) {
+        Some(properties) if schema.get("$ref").is_none() => properties,
+        _ => return,
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(|required| required.as_array())
+        .map(|required| required.iter().filter_map(|name| name.as_str()).collect())
+        .unwrap_or_else(Vec::new);
+
+    html.push_str("<ul>\n");
+    for (name, property) in properties {
+        let mut line = format!("<code>{}</code> <span class=\"type\">{}</span>", escape(name), type_html(property));
+        if required.contains(&name.as_str()) {
+            line.push_str(" <span class=\"required\">required</span>");
+        }
+        if let Some(description) = property.get("description").and_then(|description| description.as_str()) {
+            line.push_str(&format!(" {}", escape(description)));
+        }
+        if has_children(property) {
+            html.push_str(&format!("<li><details>\n<summary>{}</summary>\n", line));
+            children_html(property, html);
+            html.push_str("</details></li>\n");
+        } else {
+            html.push_str(&format!("<li>{}</li>\n", line));
+        }
+    }
+    html.push_str("</ul>\n");
+}
+
+fn has_children(schema: &Value) -> bool {
+    let schema = schema.get("items").unwrap_or(schema);
+    schema.get("$ref").is_none() && schema.get("properties").is_some()
+}
+
+// object, array of <a>Pet</a>, string (date-time), string: one of a, b
+fn type_html(schema: &Value) -> String {
+    if let Some(ref_path) = schema.get("$ref").and_then(|ref_path| ref_path.as_str()) {
+        let name = ref_path.trim_start_matches("#/definitions/");
+        return format!("<a href=\"#definition-{}\">{}</a>", slug(name), escape(name));
+    }
+    let schema_type = schema.get("type").and_then(|schema_type| schema_type.as_str()).unwrap_or("object");
+    let mut text = match schema.get("items") {
+        Some(items) if schema_type == "array" => format!("array of {}", type_html(items)),
+        _ => escape(schema_type),
+    };
+    if let Some(format) = schema.get("format").and_then(|format| format.as_str()) {
+        text.push_str(&format!(" ({})", escape(format)));
+    }
+    if let Some(values) = schema.get("enum").and_then(|values| values.as_array()) {
+        let values: Vec<String> = values.iter().map(|value| escape(&value.to_string())).collect();
+        text.push_str(&format!(": one of {}", values.join(", ")));
+    }
+    text
+}
+
+fn example_html(schema: &Value, examples: &Examples, html: &mut String) {
+    let value = examples.schema(schema);
+    if let Ok(text) = serde_json::to_string_pretty(&value) {
+        html.push_str(&format!("<details>\n<summary>Example</summary>\n<pre>{}</pre>\n</details>\n", escape(&text)));
+    }
+}
+
+fn base_url(spec: &openapi::Spec) -> String {
+    let scheme = spec.schemes.as_ref().and_then(|schemes| schemes.first()).map_or("https", |scheme| scheme.as_str());
+    let host = spec.host.as_ref().map_or("", |host| host.as_str());
+    let base_path = spec.base_path.as_ref().map_or("", |base_path| base_path.as_str());
+    if host.is_empty() {
+        base_path.to_string()
+    } else {
+        format!("{}://{}{}", scheme, host, base_path)
+    }
+}
+
+// Blank lines separate paragraphs.
+fn paragraphs(text: &str) -> String {
+    text.split("\n\n")
+        .filter(|paragraph| !paragraph.trim().is_empty())
+        .map(|paragraph| format!("<p>{}</p>\n", escape(paragraph.trim())))
+        .collect()
+}
+
+// Anchors: letters, digits and - only.
+fn slug(text: &str) -> String {
+    let slug: String = text
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    slug.split('-').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+// Also for the XML of testgen.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    fn document() -> Value {
+        json(r##"{
+            "swagger": "2.0",
+            "info": {"title": "Pets & <Friends>", "version": "1", "description": "First.\n\nSecond."},
+            "host": "api.example.com", "basePath": "/v1", "schemes": ["http"],
+            "tags": [{"name": "unused"}, {"name": "pets", "description": "The pets."}],
+            "paths": {
+                "/health": {"get": {"responses": {"200": {"description": "ok"}}}},
+                "/pets/{id}": {
+                    "get": {"operationId": "pets.get", "tags": ["pets"], "responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Pet"}}}}
+                }
+            },
+            "definitions": {"Pet": {
+                "type": "object",
+                "properties": {"name": {"type": "string"}, "kind": {"type": "string", "enum": ["cat", "dog"]}},
+                "example": {"name": "Rex"}
+            }}
+        }"##)
+    }
+
+    fn page() -> String {
+        let document = document();
+        html(&serde_json::from_value(document.clone()).unwrap(), &document)
+    }
+
+    #[test]
+    fn groups_operations_by_tag_then_resource() {
+        let page = page();
+        let pets = page.find("<section id=\"group-pets\">").unwrap();
+        let health = page.find("<section id=\"group-health\">").unwrap();
+        assert!(pets < health);
+        assert!(!page.contains("group-unused"));
+        assert!(page.contains("<p>The pets.</p>"));
+        assert!(page.contains("<li><a href=\"#operation-pets-get\">GET /pets/{id}</a></li>"));
+        assert!(page.contains("<article class=\"operation\" id=\"operation-get-health\">"));
+    }
+
+    #[test]
+    fn escapes_and_links_everything() {
+        let page = page();
+        assert!(page.contains("<title>Pets &amp; &lt;Friends&gt;</title>"));
+        assert!(page.contains("<p>First.</p>\n<p>Second.</p>\n"));
+        assert!(page.contains("<code>http://api.example.com/v1</code>"));
+        assert!(page.contains("<a href=\"#definition-pet\">Pet</a>"));
+        assert!(page.contains("string: one of &quot;cat&quot;, &quot;dog&quot;"));
+        assert!(page.contains("<pre>{\n  &quot;name&quot;: &quot;Rex&quot;\n}</pre>"));
+    }
+
+    #[test]
+    fn makes_anchors() {
+        assert_eq!(slug("Users / Admin_v2"), "users-admin-v2");
+        assert_eq!(slug("/pets/{id}"), "pets-id");
+        assert_eq!(escape("a < b && \"c\" > d"), "a &lt; b &amp;&amp; &quot;c&quot; &gt; d");
+        assert_eq!(paragraphs("\n\nOne\n\n \n\nTwo <b>\n"), "<p>One</p>\n<p>Two &lt;b&gt;</p>\n");
+    }
+}
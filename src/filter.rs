@@ -0,0 +1,320 @@
+// This module extracts a subset of an OpenAPI file.
+// Operations are kept or dropped by tag, path and operationId, then only the
+// definitions, parameters and responses the kept operations use are carried over.
+
+use openapi_extensions::VERBS;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+#[derive(Default)]
+pub struct FilterOptions {
+    pub tags: Vec<String>,
+    // Globs, * matches inside a segment and ** across segments.
+    pub paths: Vec<String>,
+    // Globs, * matches anything.
+    pub operations: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    pub exclude_paths: Vec<String>,
+    pub exclude_operations: Vec<String>,
+}
+
+impl FilterOptions {
+    // With no inclusion filter every operation is included.
+    fn keeps(&self, path: &str, operation: &Value) -> bool {
+        let tags: Vec<&str> = operation
+            .get("tags")
+            .and_then(|tags| tags.as_array())
+            .map(|tags| tags.iter().filter_map(|tag| tag.as_str()).collect())
+            .unwrap_or_else(Vec::new);
+        let operation_id = operation.get("operationId").and_then(|id| id.as_str());
+
+        let by_tag = |wanted: &[String]| wanted.iter().any(|tag| tags.contains(&tag.as_str()));
+        let by_path = |globs: &[String]| globs.iter().any(|glob| glob_match(glob, path, Some('/')));
+        let by_operation = |globs: &[String]| {
+            operation_id.map_or(false, |id| globs.iter().any(|glob| glob_match(glob, id, None)))
+        };
+
+        let included = (self.tags.is_empty() || by_tag(&self.tags))
+            && (self.paths.is_empty() || by_path(&self.paths))
+            && (self.operations.is_empty() || by_operation(&self.operations));
+        let excluded = by_tag(&self.exclude_tags) || by_path(&self.exclude_paths) || by_operation(&self.exclude_operations);
+
+        included && !excluded
+    }
+}
+
+pub fn filter(mut document: Value, options: &FilterOptions) -> Value {
+    let paths = match document.get("paths") {
+        Some(&Value::Object(ref paths)) => paths.clone(),
+        _ => Map::new(),
+    };
+
+    let mut kept_paths = Map::new();
+    for (path, item) in paths {
+        let mut item = match item {
+            Value::Object(item) => item,
+            _ => continue,
+        };
+        let dropped: Vec<String> = item.iter()
+            .filter(|&(key, operation)| VERBS.contains(&key.as_str()) && !options.keeps(&path, operation))
+            .map(|(key, _)| key.to_string())
+            .collect();
+        for verb in dropped {
+            item.remove(&verb);
+        }
+        if item.keys().any(|key| VERBS.contains(&key.as_str())) {
+            kept_paths.insert(path, Value::Object(item));
+        }
+    }
+
+    let kept_paths = Value::Object(kept_paths);
+    let used = used_references(&document, &kept_paths);
+    let used_tags = used_tags(&kept_paths);
+    let used_security = used_security(&document, &kept_paths);
+
+    if let Some(map) = document.as_object_mut() {
+        map.insert("paths".to_string(), kept_paths);
+        for &(key, reference) in &[("definitions", "#/definitions/"), ("parameters", "#/parameters/"), ("responses", "#/responses/")] {
+            retain_map(map, key, |name| used.contains(&format!("{}{}", reference, name)));
+        }
+        retain_map(map, "securityDefinitions", |name| used_security.contains(name));
+        if let Some(&mut Value::Array(ref mut tags)) = map.get_mut("tags") {
+            tags.retain(|tag| tag.get("name").and_then(|name| name.as_str()).map_or(false, |name| used_tags.contains(name)));
+        }
+    }
+
+    document
+}
+
+
+fn retain_map<F>(map: &mut Map<String, Value>, key: &str, keep: F)
+where
+    F: Fn(&str) -> bool,
+{
+    let remaining = match map.get(key) {
+        Some(&Value::Object(ref elements)) => elements
+            .iter()
+            .filter(|&(name, _)| keep(name))
+            .map(|(name, element)| (name.to_string(), element.clone()))
+            .collect::<Map<String, Value>>(),
+        _ => return,
+    };
+    if remaining.is_empty() {
+        map.remove(key);
+    } else {
+        map.insert(key.to_string(), Value::Object(remaining));
+    }
+}
+
+// References reachable from the paths, following references inside definitions,
+// parameters and responses.
+fn used_references(document: &Value, paths: &Value) -> BTreeSet<String> {
+    let mut used = BTreeSet::new();
+    let mut pending = vec![];
+    collect_refs(paths, &mut pending);
+
+    while let Some(reference) = pending.pop() {
+        if used.contains(&reference) {
+            continue;
+        }
+        if let Some(target) = resolve(document, &reference) {
+            collect_refs(target, &mut pending);
+        }
+        used.insert(reference);
+    }
+    used
+}
+
+// "#/definitions/User" -> the User definition
+pub fn resolve<'a>(document: &'a Value, reference: &str) -> Option<&'a Value> {
+    if reference.starts_with('#') {
+        document.pointer(&reference[1..])
+    } else {
+        None
+    }
+}
+
+fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+    match *value {
+        Value::Object(ref map) => {
+            for (key, child) in map {
+                match (key.as_str(), child.as_str()) {
+                    ("$ref", Some(reference)) => refs.push(reference.to_string()),
+                    _ => collect_refs(child, refs),
+                }
+            }
+        }
+        Value::Array(ref array) => {
+            for child in array {
+                collect_refs(child, refs);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn operations(paths: &Value) -> Vec<&Value> {
+    paths
+        .as_object()
+        .into_iter()
+        .flat_map(|paths| paths.values())
+        .filter_map(|item| item.as_object())
+        .flat_map(|item| item.iter().filter(|&(verb, _)| VERBS.contains(&verb.as_str())).map(|(_, operation)| operation))
+        .collect()
+}
+
+fn used_tags(paths: &Value) -> BTreeSet<String> {
+    operations(paths)
+        .into_iter()
+        .filter_map(|operation| operation.get("tags").and_then(|tags| tags.as_array()))
+        .flat_map(|tags| tags.iter().filter_map(|tag| tag.as_str()).map(|tag| tag.to_string()))
+        .collect()
+}
+
+fn used_security(document: &Value, paths: &Value) -> BTreeSet<String> {
+    let mut requirements: Vec<&Value> = operations(paths).into_iter().filter_map(|operation| operation.get("security")).collect();
+    requirements.extend(document.get("security"));
+
+    requirements
+        .into_iter()
+        .filter_map(|security| security.as_array())
+        .flat_map(|security| security.iter())
+        .filter_map(|requirement| requirement.as_object())
+        .flat_map(|requirement| requirement.keys().map(|name| name.to_string()))
+        .collect()
+}
+
+// * matches any text without the separator, ** matches anything.
+// Without separator both match anything. /users/** also matches /users itself.
+pub fn glob_match(glob: &str, text: &str, separator: Option<char>) -> bool {
+    if glob.ends_with("/**") && text == &glob[..glob.len() - 3] {
+        return true;
+    }
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_chars(&glob, &text, separator)
+}
+
+fn glob_match_chars(glob: &[char], text: &[char], separator: Option<char>) -> bool {
+    match glob.first() {
+        None => text.is_empty(),
+        Some(&'*') => {
+            let (anything, rest) = if glob.get(1) == Some(&'*') {
+                (true, &glob[2..])
+            } else {
+                (separator.is_none(), &glob[1..])
+            };
+            for consumed in 0..text.len() + 1 {
+                if glob_match_chars(rest, &text[consumed..], separator) {
+                    return true;
+                }
+                if consumed < text.len() && !anything && Some(text[consumed]) == separator {
+                    return false;
+                }
+            }
+            false
+        }
+        Some(&c) => text.first() == Some(&c) && glob_match_chars(&glob[1..], &text[1..], separator),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    fn document() -> Value {
+        json(r##"{
+            "swagger": "2.0",
+            "security": [{"basic": []}],
+            "tags": [{"name": "public"}, {"name": "internal"}],
+            "paths": {
+                "/users": {
+                    "parameters": [{"$ref": "#/parameters/Trace"}],
+                    "get": {"operationId": "users.list", "tags": ["public"], "responses": {"200": {"$ref": "#/responses/Users"}}},
+                    "post": {"operationId": "internal.create", "tags": ["internal"], "security": [{"key": []}], "responses": {}}
+                },
+                "/users/{id}": {"get": {"operationId": "users.get", "tags": ["public"], "responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/User"}}}}},
+                "/groups": {"get": {"operationId": "groups.list", "tags": ["internal"], "responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Group"}}}}}
+            },
+            "definitions": {
+                "User": {"properties": {"address": {"$ref": "#/definitions/Address"}}},
+                "Address": {"type": "object"},
+                "Group": {"type": "object"}
+            },
+            "parameters": {"Trace": {"name": "trace", "in": "header", "type": "string"}},
+            "responses": {"Users": {"description": "ok", "schema": {"type": "array", "items": {"$ref": "#/definitions/User"}}}},
+            "securityDefinitions": {"basic": {"type": "basic"}, "key": {"type": "apiKey", "name": "key", "in": "header"}}
+        }"##)
+    }
+
+    fn keys(value: &Value) -> Vec<&str> {
+        value.as_object().map_or(vec![], |map| map.keys().map(|key| key.as_str()).collect())
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(glob_match("/users/*", "/users/{id}", Some('/')));
+        assert!(!glob_match("/users/*", "/users/{id}/photos", Some('/')));
+        assert!(glob_match("/users/**", "/users/{id}/photos", Some('/')));
+        assert!(glob_match("/users/**", "/users", Some('/')));
+        assert!(!glob_match("/users/**", "/usersettings", Some('/')));
+        assert!(glob_match("/*/photos", "/pets/photos", Some('/')));
+        assert!(glob_match("internal.*", "internal.users.create", None));
+        assert!(glob_match("*.list", "users.list", None));
+        assert!(!glob_match("*.list", "users.listed", None));
+        assert!(glob_match("exact", "exact", None));
+        assert!(!glob_match("", "text", None));
+    }
+
+    #[test]
+    fn keeps_everything_without_filters() {
+        let result = filter(document(), &FilterOptions::default());
+        assert_eq!(result, document());
+    }
+
+    #[test]
+    fn keeps_operations_by_tag_with_what_they_use() {
+        let options = FilterOptions { tags: vec!["public".to_string()], ..FilterOptions::default() };
+        let result = filter(document(), &options);
+        assert_eq!(keys(&result["paths"]), vec!["/users", "/users/{id}"]);
+        assert_eq!(keys(&result["paths"]["/users"]), vec!["parameters", "get"]);
+        assert_eq!(keys(&result["definitions"]), vec!["User", "Address"]);
+        assert_eq!(keys(&result["parameters"]), vec!["Trace"]);
+        assert_eq!(keys(&result["responses"]), vec!["Users"]);
+        assert_eq!(result["tags"], json::<Value>(r#"[{"name": "public"}]"#));
+        // The document security stays usable.
+        assert_eq!(keys(&result["securityDefinitions"]), vec!["basic"]);
+    }
+
+    #[test]
+    fn combines_inclusions_and_exclusions() {
+        let options = FilterOptions {
+            paths: vec!["/users/**".to_string()],
+            exclude_operations: vec!["*.get".to_string()],
+            ..FilterOptions::default()
+        };
+        let result = filter(document(), &options);
+        assert_eq!(keys(&result["paths"]), vec!["/users"]);
+        assert_eq!(keys(&result["paths"]["/users"]), vec!["parameters", "get", "post"]);
+        assert_eq!(keys(&result["securityDefinitions"]), vec!["basic", "key"]);
+
+        let options = FilterOptions {
+            operations: vec!["users.*".to_string()],
+            exclude_tags: vec!["public".to_string()],
+            ..FilterOptions::default()
+        };
+        let result = filter(document(), &options);
+        assert_eq!(keys(&result["paths"]), Vec::<&str>::new());
+        assert!(result.get("definitions").is_none());
+        assert!(result.get("responses").is_none());
+    }
+
+    #[test]
+    fn drops_operations_without_an_operation_id_when_filtering_by_it() {
+        let mut document = document();
+        document["paths"]["/groups"]["get"].as_object_mut().unwrap().remove("operationId");
+        let options = FilterOptions { operations: vec!["*".to_string()], ..FilterOptions::default() };
+        assert_eq!(keys(&filter(document, &options)["paths"]), vec!["/users", "/users/{id}"]);
+    }
+}
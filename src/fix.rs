@@ -0,0 +1,350 @@
+// This module applies the fixes for validation findings which have a single
+// mechanical remedy. It works on the untyped document so everything else in
+// the file is left as it was. Only the findings given are fixed, so the
+// profile, x-oatool-ignore and the baseline decide what changes.
+
+use openapi_extensions::{pointer, VERBS};
+use openapi_validation::{Case, Finding, ValidationOptions};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+
+const SUMMARY_LENGTH: usize = 120;
+
+// Fixes the findings of the validation of document with these options.
+// Returns a description of each fix applied.
+pub fn fix(document: &mut Value, findings: &[&Finding], options: &ValidationOptions) -> Vec<String> {
+    let mut fixes = vec![];
+    let reported = |rule: &str, location: &str| findings.iter().any(|finding| finding.rule == rule && finding.location == location);
+
+    if reported("host-absent", "/host") {
+        if let Some(map) = document.as_object_mut() {
+            if map.remove("host").is_some() {
+                fixes.push("Removed host".to_string());
+            }
+        }
+    }
+
+    for (path, verb, operation) in operations_mut(document) {
+        let name = format!("{} '{}'", verb.to_uppercase(), path);
+        let at = |key: &str| pointer(&["paths", &path, &verb, key]);
+        for key in &["schemes", "consumes", "produces"] {
+            if reported(&format!("operation-{}-absent", key), &at(key)) && operation.remove(*key).is_some() {
+                fixes.push(format!("Removed {} from {}", key, name));
+            }
+        }
+        if reported("summary-length", &at("summary")) && shorten_summary(operation) {
+            fixes.push(format!("Shortened the summary of {}", name));
+        }
+    }
+
+    let misnamed: Vec<&str> = findings
+        .iter()
+        .filter(|finding| finding.rule == "naming-parameter")
+        .map(|finding| finding.location.trim_end_matches("/name"))
+        .collect();
+    rename_path_parameters(document, &misnamed, options.naming.parameters, &mut fixes);
+
+    // Only reported with support for Google, oatool warns about operationIds otherwise.
+    let missing_ids: Vec<&str> = findings
+        .iter()
+        .filter(|finding| finding.rule == "operation-id-exists")
+        .map(|finding| finding.location.as_str())
+        .collect();
+    add_operation_ids(document, &missing_ids, options.naming.operation_ids, &mut fixes);
+
+    fixes
+}
+
+// (path, verb, operation) for every operation of the document.
+fn operations_mut(document: &mut Value) -> Vec<(String, String, &mut Map<String, Value>)> {
+    let paths = match document.get_mut("paths") {
+        Some(&mut Value::Object(ref mut paths)) => paths,
+        _ => return vec![],
+    };
+    paths
+        .iter_mut()
+        .filter_map(|(path, item)| item.as_object_mut().map(|item| (path, item)))
+        .flat_map(|(path, item)| {
+            item.iter_mut()
+                .filter(|&(ref verb, _)| VERBS.contains(&verb.as_str()))
+                .filter_map(move |(verb, operation)| {
+                    operation.as_object_mut().map(|operation| (path.to_string(), verb.to_string(), operation))
+                })
+        })
+        .collect()
+}
+
+// Cuts the summary at a word, the full text goes to the description if there is none.
+// The length is in bytes, like the validation measures it.
+fn shorten_summary(operation: &mut Map<String, Value>) -> bool {
+    let summary = match operation.get("summary").and_then(|summary| summary.as_str()) {
+        Some(summary) if summary.len() > SUMMARY_LENGTH => summary.to_string(),
+        _ => return false,
+    };
+    let mut end = SUMMARY_LENGTH - 3;
+    while !summary.is_char_boundary(end) {
+        end -= 1;
+    }
+    let limit = &summary[..end];
+    let cut = limit.rfind(' ').map_or(limit, |space| &limit[..space]);
+    let shortened = format!("{}...", cut.trim_end());
+
+    if operation.get("description").is_none() {
+        operation.insert("description".to_string(), Value::String(summary.clone()));
+    }
+    operation.insert("summary".to_string(), Value::String(shortened));
+    true
+}
+
+// Renames the path parameters used at these locations, in their declaration
+// (the one in #/parameters for references) and in the template of their path.
+fn rename_path_parameters(document: &mut Value, locations: &[&str], case: Case, fixes: &mut Vec<String>) {
+    // By path, the templated names to replace.
+    let mut renames: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    // By JSON pointer of the declaration, a reference may be used by several paths.
+    let mut renamed: BTreeMap<String, (String, String)> = BTreeMap::new();
+    let paths: Vec<String> = document.get("paths").and_then(|paths| paths.as_object()).map_or(vec![], |paths| paths.keys().cloned().collect());
+
+    // (path, declaration) of each location.
+    let found: Vec<(String, String)> = locations
+        .iter()
+        .filter_map(|location| {
+            let path = paths.iter().find(|path| location.starts_with(&format!("{}/", pointer(&["paths", path]))))?;
+            let declaration = match document.pointer(location).and_then(|parameter| parameter.get("$ref")).and_then(|r| r.as_str()) {
+                Some(ref_path) => ref_path.trim_start_matches('#').to_string(),
+                None => location.to_string(),
+            };
+            Some((path.to_string(), declaration))
+        })
+        .collect();
+    // A shared declaration is only renamed when every path using it is fixed,
+    // the templates of the others would keep the old name.
+    let shared = parameter_references(document);
+    let fixable = |declaration: &str| {
+        shared.get(declaration).map_or(true, |users| users.iter().all(|user| found.contains(&(user.to_string(), declaration.to_string()))))
+    };
+
+    for (path, declaration) in found.iter().cloned().filter(|&(_, ref declaration)| fixable(declaration)) {
+        if !renamed.contains_key(&declaration) {
+            let parameter = match document.pointer_mut(&declaration).and_then(|parameter| parameter.as_object_mut()) {
+                Some(parameter) => parameter,
+                None => continue,
+            };
+            let name = match parameter.get("name").and_then(|name| name.as_str()) {
+                Some(name) if parameter.get("in").and_then(|location| location.as_str()) == Some("path") => name.to_string(),
+                _ => continue,
+            };
+            let new_name = case.apply(&name);
+            if new_name == name {
+                continue;
+            }
+            parameter.insert("name".to_string(), Value::String(new_name.clone()));
+            fixes.push(format!("Renamed the path parameter {} to {}", name, new_name));
+            renamed.insert(declaration.clone(), (name, new_name));
+        }
+        let rename = renamed[&declaration].clone();
+        let path_renames = renames.entry(path).or_insert_with(Vec::new);
+        if !path_renames.contains(&rename) {
+            path_renames.push(rename);
+        }
+    }
+    if renames.is_empty() {
+        return;
+    }
+
+    if let Some(&mut Value::Object(ref mut paths)) = document.get_mut("paths") {
+        // Rebuilt to keep the order of the paths.
+        *paths = mem::replace(paths, Map::new())
+            .into_iter()
+            .map(|(path, item)| {
+                let renamed = renames.get(&path).into_iter().flat_map(|renames| renames.iter()).fold(path.clone(), |path, &(ref old, ref new)| {
+                    path.replace(&format!("{{{}}}", old), &format!("{{{}}}", new))
+                });
+                (renamed, item)
+            })
+            .collect();
+    }
+}
+
+// By JSON pointer of the parameters in #/parameters, the paths referencing them.
+fn parameter_references(document: &Value) -> BTreeMap<String, BTreeSet<String>> {
+    let mut references: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let paths = document.get("paths").and_then(|paths| paths.as_object());
+    for (path, item) in paths.into_iter().flat_map(|paths| paths.iter()) {
+        let operations = VERBS.iter().filter_map(|verb| item.get(verb));
+        let lists = Some(item).into_iter().chain(operations).filter_map(|object| object.get("parameters").and_then(|parameters| parameters.as_array()));
+        for parameter in lists.flat_map(|parameters| parameters.iter()) {
+            if let Some(ref_path) = parameter.get("$ref").and_then(|r| r.as_str()) {
+                references.entry(ref_path.trim_start_matches('#').to_string()).or_insert_with(BTreeSet::new).insert(path.to_string());
+            }
+        }
+    }
+    references
+}
+
+// resource.action, the resource is the last segment which is not a parameter:
+// GET /users -> users.list, GET /users/{id} -> users.get, POST /users -> users.create
+// Only for the operations at these locations.
+fn add_operation_ids(document: &mut Value, locations: &[&str], case: Case, fixes: &mut Vec<String>) {
+    let mut taken: Vec<String> = operations_mut(document)
+        .into_iter()
+        .filter_map(|(_, _, operation)| operation.get("operationId").and_then(|id| id.as_str()).map(|id| id.to_string()))
+        .collect();
+    // Ids are identifiers in generated code, they need some case.
+    let case = if case == Case::Any { Case::Snake } else { case };
+
+    for (path, verb, operation) in operations_mut(document) {
+        if operation.contains_key("operationId") || !locations.contains(&pointer(&["paths", &path, &verb, "operationId"]).as_str()) {
+            continue;
+        }
+        let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+        let resource = match segments.iter().rev().find(|segment| !segment.starts_with('{')) {
+            Some(resource) => case.apply(resource),
+            None => continue,
+        };
+        let on_item = segments.last().map_or(false, |segment| segment.starts_with('{'));
+        let action = match verb.as_str() {
+            "get" if on_item => "get",
+            "get" => "list",
+            "post" => "create",
+            "put" => "update",
+            other => other,
+        };
+        let id = format!("{}.{}", resource, case.apply(action));
+        // Leaves the collisions to the validation, there is no obvious name for them.
+        if taken.contains(&id) {
+            continue;
+        }
+        fixes.push(format!("Added the operationId {} to {} '{}'", id, verb.to_uppercase(), path));
+        operation.insert("operationId".to_string(), Value::String(id.clone()));
+        taken.push(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+    use openapi_validation::OpenAPIValidation;
+    use spec;
+
+    fn document() -> Value {
+        json(r##"{
+            "swagger": "2.0",
+            "info": {"title": "Users", "version": "1"},
+            "host": "api.example.com",
+            "paths": {
+                "/users/{userId}": {
+                    "get": {
+                        "operationId": "users.get",
+                        "summary": "Gets the user with the given id, with every group the user belongs to and the permissions the user has in each of them since it joined",
+                        "schemes": ["https"],
+                        "parameters": [{"name": "userId", "in": "path", "required": true, "type": "string", "description": "Id"}],
+                        "responses": {"200": {"description": "ok"}}
+                    },
+                    "delete": {
+                        "consumes": ["application/json"],
+                        "parameters": [{"$ref": "#/parameters/userId"}],
+                        "responses": {"204": {"description": "deleted"}}
+                    }
+                },
+                "/users/{userId}/groups": {
+                    "get": {"parameters": [{"$ref": "#/parameters/userId"}], "responses": {"200": {"description": "ok"}}}
+                }
+            },
+            "parameters": {"userId": {"name": "userId", "in": "path", "required": true, "type": "string", "description": "Id"}}
+        }"##)
+    }
+
+    // The document after fixing what the validation with these options reports, with the fixes.
+    fn fixed(mut document: Value, options: &ValidationOptions, known: &[Finding]) -> (Value, Vec<String>) {
+        let mut results = spec::from_value(document.clone()).unwrap().validate(options);
+        results.suppress(|finding| known.iter().any(|known| known.same_as(finding)));
+        let fixes = fix(&mut document, &results.findings(), options);
+        (document, fixes)
+    }
+
+    fn keys(value: &Value) -> Vec<&str> {
+        value.as_object().map_or(vec![], |map| map.keys().map(|key| key.as_str()).collect())
+    }
+
+    #[test]
+    fn fixes_what_validation_reports() {
+        let (document, fixes) = fixed(document(), &ValidationOptions::default(), &[]);
+        assert_eq!(fixes, vec![
+            "Removed host",
+            "Removed schemes from GET '/users/{userId}'",
+            "Shortened the summary of GET '/users/{userId}'",
+            "Removed consumes from DELETE '/users/{userId}'",
+            "Renamed the path parameter userId to user_id",
+            "Renamed the path parameter userId to user_id",
+        ]);
+        assert!(document.get("host").is_none());
+        assert_eq!(keys(&document["paths"]), vec!["/users/{user_id}", "/users/{user_id}/groups"]);
+        let get = &document["paths"]["/users/{user_id}"]["get"];
+        assert!(get.get("schemes").is_none());
+        assert_eq!(get["summary"], "Gets the user with the given id, with every group the user belongs to and the permissions the user has in each of...");
+        assert!(get["description"].as_str().unwrap().ends_with("since it joined"));
+        assert_eq!(get["parameters"][0]["name"], "user_id");
+        assert_eq!(document["parameters"]["userId"]["name"], "user_id");
+
+        // Fixing again has nothing left to do.
+        assert!(fixed(document, &ValidationOptions::default(), &[]).1.is_empty());
+    }
+
+    #[test]
+    fn leaves_ignored_findings() {
+        let mut document = document();
+        document["x-oatool-ignore"] = json(r#"["host-absent"]"#);
+        document["paths"]["/users/{userId}"]["x-oatool-ignore"] = json(r#"["naming-parameter", "operation-schemes-absent"]"#);
+        let (document, fixes) = fixed(document, &ValidationOptions::default(), &[]);
+        assert_eq!(fixes, vec![
+            "Shortened the summary of GET '/users/{userId}'",
+            "Removed consumes from DELETE '/users/{userId}'",
+        ]);
+        assert_eq!(document["host"], "api.example.com");
+        // The declaration of userId is shared with the ignored path, both keep the name.
+        assert_eq!(keys(&document["paths"]), vec!["/users/{userId}", "/users/{userId}/groups"]);
+        assert_eq!(document["paths"]["/users/{userId}"]["get"]["parameters"][0]["name"], "userId");
+        assert_eq!(document["parameters"]["userId"]["name"], "userId");
+    }
+
+    #[test]
+    fn leaves_findings_of_the_baseline_and_the_profile() {
+        let known = Finding { rule: "summary-length".to_string(), location: "/paths/~1users~1{userId}/get/summary".to_string(), message: String::new() };
+        let mut options = ValidationOptions::default();
+        options.naming.parameters = Case::Any;
+        let (document, fixes) = fixed(document(), &options, &[known]);
+        assert_eq!(fixes, vec![
+            "Removed host",
+            "Removed schemes from GET '/users/{userId}'",
+            "Removed consumes from DELETE '/users/{userId}'",
+        ]);
+        assert!(document["paths"]["/users/{userId}"]["get"]["summary"].as_str().unwrap().ends_with("since it joined"));
+    }
+
+    #[test]
+    fn measures_summaries_in_bytes() {
+        let mut operation: Map<String, Value> = json(&format!(r#"{{"summary": "{}"}}"#, "é".repeat(70)));
+        assert!(shorten_summary(&mut operation));
+        assert_eq!(operation["summary"], format!("{}...", "é".repeat(58)));
+        assert!(operation["summary"].as_str().unwrap().len() <= SUMMARY_LENGTH);
+        assert!(!shorten_summary(&mut operation));
+    }
+
+    #[test]
+    fn adds_operation_ids_for_google() {
+        let (document, fixes) = fixed(document(), &ValidationOptions::default(), &[]);
+        assert!(document["paths"]["/users/{user_id}"]["delete"].get("operationId").is_none());
+        assert!(!fixes.iter().any(|fix| fix.starts_with("Added")));
+
+        let options = ValidationOptions { support_google_spec: true, ..ValidationOptions::default() };
+        let (document, fixes) = fixed(document, &options, &[]);
+        assert_eq!(fixes, vec![
+            "Added the operationId users.delete to DELETE '/users/{user_id}'",
+            "Added the operationId groups.list to GET '/users/{user_id}/groups'",
+        ]);
+        assert_eq!(document["paths"]["/users/{user_id}"]["get"]["operationId"], "users.get");
+    }
+}
@@ -0,0 +1,692 @@
+// This module rewrites OpenAPI files in a canonical form.
+// Keys follow the order of the fields in the OpenAPI specification, maps of
+// names (paths, definitions, properties, ...) are sorted, strings are quoted
+// only when needed and comments are kept next to the key they were above.
+// Unknown keys, like vendor extensions, go after the known ones, sorted.
+
+use json_path::{self, Key};
+use serde_json::Value;
+use serde_yaml;
+use std::collections::{BTreeMap, BTreeSet};
+
+use errors::*;
+
+const INDENT: usize = 2;
+
+// Formats the text of a YAML file, or of a JSON file if json is true.
+pub fn format(source: &str, json: bool) -> Result<String> {
+    // YAML is a superset of JSON so this reads both.
+    let document: Value = serde_yaml::from_str(source).chain_err(|| "Unable to deserialize the file.")?;
+    Ok(format_value(&document, source, json))
+}
+
+// Formats a document which may have changed since it was read from source.
+// Comments of source are kept where the same keys still exist.
+pub fn format_value(document: &Value, source: &str, json: bool) -> String {
+    let node = order(document, Kind::Spec);
+
+    if json {
+        let mut out = String::new();
+        write_json(&mut out, &node, 0);
+        out.push('\n');
+        out
+    } else {
+        let mut emitter = YamlEmitter {
+            out: String::new(),
+            comments: collect_comments(source),
+            emitted: BTreeSet::new(),
+        };
+        emitter.write_document(&node);
+        emitter.out
+    }
+}
+
+
+// Which OpenAPI object a value is, to know the order of its keys.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Spec,
+    Info,
+    Contact,
+    License,
+    Paths,
+    PathItem,
+    Operation,
+    Parameters,
+    Parameter,
+    ParameterMap,
+    Responses,
+    Response,
+    ResponseMap,
+    Headers,
+    Header,
+    Schema,
+    Schemas,
+    SchemaMap,
+    Tags,
+    Tag,
+    ExternalDocs,
+    SecurityDefinitions,
+    SecurityScheme,
+    // Any other map, sorted.
+    Other,
+}
+
+impl Kind {
+    fn key_order(self) -> &'static [&'static str] {
+        match self {
+            Kind::Spec => &["swagger", "info", "host", "basePath", "schemes", "consumes", "produces", "paths",
+                            "definitions", "parameters", "responses", "securityDefinitions", "security", "tags",
+                            "externalDocs"],
+            Kind::Info => &["title", "description", "termsOfService", "contact", "license", "version"],
+            Kind::Contact => &["name", "url", "email"],
+            Kind::License => &["name", "url"],
+            Kind::PathItem => &["$ref", "get", "put", "post", "delete", "options", "head", "patch", "parameters"],
+            Kind::Operation => &["tags", "summary", "description", "externalDocs", "operationId", "consumes",
+                                 "produces", "parameters", "responses", "schemes", "deprecated", "security"],
+            Kind::Parameter => &["$ref", "name", "in", "description", "required", "schema", "type", "format",
+                                 "allowEmptyValue", "items", "collectionFormat", "default", "maximum",
+                                 "exclusiveMaximum", "minimum", "exclusiveMinimum", "maxLength", "minLength",
+                                 "pattern", "maxItems", "minItems", "uniqueItems", "enum", "multipleOf"],
+            Kind::Response => &["$ref", "description", "schema", "headers", "examples"],
+            Kind::Header => &["description", "type", "format", "items", "collectionFormat", "default", "maximum",
+                              "exclusiveMaximum", "minimum", "exclusiveMinimum", "maxLength", "minLength",
+                              "pattern", "maxItems", "minItems", "uniqueItems", "enum", "multipleOf"],
+            // The specification lists format before type, type goes first as everybody writes it.
+            Kind::Schema => &["$ref", "type", "format", "title", "description", "default", "multipleOf", "maximum",
+                              "exclusiveMaximum", "minimum", "exclusiveMinimum", "maxLength", "minLength",
+                              "pattern", "maxItems", "minItems", "uniqueItems", "maxProperties", "minProperties",
+                              "required", "enum", "items", "allOf", "properties", "additionalProperties",
+                              "discriminator", "readOnly", "xml", "externalDocs", "example"],
+            Kind::Tag => &["name", "description", "externalDocs"],
+            Kind::ExternalDocs => &["description", "url"],
+            Kind::SecurityScheme => &["type", "description", "name", "in", "flow", "authorizationUrl", "tokenUrl",
+                                      "scopes"],
+            _ => &[],
+        }
+    }
+
+    fn child(self, key: &str) -> Kind {
+        match (self, key) {
+            (Kind::Spec, "info") => Kind::Info,
+            (Kind::Spec, "paths") => Kind::Paths,
+            (Kind::Spec, "definitions") => Kind::SchemaMap,
+            (Kind::Spec, "parameters") => Kind::ParameterMap,
+            (Kind::Spec, "responses") => Kind::ResponseMap,
+            (Kind::Spec, "securityDefinitions") => Kind::SecurityDefinitions,
+            (Kind::Spec, "tags") => Kind::Tags,
+            (Kind::Info, "contact") => Kind::Contact,
+            (Kind::Info, "license") => Kind::License,
+            (Kind::Paths, _) => Kind::PathItem,
+            (Kind::PathItem, "parameters") => Kind::Parameters,
+            (Kind::PathItem, _) => Kind::Operation,
+            (Kind::Operation, "parameters") => Kind::Parameters,
+            (Kind::Operation, "responses") => Kind::Responses,
+            (Kind::Parameters, _) => Kind::Parameter,
+            (Kind::ParameterMap, _) => Kind::Parameter,
+            (Kind::Parameter, "schema") => Kind::Schema,
+            (Kind::Parameter, "items") => Kind::Header,
+            (Kind::Responses, _) => Kind::Response,
+            (Kind::ResponseMap, _) => Kind::Response,
+            (Kind::Response, "schema") => Kind::Schema,
+            (Kind::Response, "headers") => Kind::Headers,
+            (Kind::Headers, _) => Kind::Header,
+            (Kind::Header, "items") => Kind::Header,
+            (Kind::Schema, "items") => Kind::Schema,
+            (Kind::Schema, "additionalProperties") => Kind::Schema,
+            (Kind::Schema, "allOf") => Kind::Schemas,
+            (Kind::Schema, "properties") => Kind::SchemaMap,
+            (Kind::Schemas, _) => Kind::Schema,
+            (Kind::SchemaMap, _) => Kind::Schema,
+            (Kind::Tags, _) => Kind::Tag,
+            (Kind::SecurityDefinitions, _) => Kind::SecurityScheme,
+            (_, "externalDocs") => Kind::ExternalDocs,
+            _ => Kind::Other,
+        }
+    }
+
+    // Position of a key, known fields first in their order, then everything else sorted.
+    fn sort_key(self, key: &str) -> (usize, bool, String) {
+        let known = self.key_order();
+        match known.iter().position(|k| *k == key) {
+            Some(position) => (position, false, String::new()),
+            None => (known.len(), key.starts_with("x-"), self.name_order(key)),
+        }
+    }
+
+    // Maps of names are sorted, responses have default at the end.
+    fn name_order(self, key: &str) -> String {
+        if self == Kind::Responses && key == "default" {
+            "~".to_string()
+        } else {
+            key.to_string()
+        }
+    }
+}
+
+
+// Value with the keys of the maps in their final order.
+enum Node {
+    Scalar(Value),
+    Map(Vec<(String, Node)>),
+    Seq(Vec<Node>),
+}
+
+fn order(value: &Value, kind: Kind) -> Node {
+    match *value {
+        Value::Object(ref map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by_key(|&(key, _)| kind.sort_key(key));
+            Node::Map(entries.into_iter().map(|(key, value)| (key.to_string(), order(value, kind.child(key)))).collect())
+        }
+        Value::Array(ref array) => Node::Seq(array.iter().map(|value| order(value, kind.child(""))).collect()),
+        ref scalar => Node::Scalar(scalar.clone()),
+    }
+}
+
+
+// Comments found in the source, by the JSON pointer of the node they belong to.
+#[derive(Default)]
+struct Comments {
+    header: Vec<String>,
+    leading: BTreeMap<String, Vec<String>>,
+    trailing: BTreeMap<String, String>,
+    end: Vec<String>,
+}
+
+struct YamlEmitter {
+    out: String,
+    comments: Comments,
+    emitted: BTreeSet<String>,
+}
+
+impl YamlEmitter {
+    fn write_document(&mut self, node: &Node) {
+        for line in &self.comments.header {
+            self.out.push_str(line);
+            self.out.push('\n');
+        }
+        match *node {
+            Node::Map(ref entries) if !entries.is_empty() => self.write_map(entries, 0, &[], false),
+            Node::Seq(ref items) if !items.is_empty() => self.write_seq(items, 0, &[]),
+            _ => {
+                let line = format!("{}\n", self.scalar_or_empty(node, 0));
+                self.out.push_str(&line);
+            }
+        }
+
+        // Comments of nodes which are not in the output anymore and the ones at the end of the file.
+        let mut rest: Vec<String> = vec![];
+        for (pointer, lines) in &self.comments.leading {
+            if !self.emitted.contains(pointer) {
+                rest.extend(lines.iter().cloned());
+            }
+        }
+        rest.extend(self.comments.end.iter().cloned());
+        for line in rest {
+            self.out.push_str(&line);
+            self.out.push('\n');
+        }
+    }
+
+    // The first key of a map inside a sequence goes in the same line as the dash.
+    fn write_map(&mut self, entries: &[(String, Node)], indent: usize, location: &[Key], first_inline: bool) {
+        for (position, &(ref key, ref node)) in entries.iter().enumerate() {
+            let child_location = with_key(location, Key::Field(key.to_string()));
+            if !(first_inline && position == 0) {
+                self.write_leading(&child_location, indent);
+                self.out.push_str(&" ".repeat(indent));
+            }
+            self.out.push_str(&scalar_to_yaml(&Value::String(key.to_string()), indent, true));
+            self.out.push(':');
+            self.write_value(node, indent, &child_location);
+        }
+    }
+
+    fn write_seq(&mut self, items: &[Node], indent: usize, location: &[Key]) {
+        for (index, node) in items.iter().enumerate() {
+            let child_location = with_key(location, Key::Index(index));
+            self.write_leading(&child_location, indent);
+            if let Node::Map(ref entries) = *node {
+                if let Some(&(ref key, _)) = entries.first() {
+                    self.write_leading(&with_key(&child_location, Key::Field(key.to_string())), indent);
+                }
+            }
+            self.out.push_str(&" ".repeat(indent));
+            self.out.push('-');
+            match *node {
+                Node::Map(ref entries) if !entries.is_empty() => {
+                    self.out.push(' ');
+                    self.write_map(entries, indent + INDENT, &child_location, true);
+                }
+                _ => self.write_value(node, indent, &child_location),
+            }
+        }
+    }
+
+    // Writes what goes after "key:" or "-", including the end of the line.
+    fn write_value(&mut self, node: &Node, indent: usize, location: &[Key]) {
+        match *node {
+            Node::Map(ref entries) if !entries.is_empty() => {
+                self.write_trailing(location);
+                self.write_map(entries, indent + INDENT, location, false);
+            }
+            Node::Seq(ref items) if !items.is_empty() => {
+                self.write_trailing(location);
+                self.write_seq(items, indent + INDENT, location);
+            }
+            _ => {
+                // The comment of a literal block goes after its header, not after its content.
+                let text = format!(" {}", self.scalar_or_empty(node, indent + INDENT));
+                let mut lines = text.splitn(2, '\n');
+                self.out.push_str(lines.next().unwrap_or(""));
+                self.write_trailing(location);
+                if let Some(block) = lines.next() {
+                    self.out.push_str(block);
+                    self.out.push('\n');
+                }
+            }
+        }
+    }
+
+    fn scalar_or_empty(&self, node: &Node, indent: usize) -> String {
+        match *node {
+            Node::Scalar(ref value) => scalar_to_yaml(value, indent, false),
+            Node::Map(_) => "{}".to_string(),
+            Node::Seq(_) => "[]".to_string(),
+        }
+    }
+
+    fn write_leading(&mut self, location: &[Key], indent: usize) {
+        let pointer = json_path::to_pointer(location);
+        if let Some(lines) = self.comments.leading.get(&pointer) {
+            for line in lines {
+                self.out.push_str(&" ".repeat(indent));
+                self.out.push_str(line);
+                self.out.push('\n');
+            }
+        }
+        self.emitted.insert(pointer);
+    }
+
+    fn write_trailing(&mut self, location: &[Key]) {
+        if let Some(comment) = self.comments.trailing.get(&json_path::to_pointer(location)) {
+            self.out.push(' ');
+            self.out.push_str(comment);
+        }
+        self.out.push('\n');
+    }
+}
+
+fn with_key(location: &[Key], key: Key) -> Vec<Key> {
+    let mut location = location.to_vec();
+    location.push(key);
+    location
+}
+
+// Strings are plain when YAML reads them back as the same string, double quoted otherwise.
+// Multiline strings are literal blocks, indented at the given indentation.
+fn scalar_to_yaml(value: &Value, indent: usize, is_key: bool) -> String {
+    match *value {
+        Value::String(ref string) => {
+            if is_plain(string) {
+                string.to_string()
+            } else if !is_key && can_be_literal_block(string) {
+                let chomping = if string.ends_with('\n') { "" } else { "-" };
+                let lines: Vec<String> = string
+                    .trim_end_matches('\n')
+                    .split('\n')
+                    .map(|line| if line.is_empty() { String::new() } else { format!("{}{}", " ".repeat(indent), line) })
+                    .collect();
+                format!("|{}\n{}", chomping, lines.join("\n"))
+            } else {
+                double_quoted(string)
+            }
+        }
+        Value::Null => "null".to_string(),
+        ref other => other.to_string(),
+    }
+}
+
+fn is_plain(string: &str) -> bool {
+    let first = match string.chars().next() {
+        Some(c) => c,
+        None => return false,
+    };
+    let lowercase = string.to_lowercase();
+    let reserved = ["true", "false", "yes", "no", "on", "off", "y", "n", "null", "~", ".inf", "-.inf", "+.inf", ".nan"];
+
+    string.trim() == string
+        && !"-?:,[]{}#&*!|>'\"%@`".contains(first)
+        && !string.contains(": ")
+        && !string.contains(" #")
+        && !string.ends_with(':')
+        && !string.chars().any(|c| c.is_control())
+        && !reserved.contains(&lowercase.as_str())
+        && string.parse::<f64>().is_err()
+        && !looks_like_number(string)
+}
+
+// Integers in other bases, YAML reads them as numbers.
+fn looks_like_number(string: &str) -> bool {
+    let digits = string.trim_start_matches(|c| c == '+' || c == '-');
+    (digits.starts_with("0x") || digits.starts_with("0o")) && digits.len() > 2
+}
+
+// A literal block can not keep leading spaces in the first line or trailing spaces.
+fn can_be_literal_block(string: &str) -> bool {
+    string.contains('\n')
+        && !string.starts_with(' ')
+        && !string.starts_with('\n')
+        && !string.ends_with("\n\n")
+        && !string.lines().any(|line| line.ends_with(' ') || line.ends_with('\t'))
+        && !string.chars().any(|c| c.is_control() && c != '\n')
+}
+
+fn double_quoted(string: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn write_json(out: &mut String, node: &Node, indent: usize) {
+    match *node {
+        Node::Scalar(ref value) => out.push_str(&value.to_string()),
+        Node::Map(ref entries) if entries.is_empty() => out.push_str("{}"),
+        Node::Seq(ref items) if items.is_empty() => out.push_str("[]"),
+        Node::Map(ref entries) => {
+            out.push_str("{\n");
+            for (position, &(ref key, ref child)) in entries.iter().enumerate() {
+                out.push_str(&" ".repeat(indent + INDENT));
+                out.push_str(&Value::String(key.to_string()).to_string());
+                out.push_str(": ");
+                write_json(out, child, indent + INDENT);
+                out.push_str(if position + 1 < entries.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&" ".repeat(indent));
+            out.push('}');
+        }
+        Node::Seq(ref items) => {
+            out.push_str("[\n");
+            for (position, child) in items.iter().enumerate() {
+                out.push_str(&" ".repeat(indent + INDENT));
+                write_json(out, child, indent + INDENT);
+                out.push_str(if position + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&" ".repeat(indent));
+            out.push(']');
+        }
+    }
+}
+
+
+// Finds the comments of a block style YAML file and the node each one belongs to.
+// A comment line belongs to the key or sequence item below it and a comment at
+// the end of a line belongs to the key or item of that line.
+fn collect_comments(source: &str) -> Comments {
+    let mut comments = Comments::default();
+    // Keys and items the current line is nested in, with their indentation.
+    let mut stack: Vec<(usize, Key)> = vec![];
+    let mut pending: Vec<String> = vec![];
+    // Lines indented more than this are the content of a literal or folded block.
+    let mut block_scalar: Option<usize> = None;
+    // Open brackets of a flow collection which continues in the next lines.
+    let mut open_brackets = 0;
+    let mut seen_content = false;
+
+    for line in source.lines() {
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+
+        if let Some(parent_indent) = block_scalar {
+            if trimmed.is_empty() || indent > parent_indent {
+                continue;
+            }
+            block_scalar = None;
+        }
+        if open_brackets > 0 {
+            open_brackets += bracket_balance(trimmed);
+            continue;
+        }
+        if trimmed.is_empty() || trimmed == "---" || trimmed == "..." {
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            pending.push(trimmed.to_string());
+            continue;
+        }
+        // Comments before the first key are for the whole file.
+        if !seen_content {
+            seen_content = true;
+            comments.header.append(&mut pending);
+        }
+
+        let mut content = trimmed;
+        let mut content_indent = indent;
+        let mut is_item = false;
+        while content == "-" || content.starts_with("- ") {
+            let mut next_index = 0;
+            loop {
+                let (top_indent, top_index) = match stack.last() {
+                    Some(&(top_indent, Key::Index(index))) => (top_indent, Some(index)),
+                    Some(&(top_indent, Key::Field(_))) => (top_indent, None),
+                    None => break,
+                };
+                if top_indent > content_indent || (top_indent == content_indent && top_index.is_some()) {
+                    if top_indent == content_indent {
+                        next_index = top_index.map_or(0, |index| index + 1);
+                    }
+                    stack.pop();
+                } else {
+                    break;
+                }
+            }
+            stack.push((content_indent, Key::Index(next_index)));
+            attach_leading(&mut comments, &stack, &mut pending);
+
+            let rest = content[1..].trim_start();
+            content_indent += content.len() - rest.len();
+            content = rest;
+            is_item = true;
+        }
+        if content.is_empty() {
+            continue;
+        }
+
+        let value = match split_key(content) {
+            Some((key, value)) => {
+                while stack.last().map_or(false, |&(top_indent, _)| top_indent >= content_indent) {
+                    stack.pop();
+                }
+                stack.push((content_indent, Key::Field(key)));
+                attach_leading(&mut comments, &stack, &mut pending);
+                value
+            }
+            None if is_item => content,
+            // Continuation of a multiline scalar.
+            None => continue,
+        };
+
+        let (value, comment) = split_comment(value);
+        if let Some(comment) = comment {
+            let location: Vec<Key> = stack.iter().map(|&(_, ref key)| key.clone()).collect();
+            comments.trailing.insert(json_path::to_pointer(&location), comment.to_string());
+        }
+        if value.starts_with('|') || value.starts_with('>') {
+            block_scalar = Some(stack.last().map_or(0, |&(top_indent, _)| top_indent));
+        } else if value.starts_with('[') || value.starts_with('{') {
+            open_brackets = bracket_balance(value);
+        }
+    }
+
+    comments.end = pending;
+    comments
+}
+
+fn attach_leading(comments: &mut Comments, stack: &[(usize, Key)], pending: &mut Vec<String>) {
+    if pending.is_empty() {
+        return;
+    }
+    let location: Vec<Key> = stack.iter().map(|&(_, ref key)| key.clone()).collect();
+    comments.leading.entry(json_path::to_pointer(&location)).or_insert_with(Vec::new).append(pending);
+}
+
+// "key: value" -> (key, value). Keys can be quoted.
+fn split_key(content: &str) -> Option<(String, &str)> {
+    let (key, rest) = match content.chars().next() {
+        Some('[') | Some('{') | Some('#') | Some('|') | Some('>') => return None,
+        Some(quote) if quote == '"' || quote == '\'' => {
+            let end = closing_quote(content)?;
+            (unquote(&content[..end + 1]), &content[end + 1..])
+        }
+        _ => {
+            let position = content.find(": ").or_else(|| if content.ends_with(':') { Some(content.len() - 1) } else { None })?;
+            (content[..position].trim_end().to_string(), &content[position..])
+        }
+    };
+
+    let rest = rest.trim_start();
+    if rest == ":" || rest.starts_with(": ") {
+        Some((key, rest[1..].trim_start()))
+    } else {
+        None
+    }
+}
+
+// Position of the quote closing the string which starts at the beginning of text.
+fn closing_quote(text: &str) -> Option<usize> {
+    let quote = text.chars().next()?;
+    let mut escaped = false;
+    let mut chars = text.char_indices().skip(1).peekable();
+    while let Some((position, c)) = chars.next() {
+        if quote == '"' && c == '\\' && !escaped {
+            escaped = true;
+            continue;
+        }
+        if c == quote && !escaped {
+            // '' is an escaped quote inside single quotes
+            if quote == '\'' && chars.peek().map(|&(_, next)| next) == Some('\'') {
+                chars.next();
+                continue;
+            }
+            return Some(position);
+        }
+        escaped = false;
+    }
+    None
+}
+
+fn unquote(text: &str) -> String {
+    serde_yaml::from_str::<String>(text).unwrap_or_else(|_| text[1..text.len() - 1].to_string())
+}
+
+// "value # comment" -> ("value", Some("# comment"))
+fn split_comment(value: &str) -> (&str, Option<&str>) {
+    let start = match value.chars().next() {
+        Some(quote) if quote == '"' || quote == '\'' => closing_quote(value).map_or(value.len(), |end| end + 1),
+        _ => 0,
+    };
+    if value.starts_with('#') && start == 0 {
+        return ("", Some(value));
+    }
+    match value[start..].find(" #") {
+        Some(position) => (value[..start + position].trim_end(), Some(value[start + position + 1..].trim())),
+        None => (value, None),
+    }
+}
+
+fn bracket_balance(text: &str) -> i32 {
+    text.chars().fold(0, |balance, c| match c {
+        '[' | '{' => balance + 1,
+        ']' | '}' => balance - 1,
+        _ => balance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_keys_like_the_specification() {
+        let source = r#"{"paths": {"/b": {}, "/a": {}}, "x-logo": "l", "info": {"version": "1", "title": "t"}, "swagger": "2.0"}"#;
+        assert_eq!(format(source, false).unwrap(), "swagger: \"2.0\"\ninfo:\n  title: t\n  version: \"1\"\npaths:\n  /a: {}\n  /b: {}\nx-logo: l\n");
+    }
+
+    #[test]
+    fn writes_json() {
+        let source = "info:\n  version: '1'\n  title: t\ntags: []\nswagger: '2.0'\n";
+        assert_eq!(format(source, true).unwrap(), "{\n  \"swagger\": \"2.0\",\n  \"info\": {\n    \"title\": \"t\",\n    \"version\": \"1\"\n  },\n  \"tags\": []\n}\n");
+    }
+
+    #[test]
+    fn quotes_strings_only_when_needed() {
+        let quoted = |string: &str| scalar_to_yaml(&Value::String(string.to_string()), 0, false);
+        assert_eq!(quoted("plain text"), "plain text");
+        assert_eq!(quoted("/users/{id}"), "/users/{id}");
+        for string in &["", "yes", "Null", "1.5", "0x1F", " padded", "a: b", "a #b", "-dash", "*star", "tab\there"] {
+            assert!(quoted(string).starts_with('"'), "{} should be quoted", string);
+        }
+        assert_eq!(quoted("say \"hi\""), "say \"hi\"");
+        assert_eq!(quoted("\"hi\" \\"), r#""\"hi\" \\""#);
+        assert_eq!(scalar_to_yaml(&Value::String("one\n\ntwo".to_string()), 2, false), "|-\n  one\n\n  two");
+        assert_eq!(scalar_to_yaml(&Value::String("one\n".to_string()), 2, false), "|\n  one");
+        assert_eq!(quoted(" one\ntwo"), r#"" one\ntwo""#);
+    }
+
+    #[test]
+    fn collects_comments_by_node() {
+        let source = "# Header\n\nswagger: '2.0' # version\n# Above info\ninfo:\n  # Above title\n  title: t\ntags:\n  # First tag\n  - name: a # the a\n  -\n    # Named b\n    name: b\n# End\n";
+        let comments = collect_comments(source);
+        assert_eq!(comments.header, vec!["# Header"]);
+        assert_eq!(comments.trailing.get("/swagger").map(|c| c.as_str()), Some("# version"));
+        assert_eq!(comments.leading["/info"], vec!["# Above info"]);
+        assert_eq!(comments.leading["/info/title"], vec!["# Above title"]);
+        assert_eq!(comments.leading["/tags/0"], vec!["# First tag"]);
+        assert_eq!(comments.trailing.get("/tags/0/name").map(|c| c.as_str()), Some("# the a"));
+        assert_eq!(comments.leading["/tags/1/name"], vec!["# Named b"]);
+        assert_eq!(comments.end, vec!["# End"]);
+    }
+
+    #[test]
+    fn skips_block_scalars_flow_collections_and_quoted_hashes() {
+        let source = "info:\n  description: |\n    # not a comment\n    text\n  title: \"a # b\" # title\nschemes: [\n  http, # not read\n  https]\n# Above paths\npaths: {}\n";
+        let comments = collect_comments(source);
+        assert_eq!(comments.trailing.get("/info/title").map(|c| c.as_str()), Some("# title"));
+        assert_eq!(comments.leading.keys().collect::<Vec<_>>(), vec!["/paths"]);
+        assert_eq!(comments.trailing.len(), 1);
+    }
+
+    #[test]
+    fn keeps_comments_where_the_keys_moved() {
+        let source = "# Pets\ninfo:\n  version: '1' # bump\n  # The name\n  title: Pets\nswagger: '2.0'\n# Removed\nx-old: true\n";
+        let mut document: Value = serde_yaml::from_str(source).unwrap();
+        document.as_object_mut().unwrap().remove("x-old");
+        assert_eq!(
+            format_value(&document, source, false),
+            "# Pets\nswagger: \"2.0\"\ninfo:\n  # The name\n  title: Pets\n  version: \"1\" # bump\n# Removed\n"
+        );
+    }
+
+    #[test]
+    fn splits_keys_and_comments() {
+        assert_eq!(split_key("'it''s': value"), Some(("it's".to_string(), "value")));
+        assert_eq!(split_key("\"a: b\":"), Some(("a: b".to_string(), "")));
+        assert_eq!(split_key("url: http://host"), Some(("url".to_string(), "http://host")));
+        assert_eq!(split_key("http://host"), None);
+        assert_eq!(split_comment("'#1' # one"), ("'#1'", Some("# one")));
+        assert_eq!(split_comment("a#b"), ("a#b", None));
+        assert_eq!(split_comment("# only"), ("", Some("# only")));
+    }
+}
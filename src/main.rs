@@ -1,16 +1,49 @@
 extern crate clap;
 extern crate openapi;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate serde_yaml;
 extern crate convert_google_spec;
 extern crate google_discovery_spec;
 extern crate openapi_validation;
+extern crate openapi_extensions;
+extern crate json_path;
+extern crate inflector;
+extern crate glob;
+extern crate walkdir;
+extern crate rayon;
+extern crate openapi_examples;
+extern crate openapi_conformance;
+extern crate tiny_http;
+extern crate ureq;
 
 #[macro_use]
 extern crate error_chain;
 
 use clap::{Arg, App, AppSettings, SubCommand};
-use std::io::Write;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use serde_json::Value;
 
 mod spec;
+mod overrides;
+mod overlay;
+mod merge;
+mod filter;
+mod fmt;
+mod fix;
+mod batch;
+mod codegen;
+mod docs;
+mod markdown;
+mod mock;
+mod testgen;
+
+#[cfg(test)]
+mod testing;
 
 pub mod errors {
     error_chain!{
@@ -23,7 +56,10 @@ pub mod errors {
 }
 use errors::*;
 
-use openapi_validation::{OpenAPIValidation, ValidationOptions};
+use openapi_validation::{Finding, OpenAPIValidation, Ruleset, ValidationOptions, ValidationResults};
+use convert_google_spec::openapi_to_google::ConversionOptions;
+use openapi_extensions::Document;
+use overrides::Overrides;
 
 
 fn exit_with_error(error: &Error, extra_error_message: &str) {
@@ -36,14 +72,39 @@ fn exit_with_error(error: &Error, extra_error_message: &str) {
     std::process::exit(-1);
 }
 
-fn exit_on_validation_error(openapi_spec: &openapi::Spec, options: &ValidationOptions) {
-    let results = openapi_spec.validate(options);
+fn exit_on_validation_error(document: &Document, options: &ValidationOptions) {
+    exit_on_failure(&document.validate(options));
+}
+
+fn exit_on_failure(results: &ValidationResults) {
     if results.failed() {
         writeln!(&mut std::io::stderr(), "Validation results: {}", results).unwrap();
         std::process::exit(-1);
     }
 }
 
+fn conflict_arg<'a>(name: &'a str, help: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name(name)
+        .long(name)
+        .takes_value(true)
+        .require_equals(true)
+        .required(false)
+        .default_value("error")
+        .possible_values(&["error", "rename", "prefer_first"])
+        .help(help)
+}
+
+fn filter_arg<'a>(name: &'a str, help: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name(name)
+        .long(name)
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .require_equals(true)
+        .required(false)
+        .help(help)
+}
+
 
 fn main() {
     let file_arg = Arg::with_name("file")
@@ -57,13 +118,55 @@ fn main() {
         .about("A tool to manage OpenAPI files")
         .setting(AppSettings::AllowExternalSubcommands)
         .subcommand(SubCommand::with_name("validate")
-            .about("Validates an OpenAPI file.")
-            .arg(&file_arg)
+            .about("Validates OpenAPI files.")
+            .arg(Arg::with_name("file")
+                .help("OpenAPI spec files, directories or globs")
+                .required(true)
+                .multiple(true))
             .arg(Arg::with_name("support_google")
                 .long("support_google")
                 .takes_value(false)
                 .required(false)
                 .help("Validates an openapi file which can be converted to google (or not)."))
+            .arg(Arg::with_name("profile")
+                .long("profile")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .help("Validation profile file, turns rules on and off."))
+            .arg(Arg::with_name("rules")
+                .long("rules")
+                .takes_value(true)
+                .require_equals(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(false)
+                .help("File with custom rules. Can be given several times."))
+            .arg(Arg::with_name("fix")
+                .long("fix")
+                .takes_value(false)
+                .required(false)
+                .help("Fixes the findings which have a mechanical remedy and writes the file back before validating."))
+            .arg(Arg::with_name("baseline")
+                .long("baseline")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .help("Baseline file, only the findings which are not in it are reported."))
+            .arg(Arg::with_name("write_baseline")
+                .long("write_baseline")
+                .alias("write-baseline")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .conflicts_with("baseline")
+                .help("Writes the findings to a baseline file instead of reporting them."))
+            .arg(Arg::with_name("fail_on_skipped")
+                .long("fail_on_skipped")
+                .alias("fail-on-skipped")
+                .takes_value(false)
+                .required(false)
+                .help("Fails on files oatool has no rules for, like OpenAPI 3.x and Google Discovery files."))
         )
         .subcommand(SubCommand::with_name("convert")
             .about("Translates an API spec file to other format.")
@@ -80,28 +183,454 @@ fn main() {
                 .takes_value(true)
                 .require_equals(true)
                 .required(true)
-                .possible_values(&["openapi_yaml", "openapi_json", "google"])
-                .help("Sets the format to convert the file to.")))
+                .possible_values(&["openapi_yaml", "openapi_json", "google", "markdown"])
+                .help("Sets the format to convert the file to."))
+            .arg(Arg::with_name("group_by_tags")
+                .long("group_by_tags")
+                .takes_value(false)
+                .required(false)
+                .help("Groups Google resources by the operation tags instead of by the operationId prefix."))
+            .arg(Arg::with_name("set")
+                .long("set")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(false)
+                .help("Sets a value in the result, as key.path=value. Can be repeated."))
+            .arg(Arg::with_name("overrides")
+                .long("overrides")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .help("YAML or JSON file merged into the result. null values remove keys.")))
+        .subcommand(SubCommand::with_name("overlay")
+            .about("Applies an OpenAPI Overlay document to an OpenAPI file.")
+            .arg(&file_arg)
+            .arg(Arg::with_name("overlay")
+                .help("Overlay file")
+                .required(true)
+                .index(2))
+            .arg(Arg::with_name("to")
+                .long("to")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .default_value("openapi_yaml")
+                .possible_values(&["openapi_yaml", "openapi_json"])
+                .help("Sets the format of the result.")))
+        .subcommand(SubCommand::with_name("merge")
+            .about("Merges several OpenAPI files into one.")
+            .arg(Arg::with_name("files")
+                .help("OpenAPI spec files, in order of preference")
+                .required(true)
+                .multiple(true)
+                .min_values(2)
+                .index(1))
+            .arg(conflict_arg("paths", "What to do when paths of several files conflict."))
+            .arg(conflict_arg("definitions", "What to do when definitions of several files conflict."))
+            .arg(conflict_arg("parameters", "What to do when parameters of several files conflict."))
+            .arg(conflict_arg("responses", "What to do when responses of several files conflict."))
+            .arg(conflict_arg("tags", "What to do when tags of several files conflict."))
+            .arg(conflict_arg("security_definitions", "What to do when security definitions of several files conflict."))
+            .arg(Arg::with_name("operation_ids")
+                .long("operation_ids")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .default_value("rename")
+                .possible_values(&["error", "rename"])
+                .help("What to do when an operationId is already used by a previous file."))
+            .arg(Arg::with_name("prefix_base_paths")
+                .long("prefix_base_paths")
+                .takes_value(false)
+                .required(false)
+                .help("Adds the basePath of each file in front of its paths."))
+            .arg(Arg::with_name("to")
+                .long("to")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .default_value("openapi_yaml")
+                .possible_values(&["openapi_yaml", "openapi_json"])
+                .help("Sets the format of the result.")))
+        .subcommand(SubCommand::with_name("filter")
+            .about("Extracts the operations selected by tag, path or operationId with everything they use.")
+            .arg(&file_arg)
+            .arg(filter_arg("tag", "Keeps operations with this tag."))
+            .arg(filter_arg("path", "Keeps operations under paths matching this glob. * matches a segment, ** several."))
+            .arg(filter_arg("operation", "Keeps operations with an operationId matching this glob."))
+            .arg(filter_arg("exclude_tag", "Drops operations with this tag.").alias("exclude-tag"))
+            .arg(filter_arg("exclude_path", "Drops operations under paths matching this glob.").alias("exclude-path"))
+            .arg(filter_arg("exclude_operation", "Drops operations with an operationId matching this glob.").alias("exclude-operation"))
+            .arg(Arg::with_name("to")
+                .long("to")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .default_value("openapi_yaml")
+                .possible_values(&["openapi_yaml", "openapi_json"])
+                .help("Sets the format of the result.")))
+        .subcommand(SubCommand::with_name("fmt")
+            .about("Rewrites an OpenAPI file in the canonical format.")
+            .arg(&file_arg)
+            .arg(Arg::with_name("check")
+                .long("check")
+                .takes_value(false)
+                .required(false)
+                .conflicts_with("write")
+                .help("Fails if the file is not already formatted, without changing it."))
+            .arg(Arg::with_name("write")
+                .long("write")
+                .takes_value(false)
+                .required(false)
+                .help("Writes the result back to the file instead of printing it.")))
+        .subcommand(SubCommand::with_name("example")
+            .about("Makes up an example payload from the schemas of an operation or a definition.")
+            .arg(&file_arg)
+            .arg(Arg::with_name("operation")
+                .long("operation")
+                .takes_value(true)
+                .require_equals(true)
+                .required_unless("definition")
+                .conflicts_with("definition")
+                .help("operationId of the operation."))
+            .arg(Arg::with_name("status")
+                .long("status")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .help("Status of the response, the first 2xx one by default."))
+            .arg(Arg::with_name("request")
+                .long("request")
+                .takes_value(false)
+                .required(false)
+                .conflicts_with("status")
+                .help("Makes up the body of the request instead of the response."))
+            .arg(Arg::with_name("definition")
+                .long("definition")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .help("Name of the definition.")))
+        .subcommand(SubCommand::with_name("docs")
+            .about("Renders an HTML reference of an OpenAPI or Google Discovery file.")
+            .arg(&file_arg)
+            .arg(Arg::with_name("out")
+                .long("out")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .help("Directory to write index.html to instead of printing it.")))
+        .subcommand(SubCommand::with_name("mock")
+            .about("Serves examples from the spec over HTTP, checking the requests against it.")
+            .arg(&file_arg)
+            .arg(Arg::with_name("port")
+                .long("port")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .default_value("8080")
+                .help("Port to listen on, in localhost.")))
+        .subcommand(SubCommand::with_name("check-traffic")
+            .about("Checks recorded requests and responses against the spec.")
+            .arg(&file_arg)
+            .arg(Arg::with_name("traffic")
+                .help("HAR file or JSON lines of exchanges")
+                .required(true)
+                .index(2)))
+        .subcommand(SubCommand::with_name("testgen")
+            .about("Runs a contract test per operation against a service and reports them as JUnit XML.")
+            .arg(&file_arg)
+            .arg(Arg::with_name("base_url")
+                .long("base_url")
+                .alias("base-url")
+                .takes_value(true)
+                .require_equals(true)
+                .required(true)
+                .help("URL of the service, the basePath of the spec is added to it."))
+            .arg(Arg::with_name("out")
+                .long("out")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .help("File to write the JUnit XML to instead of printing it.")))
+        .subcommand(SubCommand::with_name("codegen")
+            .about("Generates code from an OpenAPI or Google Discovery file.")
+            .arg(Arg::with_name("target")
+                .help("What to generate: rust for the types of the definitions, rust-client for a client crate, rust-server for an axum server skeleton, typescript for types and a fetch client")
+                .required(true)
+                .possible_values(&["rust", "rust-client", "rust-server", "typescript"])
+                .index(1))
+            .arg(Arg::with_name("file")
+                .help("OpenAPI spec file")
+                .required(true)
+                .index(2))
+            .arg(Arg::with_name("out")
+                .long("out")
+                .takes_value(true)
+                .require_equals(true)
+                .required(false)
+                .help("File to write the code to, or directory for the targets which make several files.")))
         .get_matches();
 
     match application.subcommand() {
         ("validate", Some(arguments)) => {
-            let filename = arguments.value_of("file").unwrap();
-            let openapi_spec = spec::from_path(filename).unwrap();
-            let options = ValidationOptions{ support_google_spec: arguments.is_present("support_google"), ..Default::default() };
-            exit_on_validation_error(&openapi_spec, &options);
-            println!("Your file passed the validation. Congrats!");
+            let filenames: Vec<&str> = arguments.values_of("file").unwrap().collect();
+            let profile = arguments.value_of("profile").map_or_else(|| Ok(ValidationOptions::default()), validation_profile);
+            let mut options = match profile {
+                Ok(options) => options,
+                Err(e) => return exit_with_error(&e, "Reading the validation profile failed"),
+            };
+            options.support_google_spec = options.support_google_spec || arguments.is_present("support_google");
+            for filename in arguments.values_of("rules").into_iter().flat_map(|values| values) {
+                match ruleset(filename) {
+                    Ok(ruleset) => options.rules.extend(ruleset.rules),
+                    Err(e) => exit_with_error(&e, &format!("Reading the rules {} failed", filename)),
+                }
+            }
+            let fail_on_skipped = arguments.is_present("fail_on_skipped");
+
+            // Several files, directories or globs get the aggregated report.
+            if filenames.len() > 1 || !Path::new(filenames[0]).is_file() {
+                let files = match batch::expand(&filenames) {
+                    Ok(files) => files,
+                    Err(e) => return exit_with_error(&e, "Finding the files to validate failed"),
+                };
+                if arguments.is_present("baseline") || arguments.is_present("write_baseline") {
+                    return exit_with_error(&"Baselines work with a single file.".into(), "Validation failed");
+                }
+                if arguments.is_present("fix") {
+                    for filename in files.iter().filter(|filename| batch::detect_file(filename) == batch::Format::OpenAPI2) {
+                        fix_and_print(filename, &options, &[]);
+                    }
+                }
+                let reports = batch::validate_all(&files, &options);
+                print!("{}", batch::report(&reports));
+                if reports.iter().any(|report| report.failed() || (fail_on_skipped && report.skipped())) {
+                    std::process::exit(-1);
+                }
+                std::process::exit(0);
+            }
+
+            let filename = filenames[0];
+            let known = match arguments.value_of("baseline") {
+                Some(baseline_filename) if !arguments.is_present("write_baseline") => match baseline(baseline_filename) {
+                    Ok(known) => known,
+                    Err(e) => return exit_with_error(&e, &format!("Reading the baseline {} failed", baseline_filename)),
+                },
+                _ => vec![],
+            };
+            if arguments.is_present("fix") && batch::detect_file(filename) == batch::Format::OpenAPI2 {
+                fix_and_print(filename, &options, &known);
+            }
+
+            let mut results = match batch::validate_file(filename, &options).outcome {
+                batch::Outcome::Validated(results) => results,
+                batch::Outcome::Skipped(reason) => {
+                    println!("Skipped: {}", reason);
+                    std::process::exit(if fail_on_skipped { -1 } else { 0 });
+                }
+                batch::Outcome::Failed(e) => return exit_with_error(&e, "Validation failed"),
+            };
+            if let Some(baseline_filename) = arguments.value_of("write_baseline") {
+                match write_baseline(baseline_filename, &results) {
+                    Ok(()) => println!("Wrote {} findings to {}", results.findings().len(), baseline_filename),
+                    Err(e) => exit_with_error(&e, &format!("Writing the baseline {} failed", baseline_filename)),
+                }
+            } else {
+                results.suppress(|finding| known.iter().any(|known| known.same_as(finding)));
+                exit_on_failure(&results);
+                println!("Your file passed the validation. Congrats!");
+            }
         }
         ("convert", Some(arguments)) => {
             let filename = arguments.value_of("file").unwrap();
             let from = arguments.value_of("from").unwrap();
             let to = arguments.value_of("to").unwrap();
+            let options = ConversionOptions{ group_by_tags: arguments.is_present("group_by_tags") };
+            let sets: Vec<&str> = arguments.values_of("set").map(|values| values.collect()).unwrap_or_else(Vec::new);
 
-            match convert(filename, from, to) {
+            let result = Overrides::new(arguments.value_of("overrides"), &sets)
+                .and_then(|overrides| convert(filename, from, to, &options, &overrides));
+            match result {
                 Ok(text) => println!("{}", text),
                 Err(e) => exit_with_error(&e, &format!("Convertion from {} to {} failed", &from, &to)),
             }
         }
+        ("overlay", Some(arguments)) => {
+            let filename = arguments.value_of("file").unwrap();
+            let overlay_filename = arguments.value_of("overlay").unwrap();
+            let to = arguments.value_of("to").unwrap();
+
+            match apply_overlay(filename, overlay_filename, to) {
+                Ok(text) => println!("{}", text),
+                Err(e) => exit_with_error(&e, &format!("Applying the overlay {} failed", &overlay_filename)),
+            }
+        }
+        ("merge", Some(arguments)) => {
+            let filenames: Vec<&str> = arguments.values_of("files").unwrap().collect();
+            let conflict = |name| merge::Conflict::from_name(arguments.value_of(name).unwrap());
+            let options = merge::MergeOptions {
+                paths: conflict("paths"),
+                definitions: conflict("definitions"),
+                parameters: conflict("parameters"),
+                responses: conflict("responses"),
+                tags: conflict("tags"),
+                security_definitions: conflict("security_definitions"),
+                operation_ids: conflict("operation_ids"),
+                prefix_base_paths: arguments.is_present("prefix_base_paths"),
+            };
+
+            match merge_files(&filenames, &options, arguments.value_of("to").unwrap()) {
+                Ok(text) => println!("{}", text),
+                Err(e) => exit_with_error(&e, "Merging the files failed"),
+            }
+        }
+        ("filter", Some(arguments)) => {
+            let filename = arguments.value_of("file").unwrap();
+            let values = |name| arguments.values_of(name).map(|values| values.map(|v| v.to_string()).collect()).unwrap_or_else(Vec::new);
+            let options = filter::FilterOptions {
+                tags: values("tag"),
+                paths: values("path"),
+                operations: values("operation"),
+                exclude_tags: values("exclude_tag"),
+                exclude_paths: values("exclude_path"),
+                exclude_operations: values("exclude_operation"),
+            };
+
+            match filter_file(filename, &options, arguments.value_of("to").unwrap()) {
+                Ok(text) => println!("{}", text),
+                Err(e) => exit_with_error(&e, &format!("Filtering {} failed", &filename)),
+            }
+        }
+        ("fmt", Some(arguments)) => {
+            let filename = arguments.value_of("file").unwrap();
+
+            match format_file(filename) {
+                Ok((ref original, ref formatted)) if arguments.is_present("check") => {
+                    if original != formatted {
+                        writeln!(&mut std::io::stderr(), "{} is not formatted. Run oatool fmt {} --write", filename, filename).unwrap();
+                        std::process::exit(-1);
+                    }
+                }
+                Ok((_, formatted)) => {
+                    if arguments.is_present("write") {
+                        if let Err(e) = write_file(filename, &formatted) {
+                            exit_with_error(&e, &format!("Formatting {} failed", filename));
+                        }
+                    } else {
+                        print!("{}", formatted);
+                    }
+                }
+                Err(e) => exit_with_error(&e, &format!("Formatting {} failed", filename)),
+            }
+        }
+        ("example", Some(arguments)) => {
+            let filename = arguments.value_of("file").unwrap();
+            let wanted = match (arguments.value_of("operation"), arguments.value_of("definition")) {
+                (Some(id), _) if arguments.is_present("request") => ExampleOf::Request(id),
+                (Some(id), _) => ExampleOf::Response(id, arguments.value_of("status")),
+                (None, definition) => ExampleOf::Definition(definition.unwrap()),
+            };
+            match document_from_path(filename).and_then(|document| make_example(&document, &wanted)) {
+                Ok(text) => println!("{}", text),
+                Err(e) => exit_with_error(&e, &format!("Making up an example from {} failed", filename)),
+            }
+        }
+        ("docs", Some(arguments)) => {
+            let filename = arguments.value_of("file").unwrap();
+            let result = document_from_path(filename)
+                .map(|document| docs::html(&document.spec, &document.source));
+            match (result, arguments.value_of("out")) {
+                (Ok(html), Some(directory)) => {
+                    let written = std::fs::create_dir_all(directory)
+                        .chain_err(|| format!("Unable to create the directory {}.", directory))
+                        .and_then(|_| write_file(&Path::new(directory).join("index.html").to_string_lossy(), &html));
+                    if let Err(e) = written {
+                        exit_with_error(&e, &format!("Writing the documentation of {} failed", filename));
+                    }
+                }
+                (Ok(html), None) => print!("{}", html),
+                (Err(e), _) => exit_with_error(&e, &format!("Rendering the documentation of {} failed", filename)),
+            }
+        }
+        ("mock", Some(arguments)) => {
+            let filename = arguments.value_of("file").unwrap();
+            let result = arguments
+                .value_of("port")
+                .unwrap()
+                .parse::<u16>()
+                .chain_err(|| "The port must be a number up to 65535.")
+                .and_then(|port| document_from_path(filename).and_then(|document| mock::serve(&document.source, port)));
+            if let Err(e) = result {
+                exit_with_error(&e, &format!("Serving a mock of {} failed", filename));
+            }
+        }
+        ("check-traffic", Some(arguments)) => {
+            let filename = arguments.value_of("file").unwrap();
+            let traffic = arguments.value_of("traffic").unwrap();
+            let checked = document_from_path(filename).and_then(|document| check_traffic(&document, traffic));
+            match checked {
+                Ok(checked) => {
+                    let mismatches: Vec<_> = checked.iter().filter(|&&(_, ref problems)| !problems.is_empty()).collect();
+                    for &&(ref exchange, ref problems) in &mismatches {
+                        println!("{} {} -> {}", exchange.request.method, exchange.request.path, exchange.response.status);
+                        for problem in problems {
+                            println!("    {}", problem);
+                        }
+                    }
+                    println!("{} exchanges, {} do not match the spec", checked.len(), mismatches.len());
+                    if !mismatches.is_empty() {
+                        std::process::exit(-1);
+                    }
+                }
+                Err(e) => exit_with_error(&e, &format!("Checking {} against {} failed", traffic, filename)),
+            }
+        }
+        ("testgen", Some(arguments)) => {
+            let filename = arguments.value_of("file").unwrap();
+            let document = match document_from_path(filename) {
+                Ok(document) => document,
+                Err(e) => return exit_with_error(&e, &format!("Reading {} failed", filename)),
+            };
+            let tests = testgen::run(&document.source, arguments.value_of("base_url").unwrap());
+            let title = document.spec.info.title.clone().unwrap_or_else(|| filename.to_string());
+            let xml = testgen::junit(&title, &tests);
+            match arguments.value_of("out") {
+                Some(out) => {
+                    if let Err(e) = write_file(out, &xml) {
+                        return exit_with_error(&e, "Writing the test report failed");
+                    }
+                    let passed = tests.iter().filter(|test| test.passed()).count();
+                    println!("{} tests, {} passed, {} failed", tests.len(), passed, tests.len() - passed);
+                }
+                None => print!("{}", xml),
+            }
+            if tests.iter().any(|test| !test.passed()) {
+                std::process::exit(-1);
+            }
+        }
+        ("codegen", Some(arguments)) => {
+            let filename = arguments.value_of("file").unwrap();
+            // clap only lets the possible values through.
+            let generated = codegen_document(filename).map(|document| match arguments.value_of("target").unwrap() {
+                "rust" => vec![(String::new(), codegen::rust::models(&document))],
+                "rust-client" => codegen::rust_client::files(&document),
+                "rust-server" => codegen::rust_server::files(&document),
+                "typescript" => vec![(String::new(), codegen::typescript::module(&document))],
+                target => unreachable!("Unknown target {}", target),
+            });
+            match (generated, arguments.value_of("out")) {
+                (Ok(files), Some(out)) => {
+                    if let Err(e) = write_generated(out, &files) {
+                        exit_with_error(&e, &format!("Writing the code of {} failed", filename));
+                    }
+                }
+                (Ok(ref files), None) if files.len() == 1 => print!("{}", files[0].1),
+                (Ok(_), None) => exit_with_error(&"It makes several files, --out is needed.".into(), "Generating code failed"),
+                (Err(e), _) => exit_with_error(&e, &format!("Generating code from {} failed", filename)),
+            }
+        }
         _ => println!("{}", application.usage()),
     }
 
@@ -110,21 +639,286 @@ fn main() {
 }
 
 
-fn convert(filename: &str, from: &str, to: &str) -> Result<String> {
-        let openapi_spec = if from == "openapi" {
+// OpenAPI or Google Discovery, converted to OpenAPI, depending on the content.
+fn document_from_path(filename: &str) -> Result<Document> {
+    let value = spec::value_from_path(filename)?;
+    if batch::Format::detect(&value) == batch::Format::GoogleDiscovery {
+        let google_spec = google_discovery_spec::from_path(filename)?;
+        Ok(Document::new(convert_google_spec::google_to_openapi::google_spec_to_openapi(&google_spec)))
+    } else {
+        spec::from_value(value)
+    }
+}
+
+
+enum ExampleOf<'a> {
+    // operationId and status.
+    Response(&'a str, Option<&'a str>),
+    Request(&'a str),
+    Definition(&'a str),
+}
+
+
+fn make_example(document: &Document, wanted: &ExampleOf) -> Result<String> {
+    let examples = openapi_examples::Examples::new(&document.source);
+    let find = |id: &str| {
+        openapi_examples::operation(&document.source, id).ok_or_else(|| Error::from(format!("There is no operation {}.", id)))
+    };
+    let example = match *wanted {
+        ExampleOf::Response(id, status) => {
+            let (path, verb, operation) = find(id)?;
+            let status = status
+                .or_else(|| openapi_examples::success_status(operation))
+                .ok_or_else(|| format!("The operation {} has no 2xx response, give the status.", id))?;
+            if operation.pointer(&format!("/responses/{}", status)).is_none() {
+                bail!("The operation {} has no response {}.", id, status);
+            }
+            examples.response(path, verb, status).ok_or_else(|| format!("The response {} of {} has no schema.", status, id))?
+        }
+        ExampleOf::Request(id) => {
+            let (path, verb, _) = find(id)?;
+            examples.request(path, verb).ok_or_else(|| format!("The operation {} has no body.", id))?
+        }
+        ExampleOf::Definition(name) => examples.definition(name).ok_or_else(|| format!("There is no definition {}.", name))?,
+    };
+    Ok(serde_json::to_string_pretty(&example).chain_err(|| "Unable to serialize the example.")?)
+}
+
+
+fn validation_profile(filename: &str) -> Result<ValidationOptions> {
+    let profile = spec::value_from_path(filename)?;
+    Ok(serde_json::from_value(profile).chain_err(|| format!("{} is not a valid validation profile.", filename))?)
+}
+
+
+fn ruleset(filename: &str) -> Result<Ruleset> {
+    let rules = spec::value_from_path(filename)?;
+    Ok(serde_json::from_value(rules).chain_err(|| format!("{} is not a valid rule file.", filename))?)
+}
+
+
+// The findings known when the baseline was written, only new ones are reported.
+fn baseline(filename: &str) -> Result<Vec<Finding>> {
+    let findings = spec::value_from_path(filename)?;
+    Ok(serde_json::from_value(findings).chain_err(|| format!("{} is not a valid baseline.", filename))?)
+}
+
+
+fn write_baseline(filename: &str, results: &ValidationResults) -> Result<()> {
+    let text = serde_yaml::to_string(&results.findings()).chain_err(|| "Unable to serialize the findings.")?;
+    write_file(filename, &format!("{}\n", text))
+}
+
+
+fn convert(filename: &str, from: &str, to: &str, options: &ConversionOptions, overrides: &Overrides) -> Result<String> {
+        let document = if from == "openapi" {
             spec::from_path(filename)?
         } else {
-            convert_google_spec::google_to_openapi::google_spec_to_openapi(&google_discovery_spec::from_path(filename)?)
+            Document::new(convert_google_spec::google_to_openapi::google_spec_to_openapi(&google_discovery_spec::from_path(filename)?))
            // openapi::Spec::from(&google_discovery::from_path(filename)?)
         };
 
-        if to == "openapi_json" {
-            spec::to_json(&openapi_spec)
-        } else if to == "openapi_yaml" {
-            spec::to_yaml(&openapi_spec)
+        if to == "openapi_json" || to == "openapi_yaml" || to == "markdown" {
+            let mut value = spec::to_value(&document)?;
+            overrides.apply(&mut value);
+            let document = spec::from_value(value).chain_err(|| "The overrides do not produce a valid spec.")?;
+            if to == "openapi_json" {
+                spec::to_json(&document)
+            } else if to == "markdown" {
+                Ok(markdown::markdown(&document.spec))
+            } else {
+                spec::to_yaml(&document)
+            }
         } else { // to google
-            exit_on_validation_error(&openapi_spec, &ValidationOptions{ support_google_spec: true, ..Default::default() });
-            // TODO: should not need thsi chain_err here
-            google_discovery_spec::to_yaml(&convert_google_spec::openapi_to_google::openapi_spec_to_google(openapi_spec)).chain_err(|| "Unable to serialize into YAML.")
+            exit_on_validation_error(&document, &ValidationOptions{ support_google_spec: true, ..Default::default() });
+            let collisions = convert_google_spec::openapi_to_google::method_collisions(&document.spec, options);
+            if !collisions.is_empty() {
+                bail!("{}. Rename their operationIds or tag them differently.", collisions.join(". "));
+            }
+            let google_spec = convert_google_spec::openapi_to_google::openapi_spec_to_google(document.spec.clone(), options);
+            let mut value = serde_json::to_value(&google_spec).chain_err(|| "Unable to serialize the Google spec.")?;
+            convert_google_spec::openapi_to_google::google_extensions(&document, options).apply(&mut value);
+            overrides.apply(&mut value);
+            serde_json::from_value::<google_discovery_spec::Spec>(value.clone()).chain_err(|| "The overrides do not produce a valid spec.")?;
+            let placeholders = overrides::placeholders(&value);
+            if !placeholders.is_empty() {
+                writeln!(&mut std::io::stderr(), "Warning: The result still has placeholders at {}. Replace them with --set or --overrides.", placeholders.join(", ")).unwrap();
+            }
+            Ok(serde_yaml::to_string(&value).chain_err(|| "Unable to serialize into YAML.")?)
+        }
+}
+
+
+fn apply_overlay(filename: &str, overlay_filename: &str, to: &str) -> Result<String> {
+    let mut document = spec::value_from_path(filename)?;
+    let overlay = overlay::from_value(spec::value_from_path(overlay_filename)?)?;
+
+    for warning in overlay::apply(&mut document, &overlay)? {
+        writeln!(&mut std::io::stderr(), "Warning: {}", warning).unwrap();
+    }
+
+    let result = spec::from_value(document)?;
+    if to == "openapi_json" {
+        spec::to_json(&result)
+    } else {
+        spec::to_yaml(&result)
+    }
+}
+
+
+fn merge_files(filenames: &[&str], options: &merge::MergeOptions, to: &str) -> Result<String> {
+    let mut inputs = vec![];
+    for filename in filenames {
+        let document = spec::value_from_path(filename)?;
+        spec::from_value(document.clone()).chain_err(|| format!("{} is not a valid OpenAPI file.", filename))?;
+        let name = Path::new(filename).file_stem().map_or("", |stem| stem.to_str().unwrap_or("")).to_string();
+        inputs.push(merge::Input { name: name, document: document });
+    }
+
+    let result = spec::from_value(merge::merge(inputs, options)?)?;
+    if to == "openapi_json" {
+        spec::to_json(&result)
+    } else {
+        spec::to_yaml(&result)
+    }
+}
+
+
+fn filter_file(filename: &str, options: &filter::FilterOptions, to: &str) -> Result<String> {
+    let document = spec::value_from_path(filename)?;
+    let result = spec::from_value(filter::filter(document, options))?;
+    if to == "openapi_json" {
+        spec::to_json(&result)
+    } else {
+        spec::to_yaml(&result)
+    }
+}
+
+
+// Writes the file back only when something changed, in the fmt form.
+// The findings in known are left as they are.
+fn fix_and_print(filename: &str, options: &ValidationOptions, known: &[Finding]) {
+    match fix_file(filename, options, known) {
+        Ok(fixes) => for fix in fixes {
+            println!("Fixed: {}", fix);
+        },
+        Err(e) => exit_with_error(&e, &format!("Fixing {} failed", filename)),
+    }
+}
+
+
+fn fix_file(filename: &str, options: &ValidationOptions, known: &[Finding]) -> Result<Vec<String>> {
+    let mut source = String::new();
+    File::open(filename)
+        .and_then(|mut file| file.read_to_string(&mut source))
+        .chain_err(|| format!("Unable to read the file {}.", filename))?;
+    let mut document: Value = serde_yaml::from_str(&source).chain_err(|| format!("Unable to deserialize the file {}.", filename))?;
+
+    let mut results = spec::from_value(document.clone())?.validate(options);
+    results.suppress(|finding| known.iter().any(|known| known.same_as(finding)));
+    let fixes = fix::fix(&mut document, &results.findings(), options);
+    if !fixes.is_empty() {
+        write_file(filename, &fmt::format_value(&document, &source, filename.ends_with(".json")))?;
+    }
+    Ok(fixes)
+}
+
+
+// The document to generate code from. Discovery schemas are taken from the file,
+// the conversion to OpenAPI loses part of them.
+fn codegen_document(filename: &str) -> Result<Value> {
+    let mut source = document_from_path(filename)?.source;
+    let value = spec::value_from_path(filename)?;
+    if batch::Format::detect(&value) == batch::Format::GoogleDiscovery {
+        source["definitions"] = codegen::discovery_definitions(&value);
+    }
+    Ok(source)
+}
+
+
+// Files generated with their path in out. A single one with no path is out itself.
+fn write_generated(out: &str, files: &[(String, String)]) -> Result<()> {
+    for &(ref path, ref text) in files {
+        // Joining an empty path would add a / to out.
+        let path = if path.is_empty() { Path::new(out).to_path_buf() } else { Path::new(out).join(path) };
+        if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            std::fs::create_dir_all(directory).chain_err(|| format!("Unable to create the directory {}.", directory.display()))?;
         }
+        write_file(&path.to_string_lossy(), text)?;
+    }
+    Ok(())
+}
+
+
+// Every recorded exchange with its problems, none when it matches the spec.
+fn check_traffic(document: &Document, filename: &str) -> Result<Vec<(openapi_conformance::Exchange, Vec<String>)>> {
+    let mut text = String::new();
+    File::open(filename)
+        .and_then(|mut file| file.read_to_string(&mut text))
+        .chain_err(|| format!("Unable to read {}.", filename))?;
+    let exchanges = openapi_conformance::traffic::read(&text).map_err(Error::from)?;
+    let router = openapi_conformance::Router::new(&document.source);
+    Ok(exchanges
+        .into_iter()
+        .map(|exchange| {
+            let problems = openapi_conformance::check_exchange(&document.source, &router, &exchange);
+            (exchange, problems)
+        })
+        .collect())
+}
+
+
+// Returns the original text and the formatted one.
+fn format_file(filename: &str) -> Result<(String, String)> {
+    let mut original = String::new();
+    File::open(filename)
+        .and_then(|mut file| file.read_to_string(&mut original))
+        .chain_err(|| format!("Unable to read {}.", filename))?;
+    let json = filename.ends_with(".json");
+    let formatted = fmt::format(&original, json)?;
+    Ok((original, formatted))
+}
+
+fn write_file(filename: &str, text: &str) -> Result<()> {
+    File::create(filename)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .chain_err(|| format!("Unable to write {}.", filename))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    #[test]
+    fn converts_inline_responses_to_google_with_placeholders() {
+        let filename = env::temp_dir().join(format!("oatool-convert-{}.json", process::id())).display().to_string();
+        write_file(&filename, r##"{
+            "swagger": "2.0",
+            "info": {"title": "Pets", "version": "1", "description": "Pets.", "contact": {"name": "Jordi", "email": "jordi@example.com"}},
+            "basePath": "/v1", "schemes": ["https"], "consumes": ["application/json"], "produces": ["application/json"],
+            "paths": {
+                "/pets/{name}": {
+                    "get": {
+                        "operationId": "pets.get", "summary": "Gets a pet.", "description": "Gets a pet.",
+                        "parameters": [{"$ref": "#/parameters/name"}],
+                        "responses": {"200": {"description": "ok", "schema": {"type": "object", "properties": {"name": {"type": "string"}}}}}
+                    }
+                }
+            },
+            "parameters": {"name": {"name": "name", "in": "path", "required": true, "type": "string", "description": "The name."}},
+            "definitions": {"Pet": {"type": "object", "properties": {"name": {"type": "string"}}}}
+        }"##).unwrap();
+        let result = convert(&filename, "openapi", "google", &ConversionOptions { group_by_tags: false }, &Overrides::new(None, &[]).unwrap());
+        fs::remove_file(&filename).unwrap();
+
+        // The response has no id to give it, the result still converts.
+        let value: Value = serde_yaml::from_str(&result.unwrap()).unwrap();
+        assert_eq!(overrides::placeholders(&value), vec![
+            "resources.pets.methods.get.response.id",
+            "resources.pets.methods.get.response.resource",
+        ]);
+    }
 }
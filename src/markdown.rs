@@ -0,0 +1,329 @@
+// This module renders a Markdown reference of the API, in the GitHub flavor.
+// Links point to the anchors GitHub makes from the headings.
+
+use openapi;
+use openapi_extensions;
+use std::collections::BTreeMap;
+
+// GitHub anchors: lowercase, punctuation dropped, spaces as -, repeated ones numbered.
+#[derive(Default)]
+struct Anchors(BTreeMap<String, usize>);
+
+impl Anchors {
+    fn add(&mut self, heading: &str) -> String {
+        let anchor: String = heading
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+            .map(|c| if c == ' ' { '-' } else { c })
+            .collect();
+        let count = self.0.entry(anchor.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 { anchor } else { format!("{}-{}", anchor, *count - 1) }
+    }
+}
+
+struct Section {
+    heading: String,
+    anchor: String,
+    operations: Vec<(String, String)>,
+}
+
+pub fn markdown(spec: &openapi::Spec) -> String {
+    let mut anchors = Anchors::default();
+    let title = spec.info.title.clone().unwrap_or_else(|| "API reference".to_string());
+    anchors.add(&title);
+    anchors.add("Table of contents");
+
+    // Anchors are given in the order of the headings.
+    let paths_anchor = anchors.add("Paths");
+    let mut sections = vec![];
+    for (path, operations) in &spec.paths {
+        let mut section = Section { heading: path.to_string(), anchor: anchors.add(path), operations: vec![] };
+        for (verb, _) in openapi_extensions::operations(operations) {
+            let heading = format!("{} {}", verb.to_uppercase(), path);
+            let anchor = anchors.add(&heading);
+            section.operations.push((heading, anchor));
+        }
+        sections.push(section);
+    }
+    let definitions_anchor = anchors.add("Definitions");
+    let definition_anchors: BTreeMap<&str, String> = spec.definitions
+        .iter()
+        .flat_map(|definitions| definitions.keys())
+        .map(|name| (name.as_str(), anchors.add(name)))
+        .collect();
+
+    let mut text = format!("# {}\n\n", title);
+    if let Some(ref description) = spec.info.description {
+        text.push_str(&format!("{}\n\n", description.trim()));
+    }
+    if let Some(ref version) = spec.info.version {
+        text.push_str(&format!("Version: {}\n\n", version));
+    }
+    if let Some(ref host) = spec.host {
+        let scheme = spec.schemes.as_ref().and_then(|schemes| schemes.first()).map_or("https", |scheme| scheme.as_str());
+        text.push_str(&format!("Base URL: `{}://{}{}`\n\n", scheme, host, spec.base_path.as_ref().map_or("", |path| path.as_str())));
+    }
+
+    text.push_str("## Table of contents\n\n");
+    text.push_str(&format!("- [Paths](#{})\n", paths_anchor));
+    for section in &sections {
+        text.push_str(&format!("  - [{}](#{})\n", section.heading, section.anchor));
+        for &(ref heading, ref anchor) in &section.operations {
+            text.push_str(&format!("    - [{}](#{})\n", heading, anchor));
+        }
+    }
+    if !definition_anchors.is_empty() {
+        text.push_str(&format!("- [Definitions](#{})\n", definitions_anchor));
+        for (name, anchor) in &definition_anchors {
+            text.push_str(&format!("  - [{}](#{})\n", name, anchor));
+        }
+    }
+
+    text.push_str("\n## Paths\n");
+    for (path, operations) in &spec.paths {
+        text.push_str(&format!("\n### {}\n", path));
+        for (verb, operation) in openapi_extensions::operations(operations) {
+            text.push_str(&format!("\n#### {} {}\n\n", verb.to_uppercase(), path));
+            operation_markdown(spec, operation, &operations.parameters, &definition_anchors, &mut text);
+        }
+    }
+
+    if let Some(ref definitions) = spec.definitions {
+        text.push_str("\n## Definitions\n");
+        for (name, definition) in definitions {
+            text.push_str(&format!("\n### {}\n\n", name));
+            if let Some(ref description) = definition.description {
+                text.push_str(&format!("{}\n\n", description.trim()));
+            }
+            definition_markdown(definition, &definition_anchors, &mut text);
+        }
+    }
+    text
+}
+
+fn operation_markdown(
+    spec: &openapi::Spec,
+    operation: &openapi::Operation,
+    path_parameters: &Option<Vec<openapi::ParameterOrRef>>,
+    anchors: &BTreeMap<&str, String>,
+    text: &mut String,
+) {
+    if let Some(ref summary) = operation.summary {
+        text.push_str(&format!("**{}**\n\n", summary.trim()));
+    }
+    if let Some(ref description) = operation.description {
+        text.push_str(&format!("{}\n\n", description.trim()));
+    }
+    if let Some(ref id) = operation.operation_id {
+        text.push_str(&format!("operationId: `{}`\n\n", id));
+    }
+
+    let parameters = parameters(spec, &operation.parameters, path_parameters);
+    if !parameters.is_empty() {
+        text.push_str("| Name | In | Type | Required | Description |\n|---|---|---|---|---|\n");
+        for parameter in &parameters {
+            let parameter_type = match (&parameter.schema, &parameter.param_type) {
+                (&Some(ref schema), _) => schema_type(schema, anchors),
+                (&None, &Some(ref parameter_type)) => with_format(parameter_type, &parameter.format),
+                (&None, &None) => String::new(),
+            };
+            text.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                parameter.name,
+                parameter.location,
+                parameter_type,
+                if parameter.required == Some(true) { "yes" } else { "no" },
+                cell(parameter.description.as_ref().map_or("", |description| description.as_str()))
+            ));
+        }
+        text.push('\n');
+    }
+
+    text.push_str("| Code | Description | Schema |\n|---|---|---|\n");
+    for (status, response) in &operation.responses {
+        text.push_str(&format!(
+            "| {} | {} | {} |\n",
+            status,
+            cell(&response.description),
+            response.schema.as_ref().map_or(String::new(), |schema| schema_type(schema, anchors))
+        ));
+    }
+}
+
+// The operation parameters override the path ones. Missing references are skipped.
+fn parameters(
+    spec: &openapi::Spec,
+    operation_parameters: &Option<Vec<openapi::ParameterOrRef>>,
+    path_parameters: &Option<Vec<openapi::ParameterOrRef>>,
+) -> Vec<openapi::Parameter> {
+    let mut parameters: Vec<openapi::Parameter> = vec![];
+    let declared = operation_parameters.iter().chain(path_parameters.iter()).flat_map(|declared| declared.iter());
+    for parameter in declared {
+        let parameter = match *parameter {
+            openapi::ParameterOrRef::Ref { ref ref_path } => spec.parameters
+                .as_ref()
+                .and_then(|parameters| parameters.get(ref_path.trim_start_matches("#/parameters/")))
+                .cloned(),
+            openapi::ParameterOrRef::Parameter {
+                ref name,
+                ref location,
+                ref required,
+                ref schema,
+                ref unique_items,
+                ref param_type,
+                ref format,
+                ref description,
+            } => Some(openapi::Parameter {
+                name: name.clone(),
+                location: location.clone(),
+                required: *required,
+                schema: schema.clone(),
+                unique_items: *unique_items,
+                param_type: param_type.clone(),
+                format: format.clone(),
+                description: description.clone(),
+            }),
+        };
+        if let Some(parameter) = parameter {
+            if !parameters.iter().any(|p| p.name == parameter.name && p.location == parameter.location) {
+                parameters.push(parameter);
+            }
+        }
+    }
+    parameters
+}
+
+fn definition_markdown(definition: &openapi::Schema, anchors: &BTreeMap<&str, String>, text: &mut String) {
+    let properties = match definition.properties {
+        Some(ref properties) if !properties.is_empty() => properties,
+        _ => {
+            text.push_str(&format!("Type: {}\n", schema_type(definition, anchors)));
+            return;
+        }
+    };
+    let required = definition.required.as_ref();
+    text.push_str("| Property | Type | Required | Description |\n|---|---|---|---|\n");
+    for (name, property) in properties {
+        text.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            name,
+            schema_type(property, anchors),
+            if required.map_or(false, |required| required.contains(name)) { "yes" } else { "no" },
+            cell(property.description.as_ref().map_or("", |description| description.as_str()))
+        ));
+    }
+}
+
+// [Pet](#pet), array of [Pet](#pet), string (date-time), string: `a`, `b`
+fn schema_type(schema: &openapi::Schema, anchors: &BTreeMap<&str, String>) -> String {
+    if let Some(ref ref_path) = schema.ref_path {
+        let name = ref_path.trim_start_matches("#/definitions/");
+        return match anchors.get(name) {
+            Some(anchor) => format!("[{}](#{})", name, anchor),
+            None => name.to_string(),
+        };
+    }
+    let name = schema.schema_type.as_ref().map_or("object", |name| name.as_str());
+    let mut text = match schema.items {
+        Some(ref items) if name == "array" => format!("array of {}", schema_type(items, anchors)),
+        _ => with_format(name, &schema.format),
+    };
+    if let Some(ref values) = schema.enum_values {
+        let values: Vec<String> = values.iter().map(|value| format!("`{}`", value)).collect();
+        text.push_str(&format!(": {}", values.join(", ")));
+    }
+    text
+}
+
+fn with_format(schema_type: &str, format: &Option<String>) -> String {
+    match *format {
+        Some(ref format) => format!("{} ({})", schema_type, format),
+        None => schema_type.to_string(),
+    }
+}
+
+// Table cells are a single line and | separates them.
+fn cell(text: &str) -> String {
+    text.trim().replace('|', "\\|").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    fn spec() -> openapi::Spec {
+        json(r##"{
+            "swagger": "2.0",
+            "info": {"title": "Pets", "version": "1"},
+            "host": "api.example.com", "basePath": "/v1",
+            "parameters": {"Limit": {"name": "limit", "in": "query", "type": "integer", "format": "int32"}},
+            "paths": {
+                "/pets/{id}": {
+                    "parameters": [
+                        {"name": "id", "in": "path", "required": true, "type": "string", "description": "The path one"},
+                        {"$ref": "#/parameters/Limit"},
+                        {"$ref": "#/parameters/Missing"}
+                    ],
+                    "get": {
+                        "summary": "Gets a pet",
+                        "operationId": "getPet",
+                        "parameters": [{"name": "id", "in": "path", "required": true, "type": "string", "description": "The pet | id\nonly"}],
+                        "responses": {"200": {"description": "ok", "schema": {"type": "array", "items": {"$ref": "#/definitions/Pet"}}}}
+                    }
+                }
+            },
+            "definitions": {
+                "Pet": {"type": "object", "required": ["name"], "properties": {
+                    "name": {"type": "string"},
+                    "born": {"type": "string", "format": "date-time", "description": "When"},
+                    "kind": {"type": "string", "enum": ["cat", "dog"]}
+                }},
+                "Paths": {"type": "string"}
+            }
+        }"##)
+    }
+
+    #[test]
+    fn numbers_repeated_anchors() {
+        let mut anchors = Anchors::default();
+        assert_eq!(anchors.add("GET /pets/{id}"), "get-petsid");
+        assert_eq!(anchors.add("Snake_case & more"), "snake_case--more");
+        assert_eq!(anchors.add("get /pets/{id}"), "get-petsid-1");
+    }
+
+    #[test]
+    fn links_the_table_of_contents_to_the_headings() {
+        let text = markdown(&spec());
+        assert!(text.starts_with("# Pets\n\nVersion: 1\n\nBase URL: `https://api.example.com/v1`\n\n"));
+        assert!(text.contains(&[
+            "- [Paths](#paths)\n",
+            "  - [/pets/{id}](#petsid)\n",
+            "    - [GET /pets/{id}](#get-petsid)\n",
+            "- [Definitions](#definitions)\n",
+            "  - [Paths](#paths-1)\n",
+            "  - [Pet](#pet)\n",
+        ].concat()));
+        assert!(text.contains("\n### /pets/{id}\n\n#### GET /pets/{id}\n\n**Gets a pet**\n\noperationId: `getPet`\n\n"));
+    }
+
+    #[test]
+    fn writes_tables_of_parameters_responses_and_properties() {
+        let text = markdown(&spec());
+        assert!(text.contains(&[
+            "| Name | In | Type | Required | Description |\n|---|---|---|---|---|\n",
+            "| id | path | string | yes | The pet \\| id<br>only |\n",
+            "| limit | query | integer (int32) | no |  |\n\n",
+        ].concat()));
+        assert!(text.contains("| 200 | ok | array of [Pet](#pet) |\n"));
+        assert!(text.contains(&[
+            "| Property | Type | Required | Description |\n|---|---|---|---|\n",
+            "| born | string (date-time) | no | When |\n",
+            "| kind | string: `cat`, `dog` | no |  |\n",
+            "| name | string | yes |  |\n",
+        ].concat()));
+        assert!(text.contains("\n### Paths\n\nType: string\n"));
+    }
+}
@@ -0,0 +1,574 @@
+// This module merges several OpenAPI files into one.
+// It works on the untyped documents so references can be rewritten when
+// something is renamed, the result is checked against the types afterwards.
+
+use inflector::Inflector;
+use openapi_extensions::VERBS;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+use errors::*;
+
+// What to do when two files define the same element differently.
+// Identical elements are never a conflict.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Conflict {
+    Error,
+    // The element of the later file is renamed using the file name as prefix.
+    Rename,
+    // The element of the earlier file is kept, the later one is dropped.
+    PreferFirst,
+}
+
+impl Conflict {
+    pub fn from_name(name: &str) -> Conflict {
+        match name {
+            "rename" => Conflict::Rename,
+            "prefer_first" => Conflict::PreferFirst,
+            _ => Conflict::Error,
+        }
+    }
+}
+
+pub struct MergeOptions {
+    pub paths: Conflict,
+    pub definitions: Conflict,
+    pub parameters: Conflict,
+    pub responses: Conflict,
+    pub tags: Conflict,
+    pub security_definitions: Conflict,
+    // Only Error and Rename make sense, the operation is kept either way.
+    pub operation_ids: Conflict,
+    // Adds the basePath of each file in front of its paths.
+    pub prefix_base_paths: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> MergeOptions {
+        MergeOptions {
+            paths: Conflict::Error,
+            definitions: Conflict::Error,
+            parameters: Conflict::Error,
+            responses: Conflict::Error,
+            tags: Conflict::Error,
+            security_definitions: Conflict::Error,
+            operation_ids: Conflict::Rename,
+            prefix_base_paths: false,
+        }
+    }
+}
+
+pub struct Input {
+    // Used as prefix when renaming, usually the file name.
+    pub name: String,
+    pub document: Value,
+}
+
+// Maps of named elements, with the prefix used by their references and how renamed names look.
+struct Category {
+    key: &'static str,
+    reference: &'static str,
+    description: &'static str,
+}
+
+const DEFINITIONS: Category = Category { key: "definitions", reference: "#/definitions/", description: "definition" };
+const PARAMETERS: Category = Category { key: "parameters", reference: "#/parameters/", description: "parameter" };
+const RESPONSES: Category = Category { key: "responses", reference: "#/responses/", description: "response" };
+const SECURITY_DEFINITIONS: Category = Category { key: "securityDefinitions", reference: "", description: "security definition" };
+
+pub fn merge(inputs: Vec<Input>, options: &MergeOptions) -> Result<Value> {
+    let mut result = match inputs.first() {
+        Some(input) => input.document.clone(),
+        None => bail!("There is nothing to merge."),
+    };
+    {
+        let map = as_object_mut(&mut result)?;
+        for key in &["paths", "definitions", "parameters", "responses", "tags", "securityDefinitions"] {
+            map.remove(*key);
+        }
+        map.insert("paths".to_string(), Value::Object(Map::new()));
+        if options.prefix_base_paths {
+            map.insert("basePath".to_string(), Value::String("/".to_string()));
+        }
+    }
+
+    for input in inputs {
+        merge_input(&mut result, input, options)?;
+    }
+
+    Ok(result)
+}
+
+
+fn merge_input(result: &mut Value, input: Input, options: &MergeOptions) -> Result<()> {
+    let name = input.name;
+    let mut document = input.document;
+
+    if options.prefix_base_paths {
+        prefix_base_path(&mut document)?;
+    } else if document.get("basePath") != result.get("basePath") {
+        bail!(
+            "The basePath of {} is different from the basePath of the first file. Use --prefix_base_paths to merge them.",
+            name
+        );
+    }
+
+    for &(category, conflict) in &[
+        (&DEFINITIONS, options.definitions),
+        (&PARAMETERS, options.parameters),
+        (&RESPONSES, options.responses),
+        (&SECURITY_DEFINITIONS, options.security_definitions),
+    ] {
+        merge_category(result, &mut document, &name, category, conflict)?;
+    }
+    merge_tags(result, &mut document, &name, options.tags)?;
+    rename_operation_ids(result, &mut document, &name, options.operation_ids)?;
+    merge_paths(result, &mut document, &name, options.paths)
+}
+
+fn prefix_base_path(document: &mut Value) -> Result<()> {
+    let base_path = document.get("basePath").and_then(|b| b.as_str()).unwrap_or("").trim_end_matches('/').to_string();
+    let map = as_object_mut(document)?;
+    map.remove("basePath");
+    if let Some(Value::Object(paths)) = map.remove("paths") {
+        let prefixed = paths.into_iter().map(|(path, item)| (format!("{}{}", base_path, path), item)).collect();
+        map.insert("paths".to_string(), Value::Object(prefixed));
+    }
+    Ok(())
+}
+
+fn merge_category(result: &mut Value, document: &mut Value, name: &str, category: &Category, conflict: Conflict) -> Result<()> {
+    let mut elements = match as_object_mut(document)?.remove(category.key) {
+        Some(elements @ Value::Object(_)) => elements,
+        _ => return Ok(()),
+    };
+
+    let mut merged = child_object(result, category.key)?.clone();
+    let mut renames = vec![];
+    let mut skipped = BTreeSet::new();
+    for (key, element) in elements.as_object().unwrap() {
+        match merged.get(key) {
+            Some(existing) if existing != element => match conflict {
+                Conflict::Error => bail!("The {} {} in {} conflicts with a previous file.", category.description, key, name),
+                Conflict::PreferFirst => {
+                    skipped.insert(key.to_string());
+                }
+                Conflict::Rename => {
+                    let new_key = unique_name(&renamed(name, key, category), |candidate| {
+                        merged.contains_key(candidate) || elements.get(candidate).is_some()
+                    });
+                    renames.push((key.to_string(), new_key));
+                }
+            },
+            _ => {}
+        }
+    }
+
+    // Elements of this category can reference each other, they are renamed too.
+    for &(ref old, ref new) in &renames {
+        rename_references(document, category, old, new);
+        rename_references(&mut elements, category, old, new);
+    }
+
+    if let Value::Object(elements) = elements {
+        for (key, element) in elements {
+            if skipped.contains(&key) {
+                continue;
+            }
+            let key = renames.iter().find(|&&(ref old, _)| old == &key).map_or(key.clone(), |&(_, ref new)| new.clone());
+            merged.insert(key, element);
+        }
+    }
+
+    as_object_mut(result)?.insert(category.key.to_string(), Value::Object(merged));
+    Ok(())
+}
+
+fn merge_tags(result: &mut Value, document: &mut Value, name: &str, conflict: Conflict) -> Result<()> {
+    let tags = match as_object_mut(document)?.remove("tags") {
+        Some(Value::Array(tags)) => tags,
+        _ => return Ok(()),
+    };
+
+    let mut merged = match result.get("tags") {
+        Some(&Value::Array(ref tags)) => tags.clone(),
+        _ => vec![],
+    };
+    for mut tag in tags {
+        let tag_name = tag.get("name").and_then(|n| n.as_str()).unwrap_or("").to_string();
+        let existing = merged.iter().find(|t| t.get("name").and_then(|n| n.as_str()) == Some(tag_name.as_str())).cloned();
+        match existing {
+            Some(ref existing) if existing == &tag => continue,
+            Some(_) => match conflict {
+                Conflict::Error => bail!("The tag {} in {} conflicts with a previous file.", tag_name, name),
+                Conflict::PreferFirst => continue,
+                Conflict::Rename => {
+                    let new_name = unique_name(&format!("{}_{}", name, tag_name), |candidate| {
+                        merged.iter().any(|t| t.get("name").and_then(|n| n.as_str()) == Some(candidate))
+                    });
+                    for operation in operations_mut(document) {
+                        if let Some(&mut Value::Array(ref mut operation_tags)) = operation.get_mut("tags") {
+                            for operation_tag in operation_tags.iter_mut() {
+                                if operation_tag.as_str() == Some(&tag_name) {
+                                    *operation_tag = Value::String(new_name.clone());
+                                }
+                            }
+                        }
+                    }
+                    tag["name"] = Value::String(new_name);
+                }
+            },
+            None => {}
+        }
+        merged.push(tag);
+    }
+
+    as_object_mut(result)?.insert("tags".to_string(), Value::Array(merged));
+    Ok(())
+}
+
+fn rename_operation_ids(result: &mut Value, document: &mut Value, name: &str, conflict: Conflict) -> Result<()> {
+    let mut existing: BTreeSet<String> = operations_mut(result)
+        .into_iter()
+        .filter_map(|operation| operation.get("operationId").and_then(|id| id.as_str()).map(|id| id.to_string()))
+        .collect();
+
+    let merged = result.get("paths");
+    let paths = document.get_mut("paths").and_then(|paths| paths.as_object_mut());
+    for (path, item) in paths.into_iter().flat_map(|paths| paths.iter_mut()) {
+        // A path item identical to the merged one (a shared /health, the same file
+        // twice) is no conflict, its ids are those already there.
+        if merged.and_then(|merged| merged.get(path)) == Some(&*item) {
+            continue;
+        }
+        let operations = item.as_object_mut().into_iter().flat_map(|item| item.iter_mut()).filter(|entry| VERBS.contains(&entry.0.as_str()));
+        for (_, operation) in operations {
+            let operation_id = match operation.get("operationId").and_then(|id| id.as_str()) {
+                Some(id) => id.to_string(),
+                None => continue,
+            };
+            if existing.contains(&operation_id) {
+                if conflict != Conflict::Rename {
+                    bail!("The operationId {} in {} is already used by a previous file.", operation_id, name);
+                }
+                let new_id = unique_name(&format!("{}_{}", name.to_snake_case(), operation_id), |candidate| existing.contains(candidate));
+                operation["operationId"] = Value::String(new_id.clone());
+                existing.insert(new_id);
+            } else {
+                existing.insert(operation_id);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge_paths(result: &mut Value, document: &mut Value, name: &str, conflict: Conflict) -> Result<()> {
+    let paths = match as_object_mut(document)?.remove("paths") {
+        Some(Value::Object(paths)) => paths,
+        _ => return Ok(()),
+    };
+
+    let merged = child_object(result, "paths")?;
+    for (path, item) in paths {
+        let clashes = match merged.get(&path) {
+            Some(existing) => clashing_keys(existing, &item),
+            None => vec![],
+        };
+
+        if clashes.is_empty() {
+            let target = merged.entry(path).or_insert_with(|| Value::Object(Map::new()));
+            if let (&mut Value::Object(ref mut target), Value::Object(item)) = (target, item) {
+                target.extend(item);
+            }
+            continue;
+        }
+
+        match conflict {
+            Conflict::Error => bail!("{} of path {} in {} are already defined by a previous file.", clashes.join(", "), path, name),
+            Conflict::PreferFirst => {
+                if let (Some(&mut Value::Object(ref mut target)), Value::Object(item)) = (merged.get_mut(&path), item) {
+                    for (key, value) in item {
+                        target.entry(key).or_insert(value);
+                    }
+                }
+            }
+            Conflict::Rename => {
+                let new_path = format!("/{}{}", name.to_snake_case(), path);
+                if merged.contains_key(&new_path) {
+                    bail!("Path {} in {} can not be renamed to {}, it already exists.", path, name, new_path);
+                }
+                merged.insert(new_path, item);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Verbs or path parameters defined in both path items with different values.
+fn clashing_keys(existing: &Value, item: &Value) -> Vec<String> {
+    item.as_object()
+        .map(|item| {
+            item.iter()
+                .filter(|&(key, value)| existing.get(key).map_or(false, |existing| existing != value))
+                .map(|(key, _)| key.to_string())
+                .collect()
+        })
+        .unwrap_or_else(Vec::new)
+}
+
+fn renamed(name: &str, key: &str, category: &Category) -> String {
+    if category.key == DEFINITIONS.key {
+        format!("{}{}", name.to_pascal_case(), key)
+    } else {
+        format!("{}_{}", name.to_snake_case(), key)
+    }
+}
+
+fn unique_name<F>(candidate: &str, exists: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    let mut name = candidate.to_string();
+    let mut counter = 2;
+    while exists(&name) {
+        name = format!("{}{}", candidate, counter);
+        counter += 1;
+    }
+    name
+}
+
+fn rename_references(document: &mut Value, category: &Category, old: &str, new: &str) {
+    if category.key == SECURITY_DEFINITIONS.key {
+        // Security definitions are used by name in the security requirements.
+        for operation in operations_mut(document) {
+            if let Some(security) = operation.get_mut("security") {
+                rename_security_requirement(security, old, new);
+            }
+        }
+        if let Some(security) = document.get_mut("security") {
+            rename_security_requirement(security, old, new);
+        }
+    } else {
+        rewrite_refs(document, &format!("{}{}", category.reference, old), &format!("{}{}", category.reference, new));
+    }
+}
+
+fn rename_security_requirement(security: &mut Value, old: &str, new: &str) {
+    for requirement in security.as_array_mut().into_iter().flat_map(|r| r.iter_mut()) {
+        if let Some(map) = requirement.as_object_mut() {
+            if let Some(scopes) = map.remove(old) {
+                map.insert(new.to_string(), scopes);
+            }
+        }
+    }
+}
+
+fn rewrite_refs(value: &mut Value, old: &str, new: &str) {
+    match *value {
+        Value::Object(ref mut map) => {
+            for (key, child) in map.iter_mut() {
+                if key == "$ref" && child.as_str() == Some(old) {
+                    *child = Value::String(new.to_string());
+                } else {
+                    rewrite_refs(child, old, new);
+                }
+            }
+        }
+        Value::Array(ref mut array) => {
+            for child in array.iter_mut() {
+                rewrite_refs(child, old, new);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn operations_mut(document: &mut Value) -> Vec<&mut Value> {
+    match document.get_mut("paths") {
+        Some(&mut Value::Object(ref mut paths)) => paths
+            .values_mut()
+            .filter_map(|item| item.as_object_mut())
+            .flat_map(|item| item.iter_mut().filter(|entry| VERBS.contains(&entry.0.as_str())).map(|(_, operation)| operation))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn as_object_mut(value: &mut Value) -> Result<&mut Map<String, Value>> {
+    value.as_object_mut().ok_or_else(|| "The document is not an object.".into())
+}
+
+fn child_object<'a>(value: &'a mut Value, key: &str) -> Result<&'a mut Map<String, Value>> {
+    as_object_mut(value)?
+        .entry(key.to_string())
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| format!("{} is not an object.", key).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    fn input(name: &str, text: &str) -> Input {
+        Input {
+            name: name.to_string(),
+            document: json(text),
+        }
+    }
+
+    fn pets() -> Input {
+        input("pets", r##"{
+            "swagger": "2.0", "info": {"title": "Pets", "version": "1"}, "basePath": "/v1",
+            "tags": [{"name": "common", "description": "Pets"}],
+            "paths": {"/pets": {"get": {"operationId": "list", "tags": ["common"], "responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Item"}}}}}},
+            "definitions": {"Item": {"type": "object", "properties": {"name": {"type": "string"}}}},
+            "securityDefinitions": {"key": {"type": "apiKey", "name": "key", "in": "header"}}
+        }"##)
+    }
+
+    fn users() -> Input {
+        input("users", r##"{
+            "swagger": "2.0", "info": {"title": "Users", "version": "1"}, "basePath": "/v1",
+            "tags": [{"name": "common", "description": "Users"}],
+            "paths": {
+                "/users": {"get": {"operationId": "list", "tags": ["common"], "security": [{"key": []}], "responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Item"}}}}},
+                "/pets": {"post": {"operationId": "create", "responses": {"201": {"description": "created"}}}}
+            },
+            "definitions": {
+                "Item": {"type": "object", "properties": {"email": {"type": "string"}}},
+                "Items": {"type": "array", "items": {"$ref": "#/definitions/Item"}}
+            },
+            "securityDefinitions": {"key": {"type": "apiKey", "name": "token", "in": "query"}}
+        }"##)
+    }
+
+    fn renaming() -> MergeOptions {
+        MergeOptions {
+            definitions: Conflict::Rename,
+            tags: Conflict::Rename,
+            security_definitions: Conflict::Rename,
+            ..MergeOptions::default()
+        }
+    }
+
+    #[test]
+    fn needs_an_input() {
+        assert!(merge(vec![], &MergeOptions::default()).is_err());
+    }
+
+    #[test]
+    fn keeps_the_first_info_and_identical_elements() {
+        let copy = input("copy", r#"{
+            "info": {"title": "Copy"}, "basePath": "/v1",
+            "tags": [{"name": "common", "description": "Pets"}],
+            "paths": {"/copies": {"get": {"operationId": "list", "responses": {}}}},
+            "definitions": {"Item": {"type": "object", "properties": {"name": {"type": "string"}}}}
+        }"#);
+        let result = merge(vec![pets(), copy], &MergeOptions::default()).unwrap();
+        assert_eq!(result["info"]["title"], "Pets");
+        assert_eq!(result["tags"].as_array().unwrap().len(), 1);
+        assert_eq!(result["definitions"].as_object().unwrap().len(), 1);
+        // A used operationId is renamed by default.
+        assert_eq!(result["paths"]["/copies"]["get"]["operationId"], "copy_list");
+    }
+
+    #[test]
+    fn fails_on_conflicts_by_default() {
+        let error = merge(vec![pets(), users()], &MergeOptions::default()).unwrap_err();
+        assert_eq!(error.to_string(), "The definition Item in users conflicts with a previous file.");
+    }
+
+    #[test]
+    fn renames_conflicts_and_their_references() {
+        let result = merge(vec![pets(), users()], &renaming()).unwrap();
+        let definitions = result["definitions"].as_object().unwrap();
+        assert_eq!(definitions.keys().collect::<Vec<_>>(), vec!["Item", "UsersItem", "Items"]);
+        assert_eq!(result["definitions"]["Items"]["items"]["$ref"], "#/definitions/UsersItem");
+        assert_eq!(result["paths"]["/users"]["get"]["responses"]["200"]["schema"]["$ref"], "#/definitions/UsersItem");
+        assert_eq!(result["paths"]["/pets"]["get"]["responses"]["200"]["schema"]["$ref"], "#/definitions/Item");
+
+        assert_eq!(result["tags"][1]["name"], "users_common");
+        assert_eq!(result["paths"]["/users"]["get"]["tags"][0], "users_common");
+        assert_eq!(result["paths"]["/users"]["get"]["operationId"], "users_list");
+
+        assert!(result["securityDefinitions"].get("users_key").is_some());
+        assert_eq!(result["paths"]["/users"]["get"]["security"], json::<Value>(r#"[{"users_key": []}]"#));
+    }
+
+    #[test]
+    fn prefers_the_first_file() {
+        let options = MergeOptions {
+            definitions: Conflict::PreferFirst,
+            tags: Conflict::PreferFirst,
+            security_definitions: Conflict::PreferFirst,
+            ..MergeOptions::default()
+        };
+        let result = merge(vec![pets(), users()], &options).unwrap();
+        assert_eq!(result["definitions"]["Item"]["properties"]["name"]["type"], "string");
+        assert_eq!(result["tags"].as_array().unwrap().len(), 1);
+        assert_eq!(result["securityDefinitions"]["key"]["name"], "key");
+    }
+
+    #[test]
+    fn merges_verbs_of_the_same_path() {
+        let result = merge(vec![pets(), users()], &renaming()).unwrap();
+        let verbs: Vec<&String> = result["paths"]["/pets"].as_object().unwrap().keys().collect();
+        assert_eq!(verbs, vec!["get", "post"]);
+    }
+
+    #[test]
+    fn handles_clashing_operations() {
+        let other = || input("other", r#"{"basePath": "/v1", "paths": {"/pets": {"get": {"operationId": "other", "responses": {}}}}}"#);
+        let error = merge(vec![pets(), other()], &MergeOptions::default()).unwrap_err();
+        assert_eq!(error.to_string(), "get of path /pets in other are already defined by a previous file.");
+
+        let options = MergeOptions { paths: Conflict::Rename, ..MergeOptions::default() };
+        let result = merge(vec![pets(), other()], &options).unwrap();
+        assert_eq!(result["paths"]["/other/pets"]["get"]["operationId"], "other");
+
+        let options = MergeOptions { paths: Conflict::PreferFirst, ..MergeOptions::default() };
+        let result = merge(vec![pets(), other()], &options).unwrap();
+        assert_eq!(result["paths"]["/pets"]["get"]["operationId"], "list");
+    }
+
+    #[test]
+    fn rejects_duplicated_operation_ids_when_asked() {
+        let options = MergeOptions { operation_ids: Conflict::Error, ..renaming() };
+        let error = merge(vec![pets(), users()], &options).unwrap_err();
+        assert_eq!(error.to_string(), "The operationId list in users is already used by a previous file.");
+    }
+
+    #[test]
+    fn merges_identical_path_items_as_they_are() {
+        let health = |name| input(name, r#"{"basePath": "/v1", "paths": {"/health": {"get": {"operationId": "health", "responses": {}}}}}"#);
+        for &operation_ids in &[Conflict::Rename, Conflict::Error] {
+            let options = MergeOptions { operation_ids: operation_ids, ..MergeOptions::default() };
+            let result = merge(vec![health("pets"), health("users")], &options).unwrap();
+            assert_eq!(result["paths"], health("pets").document["paths"]);
+        }
+
+        let twice = merge(vec![pets(), pets()], &MergeOptions::default()).unwrap();
+        assert_eq!(twice, merge(vec![pets()], &MergeOptions::default()).unwrap());
+    }
+
+    #[test]
+    fn checks_or_prefixes_base_paths() {
+        let admin = || input("admin", r#"{"basePath": "/admin/", "paths": {"/users": {"get": {"responses": {}}}}}"#);
+        assert!(merge(vec![pets(), admin()], &MergeOptions::default()).is_err());
+
+        let options = MergeOptions { prefix_base_paths: true, ..MergeOptions::default() };
+        let result = merge(vec![pets(), admin()], &options).unwrap();
+        assert_eq!(result["basePath"], "/");
+        let paths: Vec<&String> = result["paths"].as_object().unwrap().keys().collect();
+        assert_eq!(paths, vec!["/v1/pets", "/admin/users"]);
+    }
+
+    #[test]
+    fn makes_unique_names() {
+        let taken = ["a", "a2"];
+        assert_eq!(unique_name("a", |name| taken.contains(&name)), "a3");
+        assert_eq!(unique_name("b", |name| taken.contains(&name)), "b");
+    }
+}
@@ -0,0 +1,159 @@
+// This module serves a spec over HTTP before the API exists. Requests are routed
+// by the paths of the spec, checked against their operation and answered with
+// an example of the first 2xx response. Invalid requests get a 400 with the
+// problems found.
+
+use openapi_conformance::{self, Request, RouteError, Router};
+use openapi_examples::{self, Examples};
+use serde_json::{self, Map, Value};
+use tiny_http::{Header, Response, Server};
+
+use errors::*;
+
+pub fn serve(document: &Value, port: u16) -> Result<()> {
+    let address = format!("127.0.0.1:{}", port);
+    let server = Server::http(address.as_str()).map_err(|e| format!("Unable to listen on {}: {}", address, e))?;
+    println!("Serving the mock on http://{}", address);
+
+    let router = Router::new(document);
+
+    for mut incoming in server.incoming_requests() {
+        let mut body = String::new();
+        let (status, text) = match incoming.as_reader().read_to_string(&mut body) {
+            Ok(_) => {
+                let headers = incoming
+                    .headers()
+                    .iter()
+                    .map(|header| (header.field.as_str().as_str().to_string(), header.value.as_str().to_string()))
+                    .collect();
+                answer(document, &router, &incoming.method().to_string(), incoming.url(), headers, &body)
+            }
+            Err(e) => (400, errors(&[format!("Unable to read the body: {}", e)])),
+        };
+        println!("{} {} {}", incoming.method(), incoming.url(), status);
+
+        let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+        let response = Response::from_string(text).with_status_code(status).with_header(content_type);
+        if let Err(e) = incoming.respond(response) {
+            println!("Unable to answer: {}", e);
+        }
+    }
+    Ok(())
+}
+
+// (status, body) of the answer to a request.
+fn answer(
+    document: &Value,
+    router: &Router,
+    method: &str,
+    url: &str,
+    headers: Vec<(String, String)>,
+    body: &str,
+) -> (u16, String) {
+    let mut parts = url.splitn(2, '?');
+    let path = parts.next().unwrap_or("/");
+    let query = parts.next().unwrap_or("");
+
+    let route = match router.route(method, path) {
+        Ok(route) => route,
+        Err(error @ RouteError::UnknownPath) => return (404, errors(&[error.to_string()])),
+        Err(error) => return (405, errors(&[error.to_string()])),
+    };
+
+    // Clients send form content types by default, the operation says what the body is.
+    let is_form = openapi_examples::parameters(document, route.path, route.verb)
+        .iter()
+        .any(|&(_, parameter)| parameter.get("in").and_then(|location| location.as_str()) == Some("formData"));
+    let body = if body.trim().is_empty() {
+        None
+    } else if is_form {
+        let fields: Map<String, Value> = openapi_conformance::parse_query(body)
+            .into_iter()
+            .map(|(name, value)| (name, Value::String(value)))
+            .collect();
+        Some(Value::Object(fields))
+    } else {
+        match serde_json::from_str(body) {
+            Ok(body) => Some(body),
+            Err(e) => return (400, errors(&[format!("The body is not valid JSON: {}", e)])),
+        }
+    };
+
+    let request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: openapi_conformance::parse_query(query),
+        headers: headers,
+        body: body,
+    };
+    let problems = openapi_conformance::check_request(document, &route, &request);
+    if !problems.is_empty() {
+        return (400, errors(&problems));
+    }
+
+    match openapi_examples::success_status(route.operation) {
+        Some(status) => {
+            let example = Examples::new(document).response(route.path, route.verb, status);
+            let text = example.map_or(String::new(), |example| serde_json::to_string_pretty(&example).unwrap_or_default());
+            (status.parse().unwrap_or(200), text)
+        }
+        None => (200, String::new()),
+    }
+}
+
+fn errors(problems: &[String]) -> String {
+    let mut body = Map::new();
+    body.insert("errors".to_string(), Value::Array(problems.iter().cloned().map(Value::String).collect()));
+    serde_json::to_string_pretty(&Value::Object(body)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    fn document() -> Value {
+        json(r##"{
+            "swagger": "2.0",
+            "basePath": "/v1",
+            "paths": {
+                "/pets": {
+                    "get": {"parameters": [{"name": "limit", "in": "query", "type": "integer"}], "responses": {"200": {"description": "ok", "schema": {"type": "array", "items": {"$ref": "#/definitions/Pet"}}}}},
+                    "post": {"parameters": [{"name": "name", "in": "formData", "required": true, "type": "string"}], "responses": {"201": {"description": "made"}}}
+                }
+            },
+            "definitions": {"Pet": {"type": "object", "properties": {"name": {"type": "string", "example": "Rex"}}}}
+        }"##)
+    }
+
+    fn call(method: &str, url: &str, body: &str) -> (u16, Value) {
+        let document = document();
+        let router = Router::new(&document);
+        let (status, text) = answer(&document, &router, method, url, vec![], body);
+        (status, if text.is_empty() { Value::Null } else { json(&text) })
+    }
+
+    #[test]
+    fn answers_with_examples_of_the_first_success() {
+        assert_eq!(call("GET", "/v1/pets?limit=2", ""), (200, json(r#"[{"name": "Rex"}]"#)));
+        assert_eq!(call("POST", "/v1/pets", "name=Rex"), (201, Value::Null));
+    }
+
+    #[test]
+    fn rejects_what_the_spec_does_not_allow() {
+        assert_eq!(call("GET", "/v1/pets?limit=many", ""), (400, json(r#"{"errors": ["query.limit: expected integer but it was the string \"many\""]}"#)));
+        assert_eq!(call("POST", "/v1/pets", ""), (400, json(r#"{"errors": ["The formData parameter name is required"]}"#)));
+        assert_eq!(call("GET", "/v1/cats", ""), (404, json(r#"{"errors": ["No path of the spec matches"]}"#)));
+        assert_eq!(call("PUT", "/v1/pets", ""), (405, json(r#"{"errors": ["The path only has GET, POST"]}"#)));
+    }
+
+    #[test]
+    fn rejects_bodies_which_are_not_json() {
+        let document: Value = json(r#"{"swagger": "2.0", "paths": {"/pets": {"post": {"responses": {}}}}}"#);
+        let router = Router::new(&document);
+        let (status, text) = answer(&document, &router, "POST", "/pets", vec![], "{");
+        assert_eq!(status, 400);
+        assert!(text.contains("The body is not valid JSON"));
+        assert_eq!(answer(&document, &router, "POST", "/pets", vec![], "{}"), (200, String::new()));
+    }
+}
@@ -0,0 +1,194 @@
+// This module applies OpenAPI Overlay documents to a spec.
+// https://github.com/OAI/Overlay-Specification
+// Each action selects nodes with a JSONPath and updates or removes them.
+
+use json_path::{self, JsonPath};
+use serde_json::{self, Map, Value};
+
+use errors::*;
+
+// info and extends are informative only, they are not read.
+#[derive(Debug, Deserialize)]
+pub struct Overlay {
+    pub overlay: String,
+    pub actions: Vec<Action>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Action {
+    pub target: String,
+    pub description: Option<String>,
+    pub update: Option<Value>,
+    #[serde(default)]
+    pub remove: bool,
+}
+
+impl Action {
+    fn describe(&self) -> String {
+        self.description.as_ref().map_or(String::new(), |description| format!(" ({})", description))
+    }
+}
+
+pub fn from_value(value: Value) -> Result<Overlay> {
+    let overlay: Overlay = serde_json::from_value(value).chain_err(|| "The file is not a valid overlay document.")?;
+    if !overlay.overlay.starts_with("1.") {
+        bail!("Overlay version {} is not supported. Only 1.x overlays are.", overlay.overlay);
+    }
+    Ok(overlay)
+}
+
+// Applies the actions in order, each one sees the result of the previous ones.
+// Returns warnings for the actions which did not change anything.
+pub fn apply(document: &mut Value, overlay: &Overlay) -> Result<Vec<String>> {
+    let mut warnings = vec![];
+
+    for action in &overlay.actions {
+        let path = JsonPath::parse(&action.target)?;
+        let locations = path.locate(document);
+        if locations.is_empty() {
+            warnings.push(format!("The target {} does not match anything in the document.{}", action.target, action.describe()));
+            continue;
+        }
+
+        if action.remove {
+            // In reverse so removing array elements does not shift the next ones.
+            for location in locations.iter().rev() {
+                json_path::remove(document, location);
+            }
+        } else if let Some(ref update) = action.update {
+            for location in &locations {
+                if let Some(target) = json_path::get_mut(document, location) {
+                    update_node(target, update);
+                }
+            }
+        } else {
+            warnings.push(format!("The action for {} has neither update nor remove.{}", action.target, action.describe()));
+        }
+    }
+
+    Ok(warnings)
+}
+
+// Objects are merged recursively and arrays receive the update as a new element.
+// Any other node is replaced.
+fn update_node(target: &mut Value, update: &Value) {
+    match (target, update) {
+        (&mut Value::Array(ref mut array), _) => array.push(update.clone()),
+        (&mut Value::Object(ref mut map), &Value::Object(ref update_map)) => merge(map, update_map),
+        (target, _) => *target = update.clone(),
+    }
+}
+
+fn merge(target: &mut Map<String, Value>, update: &Map<String, Value>) {
+    for (key, value) in update {
+        let merged = match (target.get_mut(key), value) {
+            (Some(&mut Value::Object(ref mut target_map)), &Value::Object(ref update_map)) => {
+                merge(target_map, update_map);
+                true
+            }
+            _ => false,
+        };
+        if !merged {
+            target.insert(key.to_string(), value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    fn overlay(actions: &str) -> Overlay {
+        from_value(json(&format!(r#"{{"overlay": "1.0.0", "info": {{"title": "t", "version": "1"}}, "actions": {}}}"#, actions))).unwrap()
+    }
+
+    fn document() -> Value {
+        json(r#"{
+            "info": {"title": "Pets", "contact": {"name": "a"}},
+            "tags": [{"name": "pets"}],
+            "paths": {
+                "/pets": {"get": {"summary": "List", "tags": ["pets"]}, "post": {"summary": "Create", "x-internal": true}},
+                "/admin": {"get": {"summary": "Admin", "x-internal": true}}
+            }
+        }"#)
+    }
+
+    #[test]
+    fn rejects_other_versions_and_documents() {
+        assert!(from_value(json(r#"{"overlay": "2.0.0", "actions": []}"#)).is_err());
+        assert!(from_value(json(r#"{"overlay": "1.0.0"}"#)).is_err());
+        assert!(from_value(json(r#"{"overlay": "1.0.0", "actions": [{"update": {}}]}"#)).is_err());
+    }
+
+    #[test]
+    fn merges_updates_into_objects() {
+        let mut document = document();
+        let warnings = apply(&mut document, &overlay(r#"[{"target": "$.info", "update": {"version": "2", "contact": {"email": "b"}}}]"#)).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(document["info"], json::<Value>(r#"{"title": "Pets", "contact": {"name": "a", "email": "b"}, "version": "2"}"#));
+    }
+
+    #[test]
+    fn appends_to_arrays_and_replaces_scalars() {
+        let mut document = document();
+        let actions = r#"[
+            {"target": "$.tags", "update": {"name": "admin"}},
+            {"target": "$.paths.*.*.summary", "update": "Changed"}
+        ]"#;
+        apply(&mut document, &overlay(actions)).unwrap();
+        assert_eq!(document["tags"], json::<Value>(r#"[{"name": "pets"}, {"name": "admin"}]"#));
+        assert_eq!(document["paths"]["/pets"]["post"]["summary"], "Changed");
+        assert_eq!(document["paths"]["/admin"]["get"]["summary"], "Changed");
+    }
+
+    #[test]
+    fn removes_every_match() {
+        let mut document = document();
+        let actions = r#"[
+            {"target": "$.paths.*[?(@['x-internal'] == true)]", "remove": true},
+            {"target": "$.paths[?(@.get == null)]", "remove": true}
+        ]"#;
+        apply(&mut document, &overlay(actions)).unwrap();
+        assert_eq!(document["paths"], json::<Value>(r#"{"/pets": {"get": {"summary": "List", "tags": ["pets"]}}, "/admin": {}}"#));
+    }
+
+    #[test]
+    fn removes_array_elements_from_the_last() {
+        let mut document = json(r#"{"list": [0, 1, 2, 3]}"#);
+        apply(&mut document, &overlay(r#"[{"target": "$.list[0, 2]", "remove": true}]"#)).unwrap();
+        assert_eq!(document["list"], json::<Value>("[1, 3]"));
+    }
+
+    #[test]
+    fn actions_see_previous_results() {
+        let mut document = document();
+        let actions = r#"[
+            {"target": "$.info", "update": {"x-logo": "logo.png"}},
+            {"target": "$.info['x-logo']", "update": "other.png"}
+        ]"#;
+        apply(&mut document, &overlay(actions)).unwrap();
+        assert_eq!(document["info"]["x-logo"], "other.png");
+    }
+
+    #[test]
+    fn warns_about_actions_without_effect() {
+        let mut document = document();
+        let actions = r#"[
+            {"target": "$.missing", "description": "gone", "update": {}},
+            {"target": "$.info"}
+        ]"#;
+        let warnings = apply(&mut document, &overlay(actions)).unwrap();
+        assert_eq!(warnings, vec![
+            "The target $.missing does not match anything in the document. (gone)".to_string(),
+            "The action for $.info has neither update nor remove.".to_string(),
+        ]);
+        assert_eq!(document, self::document());
+    }
+
+    #[test]
+    fn fails_on_invalid_targets() {
+        let mut document = document();
+        assert!(apply(&mut document, &overlay(r#"[{"target": "info", "remove": true}]"#)).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+// This module applies user supplied values on top of a converted spec.
+// Conversions can not guess some values (host, contact, ...) and write
+// placeholders instead, overrides replace them without hand editing the output.
+
+use openapi_validation::PLACEHOLDERS;
+use serde_json::{Map, Value};
+
+use errors::*;
+use spec;
+
+pub struct Overrides {
+    // Document merged into the result, with JSON Merge Patch semantics.
+    file: Option<Value>,
+    // (dotted path, value) pairs from --set
+    values: Vec<(Vec<String>, String)>,
+}
+
+impl Overrides {
+    pub fn new(file: Option<&str>, sets: &[&str]) -> Result<Overrides> {
+        let file = match file {
+            Some(path) => Some(spec::value_from_path(path)?),
+            None => None,
+        };
+        let values = sets.iter().map(|set| parse_set(set)).collect::<Result<Vec<_>>>()?;
+
+        Ok(Overrides {
+            file: file,
+            values: values,
+        })
+    }
+
+    // Works on the JSON representation of any document (OpenAPI or Google),
+    // callers check the result against the types.
+    pub fn apply(&self, document: &mut Value) {
+        if let Some(ref file) = self.file {
+            merge(document, file);
+        }
+        for &(ref path, ref new_value) in &self.values {
+            set(document, path, new_value);
+        }
+    }
+}
+
+// Dotted paths, as --set takes them, of the placeholders left in a document.
+pub fn placeholders(document: &Value) -> Vec<String> {
+    let mut found = vec![];
+    collect_placeholders(document, &mut vec![], &mut found);
+    found
+}
+
+fn collect_placeholders(value: &Value, path: &mut Vec<String>, found: &mut Vec<String>) {
+    match *value {
+        Value::String(ref text) if PLACEHOLDERS.contains(&text.as_str()) => found.push(path.join(".")),
+        Value::Object(ref map) => {
+            for (key, child) in map {
+                path.push(key.to_string());
+                collect_placeholders(child, path, found);
+                path.pop();
+            }
+        }
+        Value::Array(ref items) => {
+            for (index, child) in items.iter().enumerate() {
+                path.push(index.to_string());
+                collect_placeholders(child, path, found);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+// "info.contact.name=Jordi" -> (["info", "contact", "name"], "Jordi")
+fn parse_set(set: &str) -> Result<(Vec<String>, String)> {
+    let mut pieces = set.splitn(2, '=');
+    let path = pieces.next().unwrap_or("");
+    let value = pieces.next().ok_or_else(|| format!("Invalid --set '{}'. The format is key.path=value.", set))?;
+    if path.is_empty() {
+        bail!("Invalid --set '{}'. The key path is empty.", set);
+    }
+    Ok((path.split('.').map(|key| key.to_string()).collect(), value.to_string()))
+}
+
+// Values from the command line are always strings, that covers every placeholder.
+fn set(document: &mut Value, path: &[String], new_value: &str) {
+    let mut current = document;
+    for key in path {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        current = current.as_object_mut().unwrap().entry(key.to_string()).or_insert(Value::Null);
+    }
+    *current = Value::String(new_value.to_string());
+}
+
+// RFC 7386: objects merge recursively, null deletes a key, anything else replaces.
+fn merge(target: &mut Value, patch: &Value) {
+    match *patch {
+        Value::Object(ref patch_map) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            let target_map = target.as_object_mut().unwrap();
+            for (key, patch_value) in patch_map {
+                if patch_value.is_null() {
+                    target_map.remove(key);
+                } else {
+                    merge(target_map.entry(key.to_string()).or_insert(Value::Null), patch_value);
+                }
+            }
+        }
+        _ => *target = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+
+    #[test]
+    fn parses_sets() {
+        assert_eq!(parse_set("info.contact.name=Jordi").unwrap(), (vec!["info".to_string(), "contact".to_string(), "name".to_string()], "Jordi".to_string()));
+        assert_eq!(parse_set("host=a=b").unwrap().1, "a=b");
+        assert!(parse_set("host").is_err());
+        assert!(parse_set("=value").is_err());
+    }
+
+    #[test]
+    fn sets_values_creating_objects() {
+        let mut document = json(r#"{"info": {"title": "t"}, "host": 3}"#);
+        set(&mut document, &["info".to_string(), "contact".to_string(), "name".to_string()], "Jordi");
+        set(&mut document, &["host".to_string(), "name".to_string()], "x");
+        assert_eq!(document, json::<Value>(r#"{"info": {"title": "t", "contact": {"name": "Jordi"}}, "host": {"name": "x"}}"#));
+    }
+
+    #[test]
+    fn merges_as_json_merge_patch() {
+        let mut document = json(r#"{"a": {"b": 1, "c": 2}, "d": [1, 2], "e": 1}"#);
+        merge(&mut document, &json(r#"{"a": {"b": null, "f": 3}, "d": [3], "e": {"g": 1}}"#));
+        assert_eq!(document, json::<Value>(r#"{"a": {"c": 2, "f": 3}, "d": [3], "e": {"g": 1}}"#));
+    }
+
+    #[test]
+    fn applies_the_file_before_the_sets() {
+        let overrides = Overrides {
+            file: Some(json(r#"{"host": "file.example.com"}"#)),
+            values: vec![(vec!["host".to_string()], "set.example.com".to_string())],
+        };
+        let mut document = json(r#"{"host": "PLEASE.SETHOST.com"}"#);
+        overrides.apply(&mut document);
+        assert_eq!(document["host"], "set.example.com");
+    }
+
+    #[test]
+    fn finds_placeholders_left() {
+        let document = json(r#"{
+            "host": "PLEASE.SETHOST.com",
+            "resources": {"pets": {"methods": {"get": {"response": {"id": "NOTSET", "type": "object"}}}}},
+            "list": ["ok", "<YOUR NAME>"]
+        }"#);
+        assert_eq!(placeholders(&document), vec!["host", "resources.pets.methods.get.response.id", "list.1"]);
+        assert!(placeholders(&json(r#"{"host": "api.example.com"}"#)).is_empty());
+    }
+}
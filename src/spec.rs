@@ -1,19 +1,38 @@
 // This module validates that the OpenAPI file is correct
 
-use openapi;
+use openapi_extensions::Document;
+use serde_json::{self, Value};
+use serde_yaml;
+use std::fs::File;
 //use error::Result;
 use errors::*;
 
-pub fn from_path(path: &str) -> Result<openapi::Spec> {
-    Ok(openapi::from_path(path).chain_err(|| "Unable to deserialize the input file.")?)
+// Vendor extensions are kept apart from the typed spec, see openapi_extensions.
+pub fn from_path(path: &str) -> Result<Document> {
+    from_value(value_from_path(path)?)
 }
 
-pub fn to_json(spec: &openapi::Spec) -> Result<String> {
-    Ok(openapi::to_json(spec).chain_err(|| "Unable to serialize into JSON.")?)
+// Untyped document, for operations which work on any part of the file.
+// YAML is a superset of JSON so this reads both.
+pub fn value_from_path(path: &str) -> Result<Value> {
+    let file = File::open(path).chain_err(|| format!("Unable to open the file {}.", path))?;
+    Ok(serde_yaml::from_reader(file).chain_err(|| format!("Unable to deserialize the file {}.", path))?)
 }
 
-pub fn to_yaml(spec: &openapi::Spec) -> Result<String> {
-    Ok(openapi::to_yaml(spec).chain_err(|| "Unable to serialize into YAML.")?)
+pub fn from_value(value: Value) -> Result<Document> {
+    Ok(Document::from_value(value).chain_err(|| "The result is not a valid OpenAPI file.")?)
+}
+
+pub fn to_value(document: &Document) -> Result<Value> {
+    Ok(document.to_value().chain_err(|| "Unable to serialize the OpenAPI file.")?)
+}
+
+pub fn to_json(document: &Document) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&to_value(document)?).chain_err(|| "Unable to serialize into JSON.")?)
+}
+
+pub fn to_yaml(document: &Document) -> Result<String> {
+    Ok(serde_yaml::to_string(&to_value(document)?).chain_err(|| "Unable to serialize into YAML.")?)
 }
 
 /*
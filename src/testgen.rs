@@ -0,0 +1,363 @@
+// This module runs contract tests made from a spec against a running service.
+// There is one test per operation: the request is made up from the examples of
+// its parameters and body, and the response must have the first 2xx status of
+// the operation and match its schema. Results are reported as JUnit XML.
+
+use openapi_conformance::{self, Request, Response, Route};
+use openapi_examples::{self, Examples};
+use openapi_extensions::VERBS;
+use serde_json::{self, Map, Value};
+use std::time::{Duration, Instant};
+use ureq;
+
+use docs::escape;
+
+pub struct TestCase {
+    pub name: String,
+    // VERB path, where JUnit readers group the tests.
+    pub class_name: String,
+    pub seconds: f64,
+    pub outcome: Outcome,
+}
+
+pub enum Outcome {
+    Passed,
+    // The service answered but not as the spec says.
+    Failed(Vec<String>),
+    // The service could not be reached.
+    Error(String),
+}
+
+impl TestCase {
+    pub fn passed(&self) -> bool {
+        match self.outcome {
+            Outcome::Passed => true,
+            _ => false,
+        }
+    }
+}
+
+pub fn run(document: &Value, base_url: &str) -> Vec<TestCase> {
+    let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build();
+    let mut tests = vec![];
+    let paths = document.get("paths").and_then(|paths| paths.as_object());
+    for (path, item) in paths.into_iter().flat_map(|paths| paths.iter()) {
+        for verb in VERBS.iter().filter(|verb| item.get(**verb).is_some()) {
+            let operation = &item[*verb];
+            let route = Route {
+                path: path,
+                verb: verb,
+                operation: operation,
+                path_parameters: vec![],
+            };
+            let start = Instant::now();
+            let outcome = test(&agent, document, base_url, &route);
+            let elapsed = start.elapsed();
+            tests.push(TestCase {
+                name: operation.get("operationId").and_then(|id| id.as_str()).unwrap_or(path).to_string(),
+                class_name: format!("{} {}", verb.to_uppercase(), path),
+                seconds: elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9,
+                outcome: outcome,
+            });
+        }
+    }
+    tests
+}
+
+fn test(agent: &ureq::Agent, document: &Value, base_url: &str, route: &Route) -> Outcome {
+    let request = make_request(document, route);
+    let url = format!(
+        "{}{}{}",
+        base_url.trim_end_matches('/'),
+        document.get("basePath").and_then(|base_path| base_path.as_str()).unwrap_or("").trim_end_matches('/'),
+        request.path
+    );
+
+    let mut call = agent.request(&route.verb.to_uppercase(), &url);
+    for &(ref name, ref value) in &request.query {
+        call = call.query(name, value);
+    }
+    for &(ref name, ref value) in &request.headers {
+        call = call.set(name, value);
+    }
+    let answer = match request.body {
+        Some(Value::Object(ref fields)) if is_form(document, route) => {
+            let fields: Vec<(&str, &str)> = fields
+                .iter()
+                .flat_map(|(name, value)| match *value {
+                    Value::Array(ref values) => values.iter().map(|value| (name.as_str(), value.as_str().unwrap_or(""))).collect(),
+                    _ => vec![(name.as_str(), value.as_str().unwrap_or(""))],
+                })
+                .collect();
+            call.send_form(&fields)
+        }
+        Some(ref body) => call.send_json(body.clone()),
+        None => call.call(),
+    };
+    let answer = match answer {
+        Ok(answer) | Err(ureq::Error::Status(_, answer)) => answer,
+        Err(e) => return Outcome::Error(e.to_string()),
+    };
+
+    let status = answer.status();
+    let text = answer.into_string().unwrap_or_default();
+    let response = Response {
+        status: status,
+        headers: vec![],
+        body: if text.trim().is_empty() { None } else { Some(serde_json::from_str(&text).unwrap_or(Value::String(text))) },
+    };
+
+    let mut problems = vec![];
+    match openapi_examples::success_status(route.operation) {
+        Some(expected) if expected != status.to_string() => problems.push(format!("Expected the status {} but it was {}", expected, status)),
+        None if status < 200 || status >= 300 => problems.push(format!("Expected a 2xx status but it was {}", status)),
+        _ => {}
+    }
+    problems.extend(openapi_conformance::check_response(document, route, &response));
+    if problems.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(problems)
+    }
+}
+
+// The path with its parameters filled in, required query parameters and
+// headers, and the body, all from examples.
+fn make_request(document: &Value, route: &Route) -> Request {
+    let examples = Examples::new(document);
+    let mut request = Request {
+        method: route.verb.to_uppercase(),
+        path: route.path.to_string(),
+        ..Request::default()
+    };
+    let mut form = Map::new();
+    for (_, parameter) in openapi_examples::parameters(document, route.path, route.verb) {
+        let name = parameter.get("name").and_then(|name| name.as_str()).unwrap_or("");
+        let location = parameter.get("in").and_then(|location| location.as_str()).unwrap_or("");
+        let required = parameter.get("required").and_then(|required| required.as_bool()).unwrap_or(false);
+        let values = texts(parameter, &examples.schema(parameter));
+        match location {
+            "path" => {
+                request.path = request.path.replace(&format!("{{{}}}", name), &encode(&values.join(",")));
+            }
+            "query" if required => request.query.extend(values.into_iter().map(|value| (name.to_string(), value))),
+            "header" if required => request.headers.push((name.to_string(), values.join(","))),
+            // multi form fields are kept as arrays, sent as a field per item.
+            "formData" if required && values.len() == 1 => {
+                form.insert(name.to_string(), Value::String(values.join(",")));
+            }
+            "formData" if required => {
+                form.insert(name.to_string(), Value::Array(values.into_iter().map(Value::String).collect()));
+            }
+            _ => {}
+        }
+    }
+    request.body = examples.request(route.path, route.verb);
+    if request.body.is_none() && !form.is_empty() {
+        request.body = Some(Value::Object(form));
+    }
+    request
+}
+
+fn is_form(document: &Value, route: &Route) -> bool {
+    openapi_examples::parameters(document, route.path, route.verb)
+        .iter()
+        .any(|&(_, parameter)| parameter.get("in").and_then(|location| location.as_str()) == Some("formData"))
+}
+
+// Parameters outside the body go as text, arrays joined as their collectionFormat
+// says, like schema::parameter_value reads them. multi repeats the parameter, a text per item.
+fn texts(parameter: &Value, value: &Value) -> Vec<String> {
+    let items = match *value {
+        Value::Array(ref items) => items,
+        _ => return vec![text(value)],
+    };
+    let separator = match parameter.get("collectionFormat").and_then(|format| format.as_str()) {
+        Some("multi") => return items.iter().map(text).collect(),
+        Some("ssv") => " ",
+        Some("tsv") => "\t",
+        Some("pipes") => "|",
+        _ => ",",
+    };
+    vec![items.iter().map(text).collect::<Vec<String>>().join(separator)]
+}
+
+fn text(value: &Value) -> String {
+    match *value {
+        Value::String(ref text) => text.to_string(),
+        Value::Array(ref items) => items.iter().map(text).collect::<Vec<String>>().join(","),
+        _ => value.to_string(),
+    }
+}
+
+// Percent encoding of a path segment.
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+pub fn junit(suite: &str, tests: &[TestCase]) -> String {
+    let failures = tests.iter().filter(|test| match test.outcome { Outcome::Failed(_) => true, _ => false }).count();
+    let errors = tests.iter().filter(|test| match test.outcome { Outcome::Error(_) => true, _ => false }).count();
+    let seconds: f64 = tests.iter().map(|test| test.seconds).sum();
+
+    let mut xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n".to_string();
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        escape(suite),
+        tests.len(),
+        failures,
+        errors,
+        seconds
+    ));
+    for test in tests {
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            escape(&test.name),
+            escape(&test.class_name),
+            test.seconds
+        ));
+        match test.outcome {
+            Outcome::Passed => xml.push_str("/>\n"),
+            Outcome::Failed(ref problems) => xml.push_str(&format!(
+                ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                escape(&problems[0]),
+                escape(&problems.join("\n"))
+            )),
+            Outcome::Error(ref error) => xml.push_str(&format!(
+                ">\n      <error message=\"{}\"/>\n    </testcase>\n",
+                escape(error)
+            )),
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::json;
+    use std::thread;
+    use tiny_http;
+
+    fn document() -> Value {
+        json(r##"{
+            "swagger": "2.0",
+            "basePath": "/v1/",
+            "paths": {
+                "/pets/{name}": {
+                    "parameters": [{"name": "name", "in": "path", "required": true, "type": "string", "example": "Rex Jr"}],
+                    "put": {
+                        "operationId": "pets.update",
+                        "parameters": [
+                            {"name": "tags", "in": "query", "required": true, "type": "array", "items": {"type": "integer"}},
+                            {"name": "limit", "in": "query", "type": "integer"},
+                            {"name": "X-Trace", "in": "header", "required": true, "type": "string"},
+                            {"name": "body", "in": "body", "schema": {"$ref": "#/definitions/Pet"}}
+                        ],
+                        "responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Pet"}}}
+                    },
+                    "get": {"responses": {"200": {"description": "ok", "schema": {"$ref": "#/definitions/Pet"}}}}
+                },
+                "/pets": {
+                    "post": {"parameters": [{"name": "name", "in": "formData", "required": true, "type": "string"}], "responses": {"201": {"description": "made"}}}
+                }
+            },
+            "definitions": {"Pet": {"type": "object", "required": ["name"], "properties": {"name": {"type": "string"}}}}
+        }"##)
+    }
+
+    fn route<'a>(document: &'a Value, path: &'a str, verb: &'a str) -> Route<'a> {
+        Route {
+            path: path,
+            verb: verb,
+            operation: &document["paths"][path][verb],
+            path_parameters: vec![],
+        }
+    }
+
+    #[test]
+    fn makes_requests_from_examples() {
+        let document = document();
+        let request = make_request(&document, &route(&document, "/pets/{name}", "put"));
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("PUT", "/pets/Rex%20Jr"));
+        assert_eq!(request.query, vec![("tags".to_string(), "1".to_string())]);
+        assert_eq!(request.headers, vec![("X-Trace".to_string(), "string".to_string())]);
+        assert_eq!(request.body, Some(json(r#"{"name": "name"}"#)));
+
+        let form = make_request(&document, &route(&document, "/pets", "post"));
+        assert_eq!(form.body, Some(json(r#"{"name": "string"}"#)));
+        assert!(is_form(&document, &route(&document, "/pets", "post")));
+    }
+
+    #[test]
+    fn joins_arrays_as_their_collection_format() {
+        let value = json(r#"["a", "b"]"#);
+        let texts_for = |format: &str| texts(&json(&format!(r#"{{"collectionFormat": "{}"}}"#, format)), &value);
+        assert_eq!(texts(&json("{}"), &value), vec!["a,b"]);
+        assert_eq!(texts_for("csv"), vec!["a,b"]);
+        assert_eq!(texts_for("ssv"), vec!["a b"]);
+        assert_eq!(texts_for("tsv"), vec!["a\tb"]);
+        assert_eq!(texts_for("pipes"), vec!["a|b"]);
+        assert_eq!(texts_for("multi"), vec!["a", "b"]);
+        assert_eq!(texts(&json("{}"), &json("1")), vec!["1"]);
+    }
+
+    #[test]
+    fn runs_a_test_per_operation() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", server.server_addr());
+        let answers = thread::spawn(move || {
+            let mut urls = vec![];
+            for _ in 0..3 {
+                let request = server.recv().unwrap();
+                urls.push(format!("{} {}", request.method(), request.url()));
+                let body = if request.url().starts_with("/v1/pets/") { "{\"name\": 1}" } else { "" };
+                request.respond(tiny_http::Response::from_string(body).with_status_code(201)).unwrap();
+            }
+            urls
+        });
+
+        let document = document();
+        let tests = run(&document, &format!("{}/", base_url));
+        let mut urls = answers.join().unwrap();
+        urls.sort();
+        assert_eq!(urls, vec!["GET /v1/pets/Rex%20Jr", "POST /v1/pets", "PUT /v1/pets/Rex%20Jr?tags=1"]);
+
+        let names: Vec<&str> = tests.iter().map(|test| test.name.as_str()).collect();
+        assert_eq!(names, vec!["/pets/{name}", "pets.update", "/pets"]);
+        assert_eq!(tests[0].class_name, "GET /pets/{name}");
+        match tests[1].outcome {
+            Outcome::Failed(ref problems) => assert_eq!(problems, &vec![
+                "Expected the status 200 but it was 201".to_string(),
+                "The status 201 is not documented".to_string(),
+            ]),
+            _ => panic!("The test should fail"),
+        }
+        assert!(tests[2].passed());
+    }
+
+    #[test]
+    fn reports_errors_and_failures_as_junit() {
+        let tests = vec![
+            TestCase { name: "a<b".to_string(), class_name: "GET /a".to_string(), seconds: 0.5, outcome: Outcome::Passed },
+            TestCase { name: "b".to_string(), class_name: "GET /b".to_string(), seconds: 0.25, outcome: Outcome::Failed(vec!["one \"1\"".to_string(), "two".to_string()]) },
+            TestCase { name: "c".to_string(), class_name: "GET /c".to_string(), seconds: 0.0, outcome: Outcome::Error("refused".to_string()) },
+        ];
+        assert_eq!(junit("pets & co", &tests), [
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n",
+            "  <testsuite name=\"pets &amp; co\" tests=\"3\" failures=\"1\" errors=\"1\" time=\"0.750\">\n",
+            "    <testcase name=\"a&lt;b\" classname=\"GET /a\" time=\"0.500\"/>\n",
+            "    <testcase name=\"b\" classname=\"GET /b\" time=\"0.250\">\n",
+            "      <failure message=\"one &quot;1&quot;\">one &quot;1&quot;\ntwo</failure>\n    </testcase>\n",
+            "    <testcase name=\"c\" classname=\"GET /c\" time=\"0.000\">\n",
+            "      <error message=\"refused\"/>\n    </testcase>\n",
+            "  </testsuite>\n</testsuites>\n",
+        ].concat());
+    }
+}
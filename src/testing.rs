@@ -0,0 +1,9 @@
+// Helpers shared by the tests of the modules.
+
+use serde::de::DeserializeOwned;
+use serde_json;
+
+// Fixtures are written as JSON: documents, typed specs and expected values.
+pub fn json<T: DeserializeOwned>(text: &str) -> T {
+    serde_json::from_str(text).unwrap()
+}